@@ -0,0 +1,41 @@
+//! 轮询场景的简单计时对比：复用连接的 `BiliClient` vs 每次新建连接的 client。
+//!
+//! 运行：`cargo run -p api_client --example poll_bench --release -- [次数]`
+
+use api_client::BiliClient;
+use std::time::{Duration, Instant};
+
+const POLL_URL: &str = "https://api.live.bilibili.com/room/v1/Area/getList";
+
+async fn run(client: &reqwest::Client, rounds: u32) -> Duration {
+    let mut total = Duration::ZERO;
+    for _ in 0..rounds {
+        let start = Instant::now();
+        match client.get(POLL_URL).send().await {
+            Ok(resp) => {
+                let _ = resp.bytes().await;
+            }
+            Err(e) => println!("请求失败: {}", e),
+        }
+        total += start.elapsed();
+    }
+    total / rounds
+}
+
+#[tokio::main]
+async fn main() {
+    let rounds: u32 = std::env::args().nth(1).and_then(|s| s.parse().ok()).unwrap_or(20);
+
+    let tuned = BiliClient::new();
+    // 禁用连接池，模拟每次请求都重新握手
+    let fresh = reqwest::Client::builder()
+        .pool_max_idle_per_host(0)
+        .build()
+        .expect("reqwest client build failed");
+
+    let reused = run(tuned.client(), rounds).await;
+    let no_reuse = run(&fresh, rounds).await;
+    println!("请求次数: {}", rounds);
+    println!("复用连接平均耗时: {:?}", reused);
+    println!("不复用连接平均耗时: {:?}", no_reuse);
+}
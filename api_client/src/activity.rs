@@ -0,0 +1,68 @@
+//! 直播首页的活动 / 任务列表，只读展示，点击后在浏览器中打开。
+
+use crate::models::{ActivityItem, ActivityListResp, Context};
+use crate::{BiliClient, Result};
+use domain::LiveActivity;
+
+/// 协议相对地址补成 https，其它协议（如 App 内跳转）的链接不展示
+fn activity_url(url: &str) -> Option<String> {
+    let url = url.trim();
+    if let Some(rest) = url.strip_prefix("//") {
+        return Some(format!("https://{}", rest));
+    }
+    (url.starts_with("https://") || url.starts_with("http://")).then(|| url.to_string())
+}
+
+/// 去掉已结束、没有标题或链接不可用的活动，按结束时间先后排序，长期活动放最后
+pub(crate) fn collect_activities(items: Vec<ActivityItem>, now: i64) -> Vec<LiveActivity> {
+    let mut activities: Vec<LiveActivity> = items
+        .into_iter()
+        .filter(|item| !item.title.trim().is_empty() && (item.end_time == 0 || item.end_time > now))
+        .filter_map(|item| {
+            Some(LiveActivity {
+                url: activity_url(&item.jump_url)?,
+                title: item.title.trim().to_string(),
+                start_time: item.start_time,
+                end_time: item.end_time,
+                reward: item.award_desc,
+            })
+        })
+        .collect();
+    activities.sort_by_key(|a| if a.end_time == 0 { i64::MAX } else { a.end_time });
+    activities
+}
+
+impl BiliClient {
+    /// 当前可参与的直播活动
+    pub async fn get_live_activities(&self) -> Result<Vec<LiveActivity>> {
+        let url = "https://api.live.bilibili.com/xlive/activity-interface/v1/anchorCenter/GetActivityList?page=1&page_size=20";
+        let resp: ActivityListResp = self.get_data(url).await.context("获取直播活动失败")?;
+        let now = domain::now_secs();
+        Ok(collect_activities(resp.list, now))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_ended_activities_and_app_links() {
+        let resp: ActivityListResp = serde_json::from_str(
+            r#"{"list": [
+                {"title": "长期任务", "start_time": 0, "end_time": 0, "award_desc": "电池", "jump_url": "//live.bilibili.com/task"},
+                {"title": "已结束", "end_time": 100, "jump_url": "https://live.bilibili.com/a"},
+                {"title": "App 内活动", "end_time": 0, "jump_url": "bilibili://live/activity"},
+                {"title": " 新人扶持 ", "start_time": "150", "end_time": "300", "award_desc": "流量曝光", "jump_url": "https://live.bilibili.com/b"}
+            ]}"#,
+        )
+        .unwrap();
+        let activities = collect_activities(resp.list, 200);
+        let summary: Vec<_> = activities.iter().map(|a| (a.title.as_str(), a.url.as_str(), a.end_time)).collect();
+        assert_eq!(
+            summary,
+            vec![("新人扶持", "https://live.bilibili.com/b", 300), ("长期任务", "https://live.bilibili.com/task", 0)]
+        );
+        assert_eq!(activities[0].reward, "流量曝光");
+    }
+}
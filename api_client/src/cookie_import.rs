@@ -0,0 +1,227 @@
+//! 从其它 B 站工具迁移登录态：自动识别几种常见的 cookie 文件格式，
+//! 统一转换为本工具的 [`AuthData`] 后写入登录信息并重建客户端。
+
+use crate::models::BiliError;
+use crate::{BiliClient, Result};
+use domain::{AuthData, Cookie, LoginState, TokenInfo};
+use serde_json::Value;
+use std::fmt;
+use std::path::Path;
+
+/// 识别失败时提示给用户的支持格式
+pub const SUPPORTED_COOKIE_FORMATS: &str = "支持的格式：\n\
+    - 本工具的 auth.json\n\
+    - bilibili-api 等工具的 JSON（{\"sessdata\": ..., \"bili_jct\": ..., \"ac_time_value\": ...}）\n\
+    - 浏览器扩展导出的 JSON 数组（[{\"name\": ..., \"value\": ..., \"domain\": ...}]）\n\
+    - 浏览器导出的 Netscape cookies.txt\n\
+    - Cookie 请求头字符串（SESSDATA=...; bili_jct=...）";
+
+/// 登录必需的 cookie
+const REQUIRED: [&str; 2] = ["SESSDATA", "bili_jct"];
+const DEFAULT_DOMAIN: &str = ".bilibili.com";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CookieFormat {
+    AuthJson,
+    KeyValueJson,
+    BrowserJson,
+    Netscape,
+    Header,
+}
+
+impl fmt::Display for CookieFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CookieFormat::AuthJson => "auth.json",
+            CookieFormat::KeyValueJson => "键值 JSON",
+            CookieFormat::BrowserJson => "浏览器扩展 JSON",
+            CookieFormat::Netscape => "Netscape cookies.txt",
+            CookieFormat::Header => "Cookie 字符串",
+        })
+    }
+}
+
+/// 第三方工具里的小写字段名 → B 站 cookie 名
+fn canonical_name(name: &str) -> &str {
+    match name {
+        "sessdata" => "SESSDATA",
+        "dedeuserid" => "DedeUserID",
+        "dedeuserid__ckmd5" => "DedeUserID__ckMd5",
+        other => other,
+    }
+}
+
+fn is_bili_domain(domain: &str) -> bool {
+    let domain = domain.trim_start_matches('.');
+    domain == "bilibili.com" || domain.ends_with(".bilibili.com")
+}
+
+fn cookie(name: &str, value: &str, domain: &str, expires: i64) -> Cookie {
+    let domain = if domain.is_empty() { DEFAULT_DOMAIN } else { domain };
+    Cookie { name: canonical_name(name).to_string(), value: value.to_string(), domain: domain.to_string(), expires }
+}
+
+/// 识别格式并解析为登录信息，缺少必需 cookie 时报错
+pub(crate) fn parse_cookie_file(content: &str) -> Result<(AuthData, CookieFormat)> {
+    let content = content.trim_start_matches('\u{feff}').trim();
+    let (auth, format) = match serde_json::from_str::<Value>(content) {
+        Ok(value) => parse_json(value),
+        Err(_) if content.lines().any(|l| l.split('\t').count() == 7) => Some((parse_netscape(content), CookieFormat::Netscape)),
+        Err(_) if content.contains('=') && !content.contains('\n') => Some((parse_header(content), CookieFormat::Header)),
+        Err(_) => None,
+    }
+    .ok_or_else(|| BiliError::InvalidArgument(format!("无法识别 cookie 文件格式。{}", SUPPORTED_COOKIE_FORMATS)))?;
+    if let Some(missing) = REQUIRED.iter().find(|name| !auth.cookies.iter().any(|c| c.name == **name && !c.value.is_empty())) {
+        return Err(BiliError::InvalidArgument(format!("按 {} 解析成功，但缺少登录必需的 {} cookie", format, missing)));
+    }
+    Ok((auth, format))
+}
+
+fn parse_json(value: Value) -> Option<(AuthData, CookieFormat)> {
+    if value.get("cookies").is_some() {
+        return serde_json::from_value(value).ok().map(|auth| (auth, CookieFormat::AuthJson));
+    }
+    if let Some(items) = value.as_array() {
+        let cookies = items
+            .iter()
+            .filter_map(|c| {
+                let domain = c["domain"].as_str().unwrap_or_default();
+                let expires = c["expirationDate"].as_f64().or_else(|| c["expires"].as_f64()).unwrap_or(0.0) as i64;
+                let (name, value) = (c["name"].as_str()?, c["value"].as_str()?);
+                (domain.is_empty() || is_bili_domain(domain)).then(|| cookie(name, value, domain, expires))
+            })
+            .collect();
+        return Some((AuthData { token: TokenInfo::default(), cookies }, CookieFormat::BrowserJson));
+    }
+    let map = value.as_object()?;
+    let mut auth = AuthData::default();
+    for (name, value) in map {
+        let Some(value) = value.as_str().filter(|v| !v.is_empty()) else { continue };
+        match name.as_str() {
+            // bilibili-api 的 ac_time_value 就是 refresh_token
+            "ac_time_value" | "refresh_token" => auth.token.refresh_token = value.to_string(),
+            name => auth.cookies.push(cookie(name, value, "", 0)),
+        }
+    }
+    Some((auth, CookieFormat::KeyValueJson))
+}
+
+/// 每行：域名、子域标记、路径、secure、过期时间、名称、值，以 Tab 分隔。`#HttpOnly_` 前缀不是注释
+fn parse_netscape(content: &str) -> AuthData {
+    let cookies = content
+        .lines()
+        .map(|line| line.trim_start_matches("#HttpOnly_"))
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| match line.split('\t').collect::<Vec<_>>()[..] {
+            [domain, _, _, _, expires, name, value] if is_bili_domain(domain) => {
+                Some(cookie(name, value.trim_end(), domain, expires.parse().unwrap_or(0)))
+            }
+            _ => None,
+        })
+        .collect();
+    AuthData { token: TokenInfo::default(), cookies }
+}
+
+/// `SESSDATA=...; bili_jct=...`，可带 `Cookie:` 前缀
+fn parse_header(content: &str) -> AuthData {
+    let content = content.strip_prefix("Cookie:").unwrap_or(content);
+    let cookies = content
+        .split(';')
+        .filter_map(|part| part.trim().split_once('='))
+        .filter(|(name, value)| !name.is_empty() && !value.is_empty())
+        .map(|(name, value)| cookie(name.trim(), value.trim(), "", 0))
+        .collect();
+    AuthData { token: TokenInfo::default(), cookies }
+}
+
+impl BiliClient {
+    /// 从其它工具导出的 cookie 文件导入登录态，返回识别出的格式
+    pub async fn import_from_file(&mut self, path: &Path) -> Result<CookieFormat> {
+        let content = std::fs::read_to_string(path)?;
+        let (auth, format) = parse_cookie_file(&content)?;
+        self.inject_auth(auth).await?;
+        Ok(format)
+    }
+
+    /// 从 Cookie 请求头字符串导入登录态
+    pub async fn import_cookie_str(&mut self, cookies: &str) -> Result<()> {
+        let (auth, _) = parse_cookie_file(cookies)?;
+        self.inject_auth(auth).await
+    }
+
+    /// 先用导入的 cookie 建临时客户端检查登录态，有效才写入登录信息并替换当前客户端，
+    /// 过期或不属于 B 站的文件不会覆盖原有的登录信息。导入的来源不同但都经过这里
+    async fn inject_auth(&mut self, auth: AuthData) -> Result<()> {
+        if Self::env_login_active() {
+            return Err(BiliError::EnvLogin("不能导入 cookie"));
+        }
+        println!("导入 {} 条 cookie", auth.cookies.len());
+        let client = Self::build(self.options.clone(), Some(auth.clone()))?;
+        if !matches!(client.check_login_state().await?, LoginState::LoggedIn) {
+            return Err(BiliError::ImportedLoginInvalid);
+        }
+        Self::save_auth(&auth)?;
+        *self = client;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(auth: &AuthData) -> Vec<&str> {
+        auth.cookies.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    #[test]
+    fn detects_json_formats() {
+        let (auth, format) = parse_cookie_file(r#"{"sessdata": "s", "bili_jct": "j", "dedeuserid": "42", "buvid3": "", "ac_time_value": "r"}"#).unwrap();
+        assert_eq!(format, CookieFormat::KeyValueJson);
+        let mut got = names(&auth);
+        got.sort_unstable();
+        assert_eq!(got, vec!["DedeUserID", "SESSDATA", "bili_jct"]);
+        assert_eq!(auth.token.refresh_token, "r");
+        assert_eq!(auth.cookies[0].domain, ".bilibili.com");
+
+        let browser = r#"[
+            {"name": "SESSDATA", "value": "s", "domain": ".bilibili.com", "expirationDate": 1767225600.5},
+            {"name": "bili_jct", "value": "j", "domain": ".bilibili.com"},
+            {"name": "other", "value": "x", "domain": ".example.com"}
+        ]"#;
+        let (auth, format) = parse_cookie_file(browser).unwrap();
+        assert_eq!(format, CookieFormat::BrowserJson);
+        assert_eq!(names(&auth), vec!["SESSDATA", "bili_jct"]);
+        assert_eq!(auth.cookies[0].expires, 1767225600);
+
+        let own = r#"{"token": {"access_token": "", "refresh_token": "r", "expires_in": 0}, "cookies": [
+            {"name": "SESSDATA", "value": "s", "domain": ".bilibili.com", "expires": 0},
+            {"name": "bili_jct", "value": "j", "domain": ".bilibili.com", "expires": 0}]}"#;
+        assert_eq!(parse_cookie_file(own).unwrap().1, CookieFormat::AuthJson);
+    }
+
+    #[test]
+    fn detects_netscape_and_header() {
+        let txt = "# Netscape HTTP Cookie File\n\
+            #HttpOnly_.bilibili.com\tTRUE\t/\tTRUE\t1767225600\tSESSDATA\ts%2C1\n\
+            .bilibili.com\tTRUE\t/\tFALSE\t0\tbili_jct\tj\r\n\
+            .example.com\tTRUE\t/\tFALSE\t0\tbili_jct\tother\n";
+        let (auth, format) = parse_cookie_file(txt).unwrap();
+        assert_eq!(format, CookieFormat::Netscape);
+        assert_eq!(names(&auth), vec!["SESSDATA", "bili_jct"]);
+        assert_eq!((auth.cookies[0].value.as_str(), auth.cookies[0].expires), ("s%2C1", 1767225600));
+        assert_eq!(auth.cookies[1].value, "j");
+
+        let (auth, format) = parse_cookie_file("Cookie: SESSDATA=s; bili_jct=j; buvid3=b").unwrap();
+        assert_eq!(format, CookieFormat::Header);
+        assert_eq!(names(&auth), vec!["SESSDATA", "bili_jct", "buvid3"]);
+    }
+
+    #[test]
+    fn reports_unknown_format_and_missing_cookie() {
+        let err = parse_cookie_file("hello world").unwrap_err().to_string();
+        assert!(err.contains("Netscape cookies.txt"), "{}", err);
+        let err = parse_cookie_file("SESSDATA=s").unwrap_err().to_string();
+        assert_eq!(err, "按 Cookie 字符串 解析成功，但缺少登录必需的 bili_jct cookie");
+    }
+}
@@ -0,0 +1,627 @@
+//! 直播间弹幕长连接：getDanmuInfo 取 token 与服务器，经 WebSocket 认证后按 30 秒心跳保活，
+//! 把弹幕、进场、上舰、礼物等消息解析为 [`DanmakuEvent`]。断线后按指数退避重新取 token 重连。
+
+use crate::models::{unwrap_data, BiliError, BiliResp, DanmakuColorResp, DanmakuHistoryResp, DanmuInfoResp, EmoticonResp, RoomUserInfoResp};
+use crate::ws::{self, Message, WsWriter};
+use crate::pipeline::ApiRequest;
+use crate::{cancellable, BiliClient, CancellationToken, Result};
+use domain::{
+    AutoReplyConfig, DanmakuColor, DanmakuConfig, DanmakuEvent, DanmakuStats, Emoticon, HistoryDanmaku, PollingConfig, RoomDanmakuEvent,
+    DANMAKU_FONTSIZE, DEFAULT_DANMAKU_COLOR, DEFAULT_DANMAKU_LENGTH,
+};
+use serde_json::{json, Value};
+use std::io::Read;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::mpsc;
+
+const HEADER_LEN: usize = 16;
+const OP_HEARTBEAT: u32 = 2;
+const OP_MESSAGE: u32 = 5;
+const OP_AUTH: u32 = 7;
+const OP_AUTH_REPLY: u32 = 8;
+/// 正文为 brotli 压缩的若干个包
+const PROTO_BROTLI: u16 = 3;
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+/// 超过这么久没收到任何数据（含心跳回复）就认为连接已死
+const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(70);
+/// 重连退避的起点与上限，第 n 次重连等待 2^(n-1) 秒
+const RECONNECT_BASE: Duration = Duration::from_secs(1);
+const RECONNECT_MAX: Duration = Duration::from_secs(60);
+pub(crate) const DEFAULT_MAX_RECONNECTS: u32 = 10;
+const FALLBACK_HOST: &str = "broadcastlv.chat.bilibili.com";
+
+/// 按 B 站直播协议封包：16 字节头（总长、头长、协议版本、操作码、序号）加正文
+pub(crate) fn encode_packet(op: u32, body: &[u8]) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(HEADER_LEN + body.len());
+    packet.extend_from_slice(&((HEADER_LEN + body.len()) as u32).to_be_bytes());
+    packet.extend_from_slice(&(HEADER_LEN as u16).to_be_bytes());
+    packet.extend_from_slice(&1u16.to_be_bytes());
+    packet.extend_from_slice(&op.to_be_bytes());
+    packet.extend_from_slice(&1u32.to_be_bytes());
+    packet.extend_from_slice(body);
+    packet
+}
+
+/// 拆包，压缩包会被解压并递归拆开，返回 (操作码, 正文) 列表
+pub(crate) fn decode_packets(data: &[u8]) -> Result<Vec<(u32, Vec<u8>)>> {
+    let mut packets = Vec::new();
+    let mut offset = 0;
+    while offset + HEADER_LEN <= data.len() {
+        let header = &data[offset..offset + HEADER_LEN];
+        let total = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let header_len = u16::from_be_bytes([header[4], header[5]]) as usize;
+        let proto = u16::from_be_bytes([header[6], header[7]]);
+        let op = u32::from_be_bytes([header[8], header[9], header[10], header[11]]);
+        if total < header_len || header_len < HEADER_LEN || offset + total > data.len() {
+            bail!("弹幕包长度异常");
+        }
+        let body = &data[offset + header_len..offset + total];
+        if proto == PROTO_BROTLI {
+            let mut plain = Vec::new();
+            brotli_decompressor::Decompressor::new(body, 4096).read_to_end(&mut plain)?;
+            packets.extend(decode_packets(&plain)?);
+        } else {
+            packets.push((op, body.to_vec()));
+        }
+        offset += total;
+    }
+    Ok(packets)
+}
+
+/// 解析一条 `OP_MESSAGE` 正文，不关心的 cmd 返回 None
+pub(crate) fn parse_event(body: &[u8]) -> Option<DanmakuEvent> {
+    let v: Value = serde_json::from_slice(body).ok()?;
+    // 部分 cmd 带版本后缀，如 "DANMU_MSG:4:0:2:2:2:0"
+    let cmd = v["cmd"].as_str()?.split(':').next()?;
+    let data = &v["data"];
+    match cmd {
+        "DANMU_MSG" => {
+            let info = &v["info"];
+            Some(DanmakuEvent::Danmaku {
+                uid: info[2][0].as_u64()?,
+                user: info[2][1].as_str()?.to_string(),
+                text: info[1].as_str()?.to_string(),
+                time: info[0][4].as_i64().unwrap_or(0) / 1000,
+            })
+        }
+        "INTERACT_WORD" if data["msg_type"].as_i64() == Some(1) => Some(DanmakuEvent::Enter {
+            uid: data["uid"].as_u64()?,
+            user: data["uname"].as_str()?.to_string(),
+        }),
+        "GUARD_BUY" => Some(DanmakuEvent::Guard {
+            uid: data["uid"].as_u64()?,
+            user: data["username"].as_str()?.to_string(),
+            level: data["guard_level"].as_u64().unwrap_or(3) as u8,
+        }),
+        "SEND_GIFT" => Some(DanmakuEvent::Gift {
+            uid: data["uid"].as_u64()?,
+            user: data["uname"].as_str()?.to_string(),
+            gift: data["giftName"].as_str().unwrap_or_default().to_string(),
+            count: data["num"].as_u64().unwrap_or(1) as u32,
+        }),
+        _ => None,
+    }
+}
+
+/// 把 gethistory 的返回合并为按时间排序的历史弹幕，房管弹幕与观众弹幕一起展示
+pub(crate) fn history_from_resp(resp: DanmakuHistoryResp) -> Vec<HistoryDanmaku> {
+    let mut list: Vec<HistoryDanmaku> = resp
+        .admin
+        .into_iter()
+        .chain(resp.room)
+        .map(|item| HistoryDanmaku {
+            uid: item.uid,
+            time: item.check_info.map(|c| c.ts).filter(|ts| *ts > 0).unwrap_or_else(|| BiliClient::parse_live_time(&item.timeline)),
+            user: item.nickname,
+            text: item.text,
+        })
+        .collect();
+    list.sort_by_key(|d| d.time);
+    list
+}
+
+/// 展开表情包，跳过缺少 unique 的条目
+pub(crate) fn emoticons_from_resp(resp: EmoticonResp) -> Vec<Emoticon> {
+    resp.data
+        .into_iter()
+        .flat_map(|pkg| {
+            let package = pkg.pkg_name;
+            pkg.emoticons.unwrap_or_default().into_iter().map(move |e| Emoticon {
+                unique: e.emoticon_unique,
+                name: e.emoji,
+                url: e.url,
+                package: package.clone(),
+                usable: e.perm == 1,
+            })
+        })
+        .filter(|e| !e.unique.is_empty())
+        .collect()
+}
+
+/// 合并颜色列表与字数上限，颜色按 0xRRGGBB 去重，解析不了的跳过
+pub(crate) fn danmaku_config_from_resp(colors: DanmakuColorResp, user: RoomUserInfoResp) -> DanmakuConfig {
+    let mut list: Vec<DanmakuColor> = Vec::new();
+    for item in colors.group.into_iter().flat_map(|g| g.color) {
+        let color = item.color.parse().ok().or_else(|| u32::from_str_radix(item.color_hex.trim_start_matches('#'), 16).ok());
+        let Some(color) = color.filter(|c| *c <= 0xFFFFFF) else { continue };
+        if !list.iter().any(|c| c.color == color) {
+            list.push(DanmakuColor { name: item.name, color, usable: item.status == 1 });
+        }
+    }
+    // 没登录或接口未返回时为 0
+    let max_length = match user.property.danmu.length {
+        0 => DEFAULT_DANMAKU_LENGTH,
+        n => n,
+    };
+    DanmakuConfig { colors: list, max_length, fontsize: DANMAKU_FONTSIZE }
+}
+
+/// 发送表情弹幕时的 dm_type
+const DM_TYPE_EMOTICON: &str = "1";
+
+/// 第 `attempt`（从 1 开始）次重连前的等待
+pub(crate) fn reconnect_delay(attempt: u32) -> Duration {
+    RECONNECT_BASE.saturating_mul(1 << attempt.saturating_sub(1).min(16)).min(RECONNECT_MAX)
+}
+
+/// 弹幕事件流，drop 时断开连接
+pub struct DanmakuStream {
+    pub(crate) rx: mpsc::UnboundedReceiver<DanmakuEvent>,
+    pub(crate) token: CancellationToken,
+}
+
+impl DanmakuStream {
+    /// 等待下一条事件，连接断开后返回 None
+    pub async fn next(&mut self) -> Option<DanmakuEvent> {
+        self.rx.recv().await
+    }
+
+    /// 非阻塞地取一条事件，供界面每帧轮询
+    pub fn try_next(&mut self) -> Option<DanmakuEvent> {
+        self.rx.try_recv().ok()
+    }
+}
+
+impl Drop for DanmakuStream {
+    fn drop(&mut self) {
+        self.token.cancel();
+    }
+}
+
+/// 多个直播间汇聚成的事件流，drop 时断开全部连接
+pub struct MultiDanmakuStream {
+    rx: mpsc::UnboundedReceiver<RoomDanmakuEvent>,
+    token: CancellationToken,
+}
+
+impl MultiDanmakuStream {
+    /// 等待下一条事件；各房间会自动重连，只有全部监听任务结束后才返回 None
+    pub async fn next(&mut self) -> Option<RoomDanmakuEvent> {
+        self.rx.recv().await
+    }
+
+    /// 非阻塞地取一条事件，供界面每帧轮询
+    pub fn try_next(&mut self) -> Option<RoomDanmakuEvent> {
+        self.rx.try_recv().ok()
+    }
+}
+
+impl Drop for MultiDanmakuStream {
+    fn drop(&mut self) {
+        self.token.cancel();
+    }
+}
+
+/// 单个房间的监听循环：首次连接失败或重连次数耗尽后等待 [`PollingConfig::danmaku_retry_secs`] 再来，直到被取消或接收端关闭
+async fn watch_room(client: BiliClient, room_id: i64, tx: mpsc::UnboundedSender<RoomDanmakuEvent>, token: CancellationToken) {
+    while !token.is_cancelled() && !tx.is_closed() {
+        let delay = PollingConfig::interval(client.polling().danmaku_retry_secs);
+        match cancellable(&token, client.connect_danmaku(room_id)).await {
+            Ok(mut stream) => loop {
+                let event = tokio::select! {
+                    _ = token.cancelled() => return,
+                    event = stream.next() => event,
+                };
+                let Some(event) = event else {
+                    println!("直播间 {} 弹幕重连失败，{} 秒后重新连接", room_id, delay.as_secs());
+                    break;
+                };
+                if tx.send(RoomDanmakuEvent { room_id, event }).is_err() {
+                    return;
+                }
+            },
+            Err(e) if e.is_cancelled() => return,
+            Err(e) => println!("直播间 {} 弹幕连接失败: {}，{} 秒后重试", room_id, e, delay.as_secs()),
+        }
+        tokio::select! {
+            _ = token.cancelled() => return,
+            _ = tokio::time::sleep(delay) => {}
+        }
+    }
+}
+
+/// 认证完成后的收发循环：读取在独立任务里进行，避免 select 取消读到一半的帧。
+/// 连接断开时返回原因；被取消或接收端已关闭时返回 None
+async fn run_session(mut reader: ws::WsReader, mut writer: WsWriter, tx: &mpsc::UnboundedSender<DanmakuEvent>, token: &CancellationToken) -> Option<String> {
+    let (msg_tx, mut msg_rx) = mpsc::unbounded_channel();
+    let read_task = tokio::spawn(async move {
+        loop {
+            let msg = reader.recv().await;
+            let stop = !matches!(msg, Ok(Message::Binary(_) | Message::Text | Message::Ping(_)));
+            if msg_tx.send(msg).is_err() || stop {
+                break;
+            }
+        }
+    });
+    let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+    let mut last_recv = Instant::now();
+    let reason = loop {
+        tokio::select! {
+            _ = token.cancelled() => break None,
+            _ = heartbeat.tick() => {
+                if last_recv.elapsed() > HEARTBEAT_TIMEOUT {
+                    break Some(format!("{} 秒未收到心跳回复", HEARTBEAT_TIMEOUT.as_secs()));
+                }
+                if let Err(e) = writer.send_binary(&encode_packet(OP_HEARTBEAT, b"[object Object]")).await {
+                    break Some(format!("心跳发送失败: {}", e));
+                }
+            }
+            msg = msg_rx.recv() => {
+                last_recv = Instant::now();
+                match msg {
+                    Some(Ok(Message::Binary(data))) => match decode_packets(&data) {
+                        Ok(packets) => {
+                            for (op, body) in packets {
+                                if op == OP_MESSAGE {
+                                    if let Some(event) = parse_event(&body) {
+                                        if tx.send(event).is_err() {
+                                            token.cancel();
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        Err(e) => println!("弹幕包解析失败: {}", e),
+                    },
+                    Some(Ok(Message::Ping(payload))) => {
+                        let _ = writer.send_pong(&payload).await;
+                    }
+                    Some(Ok(Message::Text)) => {}
+                    Some(Ok(Message::Close)) | None => break Some("连接已被服务端关闭".to_string()),
+                    Some(Err(e)) => break Some(format!("读取失败: {}", e)),
+                }
+            }
+        }
+    };
+    read_task.abort();
+    writer.close().await;
+    reason.filter(|_| !token.is_cancelled() && !tx.is_closed())
+}
+
+/// 连接的守护任务：会话断开后通知订阅方，按指数退避重新取 token 重连，
+/// 连续失败 `max_reconnects` 次后放弃，事件流随之结束
+async fn supervise(client: BiliClient, room_id: i64, session: (ws::WsReader, WsWriter), tx: mpsc::UnboundedSender<DanmakuEvent>, token: CancellationToken) {
+    let max_reconnects = client.options.max_reconnects.unwrap_or(DEFAULT_MAX_RECONNECTS);
+    let (mut reader, mut writer) = session;
+    loop {
+        let Some(reason) = run_session(reader, writer, &tx, &token).await else { return };
+        println!("直播间 {} 弹幕连接断开: {}", room_id, reason);
+        if tx.send(DanmakuEvent::Disconnected { reason }).is_err() {
+            return;
+        }
+        let mut attempt = 0;
+        (reader, writer) = loop {
+            if attempt >= max_reconnects {
+                println!("直播间 {} 弹幕已连续重连 {} 次失败，放弃", room_id, attempt);
+                return;
+            }
+            attempt += 1;
+            let delay = reconnect_delay(attempt);
+            println!("{} 秒后第 {} 次重连直播间 {} 弹幕", delay.as_secs(), attempt, room_id);
+            tokio::select! {
+                _ = token.cancelled() => return,
+                _ = tokio::time::sleep(delay) => {}
+            }
+            match cancellable(&token, client.open_session(room_id)).await {
+                Ok(session) => break session,
+                Err(e) if e.is_cancelled() => return,
+                Err(e) => println!("直播间 {} 弹幕重连失败: {}", room_id, e),
+            }
+        };
+        if tx.send(DanmakuEvent::Reconnected { attempt }).is_err() {
+            return;
+        }
+    }
+}
+
+impl BiliClient {
+    async fn get_danmu_info(&self, room_id: i64) -> Result<DanmuInfoResp> {
+        let req = ApiRequest::get("https://api.live.bilibili.com/xlive/web-room/v1/index/getDanmuInfo")
+            .wbi(vec![("id", room_id.to_string()), ("type", "0".to_string())]);
+        let resp: BiliResp<DanmuInfoResp> = BiliResp::from_value(self.execute(req).await?)?;
+        unwrap_data(resp)
+    }
+
+    /// 连接直播间弹幕，认证成功后返回事件流，短号会先换成真实房间号。
+    /// 之后断线会自动重连，并通过 `Disconnected` / `Reconnected` 事件通知
+    pub async fn connect_danmaku(&self, room_id: i64) -> Result<DanmakuStream> {
+        let room_id = self.resolve_real_room_id(room_id).await?;
+        let session = self.open_session(room_id).await?;
+        let (tx, rx) = mpsc::unbounded_channel();
+        let token = CancellationToken::new();
+        tokio::spawn(supervise(self.clone(), room_id, session, tx, token.clone()));
+        Ok(DanmakuStream { rx, token })
+    }
+
+    /// 取一次 token，依次尝试各弹幕服务器直到认证成功
+    async fn open_session(&self, room_id: i64) -> Result<(ws::WsReader, WsWriter)> {
+        let info = self.get_danmu_info(room_id).await?;
+        let uid: u64 = self.get_cookie_value("DedeUserID").and_then(|v| v.parse().ok()).unwrap_or(0);
+        let auth = json!({
+            "uid": uid,
+            "roomid": room_id,
+            "protover": PROTO_BROTLI,
+            "buvid": self.get_cookie_value("buvid3").unwrap_or_default(),
+            "platform": "web",
+            "type": 2,
+            "key": info.token,
+        });
+        let mut hosts: Vec<(String, u16)> = info.host_list.into_iter().map(|h| (h.host, h.wss_port)).collect();
+        hosts.push((FALLBACK_HOST.to_string(), 443));
+
+        let mut last_err = BiliError::Other("没有可用的弹幕服务器".into());
+        for (host, port) in hosts {
+            match self.open_danmaku(&host, port, &auth).await {
+                Ok(session) => {
+                    println!("已连接弹幕服务器 {}:{}", host, port);
+                    return Ok(session);
+                }
+                Err(e) => {
+                    println!("连接弹幕服务器 {}:{} 失败: {}", host, port, e);
+                    last_err = e;
+                }
+            }
+        }
+        Err(last_err)
+    }
+
+    /// 同时监听多个直播间，事件带房间号汇聚到一个流。单个房间断线只会让该房间重连，不影响其它房间
+    pub async fn connect_multi(&self, room_ids: &[i64]) -> Result<MultiDanmakuStream> {
+        let mut resolved = Vec::with_capacity(room_ids.len());
+        for &room_id in room_ids {
+            resolved.push(self.resolve_real_room_id(room_id).await?);
+        }
+        let room_ids = resolved.as_slice();
+        let (tx, rx) = mpsc::unbounded_channel();
+        let token = CancellationToken::new();
+        let mut seen = std::collections::HashSet::new();
+        for &room_id in room_ids.iter().filter(|id| seen.insert(**id)) {
+            tokio::spawn(watch_room(self.clone(), room_id, tx.clone(), token.clone()));
+        }
+        Ok(MultiDanmakuStream { rx, token })
+    }
+
+    /// 建立连接并发送认证包，等待认证回复
+    async fn open_danmaku(&self, host: &str, port: u16, auth: &Value) -> Result<(ws::WsReader, WsWriter)> {
+        let (mut reader, mut writer) = ws::connect(host, port, "/sub", Self::random_ua(), &self.socket).await?;
+        writer.send_binary(&encode_packet(OP_AUTH, auth.to_string().as_bytes())).await?;
+        let reply = match reader.recv().await? {
+            Message::Binary(data) => decode_packets(&data)?,
+            _ => bail!("弹幕认证未收到回复"),
+        };
+        let ok = reply.iter().any(|(op, body)| {
+            *op == OP_AUTH_REPLY && serde_json::from_slice::<Value>(body).map(|v| v["code"] == 0).unwrap_or(false)
+        });
+        if !ok {
+            bail!("弹幕认证失败");
+        }
+        Ok((reader, writer))
+    }
+
+    /// 不建立长连接，拉取直播间最近的若干条弹幕（接口通常返回最近 10 条），按时间从旧到新排列
+    pub async fn get_recent_danmaku(&self, room_id: i64) -> Result<Vec<HistoryDanmaku>> {
+        let room_id = self.resolve_real_room_id(room_id).await?;
+        let url = format!("https://api.live.bilibili.com/xlive/web-room/v1/dM/gethistory?roomid={}&room_type=0", room_id);
+        Ok(history_from_resp(self.get_data(&url).await?))
+    }
+
+    /// 拉取该直播间当前账号可见的表情列表（官方表情、房间专属表情等）
+    pub async fn get_emoticons(&self, room_id: i64) -> Result<Vec<Emoticon>> {
+        let room_id = self.resolve_real_room_id(room_id).await?;
+        let url = format!("https://api.live.bilibili.com/xlive/web-ucenter/v2/emoticon/GetEmoticons?platform=pc&room_id={}", room_id);
+        Ok(emoticons_from_resp(self.get_data(&url).await?))
+    }
+
+    /// 当前账号在该直播间可用的弹幕颜色与字数上限
+    pub async fn get_danmaku_config(&self, room_id: i64) -> Result<DanmakuConfig> {
+        let room_id = self.resolve_real_room_id(room_id).await?;
+        let colors_url = format!("https://api.live.bilibili.com/xlive/web-room/v1/dM/GetDMConfigByGroup?room_id={}", room_id);
+        let user_url = format!("https://api.live.bilibili.com/xlive/web-room/v1/index/getInfoByUser?room_id={}", room_id);
+        let (colors, user) = tokio::try_join!(self.get_data(&colors_url), self.get_data(&user_url))?;
+        Ok(danmaku_config_from_resp(colors, user))
+    }
+
+    /// 发送一条白色弹幕
+    pub async fn send_danmaku(&self, room_id: i64, msg: &str) -> Result<()> {
+        self.send_msg(room_id, msg, DEFAULT_DANMAKU_COLOR, None).await
+    }
+
+    /// 发送一条彩色弹幕，`color` 为 0xRRGGBB，可用颜色见 [`Self::get_danmaku_config`]
+    pub async fn send_colored_danmaku(&self, room_id: i64, msg: &str, color: u32) -> Result<()> {
+        self.send_msg(room_id, msg, color, None).await
+    }
+
+    /// 发送一条表情弹幕，`emoticon_unique` 取自 [`Self::get_emoticons`]
+    pub async fn send_emoticon(&self, room_id: i64, emoticon_unique: &str) -> Result<()> {
+        self.send_msg(room_id, emoticon_unique, DEFAULT_DANMAKU_COLOR, Some(DM_TYPE_EMOTICON)).await
+    }
+
+    /// 普通文本弹幕不带 dm_type；表情弹幕的 msg 为表情的 unique
+    async fn send_msg(&self, room_id: i64, msg: &str, color: u32, dm_type: Option<&str>) -> Result<()> {
+        let room_id = self.resolve_real_room_id(room_id).await?;
+        let csrf = self.get_cookie_value("bili_jct").ok_or(BiliError::NotLoggedIn("bili_jct"))?;
+        let rnd = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let mut params = std::collections::BTreeMap::new();
+        params.insert("roomid", room_id.to_string());
+        params.insert("msg", msg.to_string());
+        params.insert("color", color.to_string());
+        params.insert("fontsize", DANMAKU_FONTSIZE.to_string());
+        params.insert("mode", "1".to_string());
+        params.insert("bubble", "0".to_string());
+        params.insert("rnd", rnd.to_string());
+        params.insert("csrf", csrf.clone());
+        params.insert("csrf_token", csrf);
+        if let Some(dm_type) = dm_type {
+            params.insert("dm_type", dm_type.to_string());
+        }
+        let resp: BiliResp<Value> = self.post_resp("send_danmaku", "https://api.live.bilibili.com/msg/send", &params).await?;
+        crate::ensure_ok(resp)?;
+        Ok(())
+    }
+
+    /// 监听弹幕并在 `stats` 的基础上累计本场互动计数（断线重启任务时接着算），
+    /// 每收到一条事件把最新累计值交给 `on_update`，直到被取消或连接断开
+    pub async fn collect_danmaku_stats(
+        &self,
+        room_id: i64,
+        mut stats: DanmakuStats,
+        token: &CancellationToken,
+        mut on_update: impl FnMut(DanmakuStats) + Send,
+    ) -> Result<()> {
+        let mut stream = cancellable(token, self.connect_danmaku(room_id)).await?;
+        loop {
+            let event = tokio::select! {
+                _ = token.cancelled() => return Err(BiliError::Cancelled),
+                event = stream.next() => event,
+            };
+            let Some(event) = event else { bail!("弹幕连接已断开") };
+            stats.record(&event);
+            on_update(stats.snapshot());
+        }
+    }
+
+    /// 监听弹幕并按规则自动回复，直到被取消或连接断开。
+    /// 距上次回复不足 `min_interval_secs` 时丢弃本次回复，避免刷屏触发风控
+    pub async fn run_auto_reply(&self, room_id: i64, config: &AutoReplyConfig, token: &CancellationToken) -> Result<()> {
+        let mut stream = cancellable(token, self.connect_danmaku(room_id)).await?;
+        let self_uid: u64 = self.get_cookie_value("DedeUserID").and_then(|v| v.parse().ok()).unwrap_or(0);
+        let min_interval = Duration::from_secs(config.min_interval_secs.max(1));
+        let mut last_sent: Option<Instant> = None;
+        loop {
+            let event = tokio::select! {
+                _ = token.cancelled() => return Err(BiliError::Cancelled),
+                event = stream.next() => event,
+            };
+            let Some(event) = event else { bail!("弹幕连接已断开") };
+            let Some(reply) = config.reply_for(&event, self_uid) else { continue };
+            if last_sent.is_some_and(|t| t.elapsed() < min_interval) {
+                println!("自动回复过于频繁，已跳过: {}", reply);
+                continue;
+            }
+            last_sent = Some(Instant::now());
+            if let Err(e) = self.send_danmaku(room_id, &reply).await {
+                println!("自动回复发送失败: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packet_round_trip() {
+        let packet = encode_packet(OP_AUTH, br#"{"roomid":1}"#);
+        assert_eq!(packet.len(), HEADER_LEN + 12);
+        let packets = decode_packets(&[packet.clone(), encode_packet(OP_MESSAGE, b"{}")].concat()).unwrap();
+        assert_eq!(packets, vec![(OP_AUTH, br#"{"roomid":1}"#.to_vec()), (OP_MESSAGE, b"{}".to_vec())]);
+        assert!(decode_packets(&packet[..HEADER_LEN + 3]).is_err());
+    }
+
+    #[test]
+    fn reconnect_delay_doubles_up_to_cap() {
+        assert_eq!(reconnect_delay(1), Duration::from_secs(1));
+        assert_eq!(reconnect_delay(2), Duration::from_secs(2));
+        assert_eq!(reconnect_delay(4), Duration::from_secs(8));
+        assert_eq!(reconnect_delay(7), RECONNECT_MAX);
+        assert_eq!(reconnect_delay(u32::MAX), RECONNECT_MAX);
+    }
+
+    #[test]
+    fn history_merges_and_sorts_by_time() {
+        let resp: DanmakuHistoryResp = serde_json::from_value(json!({
+            "admin": [{ "uid": 1, "nickname": "房管", "text": "欢迎", "timeline": "2024-01-02 03:04:10" }],
+            "room": [{ "uid": 2, "nickname": "观众", "text": "来了", "timeline": "2024-01-02 03:04:05", "check_info": { "ts": 1704135845 } }]
+        }))
+        .unwrap();
+        let list = history_from_resp(resp);
+        assert_eq!(list.iter().map(|d| d.user.as_str()).collect::<Vec<_>>(), vec!["观众", "房管"]);
+        assert_eq!(list[0].time, 1704135845);
+        assert_eq!(list[1].time, 1704135850);
+    }
+
+    #[test]
+    fn emoticons_flatten_packages() {
+        let resp: EmoticonResp = serde_json::from_value(json!({
+            "data": [
+                { "pkg_name": "通用表情", "emoticons": [
+                    { "emoji": "赞", "url": "https://i0.hdslb.com/a.png", "emoticon_unique": "official_147", "perm": 1 },
+                    { "emoji": "坏", "url": "", "emoticon_unique": "", "perm": 1 }
+                ] },
+                { "pkg_name": "房间专属", "emoticons": [{ "emoji": "打call", "emoticon_unique": "room_1_2", "perm": 0 }] },
+                { "pkg_name": "空包", "emoticons": null }
+            ]
+        }))
+        .unwrap();
+        let list = emoticons_from_resp(resp);
+        assert_eq!(list.len(), 2);
+        assert_eq!((list[0].unique.as_str(), list[0].package.as_str(), list[0].usable), ("official_147", "通用表情", true));
+        assert_eq!((list[1].name.as_str(), list[1].usable), ("打call", false));
+    }
+
+    #[test]
+    fn danmaku_config_merges_colors_and_length() {
+        let colors: DanmakuColorResp = serde_json::from_value(json!({
+            "group": [
+                { "name": "通用", "color": [
+                    { "name": "白色", "color": "16777215", "color_hex": "FFFFFF", "status": 1 },
+                    { "name": "红色", "color": "", "color_hex": "FF6868", "status": 1 }
+                ] },
+                { "name": "舰长", "color": [
+                    { "name": "金色", "color": "14893055", "status": 0 },
+                    { "name": "白色", "color": "16777215", "status": 1 },
+                    { "name": "坏", "color": "abc" }
+                ] }
+            ]
+        }))
+        .unwrap();
+        let user: RoomUserInfoResp = serde_json::from_value(json!({ "property": { "danmu": { "length": 30 } } })).unwrap();
+        let config = danmaku_config_from_resp(colors, user);
+        let colors: Vec<(u32, bool)> = config.colors.iter().map(|c| (c.color, c.usable)).collect();
+        assert_eq!(colors, vec![(0xFFFFFF, true), (0xFF6868, true), (14893055, false)]);
+        assert_eq!((config.max_length, config.fontsize), (30, 25));
+        let config = danmaku_config_from_resp(DanmakuColorResp::default(), RoomUserInfoResp::default());
+        assert_eq!(config.max_length, DEFAULT_DANMAKU_LENGTH);
+    }
+
+    #[test]
+    fn parse_danmaku_and_interactions() {
+        let danmaku = json!({ "cmd": "DANMU_MSG:4:0:2:2:2:0", "info": [[0, 1, 25, 16777215, 1700000000123_i64], "晚上好", [42, "阿B"]] });
+        assert_eq!(
+            parse_event(danmaku.to_string().as_bytes()),
+            Some(DanmakuEvent::Danmaku { uid: 42, user: "阿B".into(), text: "晚上好".into(), time: 1700000000 })
+        );
+        let enter = json!({ "cmd": "INTERACT_WORD", "data": { "msg_type": 1, "uid": 7, "uname": "小明" } });
+        assert_eq!(parse_event(enter.to_string().as_bytes()), Some(DanmakuEvent::Enter { uid: 7, user: "小明".into() }));
+        let follow = json!({ "cmd": "INTERACT_WORD", "data": { "msg_type": 2, "uid": 7, "uname": "小明" } });
+        assert_eq!(parse_event(follow.to_string().as_bytes()), None);
+        let guard = json!({ "cmd": "GUARD_BUY", "data": { "uid": 8, "username": "舰长", "guard_level": 3 } });
+        assert_eq!(parse_event(guard.to_string().as_bytes()), Some(DanmakuEvent::Guard { uid: 8, user: "舰长".into(), level: 3 }));
+        let gift = json!({ "cmd": "SEND_GIFT", "data": { "uid": 9, "uname": "老板", "giftName": "辣条", "num": 10 } });
+        assert_eq!(
+            parse_event(gift.to_string().as_bytes()),
+            Some(DanmakuEvent::Gift { uid: 9, user: "老板".into(), gift: "辣条".into(), count: 10 })
+        );
+    }
+}
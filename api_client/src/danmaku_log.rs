@@ -0,0 +1,111 @@
+//! 弹幕日志：监听时把事件逐行追加为 JSONL，之后可按原时间间隔（可倍速）回放成与实时连接相同的
+//! [`DanmakuStream`]，便于离线调试下游消费者或给 overlay 做演示。
+
+use crate::danmaku::DanmakuStream;
+use crate::models::BiliError;
+use crate::{CancellationToken, Result};
+use domain::{DanmakuEvent, DanmakuLogEntry};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use tokio::sync::mpsc;
+
+/// 按行解析日志，空行跳过，解析失败的行计数后跳过
+pub(crate) fn parse_log(content: &str) -> (Vec<DanmakuLogEntry>, usize) {
+    let mut skipped = 0;
+    let entries = content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .filter_map(|line| serde_json::from_str(line).map_err(|_| skipped += 1).ok())
+        .collect();
+    (entries, skipped)
+}
+
+/// 每条事件推送前的等待：与上一条的时间差除以倍速，第一条立即推送，时间倒退的按 0 处理；倍速极小导致溢出时取最大值
+pub(crate) fn replay_delays(entries: &[DanmakuLogEntry], speed: f64) -> Vec<Duration> {
+    let mut prev = entries.first().map_or(0, |e| e.at);
+    entries
+        .iter()
+        .map(|e| {
+            let gap = (e.at - prev).max(0) as f64 / 1000.0;
+            prev = prev.max(e.at);
+            Duration::try_from_secs_f64(gap / speed).unwrap_or(Duration::MAX)
+        })
+        .collect()
+}
+
+/// 追加写入弹幕日志，每条事件一行
+pub struct DanmakuLogWriter {
+    file: fs::File,
+}
+
+impl DanmakuLogWriter {
+    /// 打开（不存在则创建）日志文件，已有内容保留，新事件追加在后面
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { file })
+    }
+
+    /// 以当前时间记录一条事件
+    pub fn record(&mut self, room_id: i64, event: &DanmakuEvent) -> Result<()> {
+        let at = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map(|d| d.as_millis() as i64).unwrap_or(0);
+        let entry = DanmakuLogEntry { at, room_id, event: event.clone() };
+        writeln!(self.file, "{}", serde_json::to_string(&entry)?)?;
+        Ok(())
+    }
+}
+
+/// 从弹幕日志回放事件，`speed` 为倍速（2.0 即两倍速）。事件放完后流结束，drop 时停止回放
+pub async fn replay_danmaku(path: &Path, speed: f64) -> Result<DanmakuStream> {
+    if !speed.is_finite() || speed <= 0.0 {
+        return Err(BiliError::InvalidArgument(format!("回放倍速必须大于 0，当前为 {}", speed)));
+    }
+    let (entries, skipped) = parse_log(&tokio::fs::read_to_string(path).await?);
+    if skipped > 0 {
+        println!("弹幕日志有 {} 行无法解析，已跳过", skipped);
+    }
+    println!("回放 {} 条弹幕事件（{} 倍速）", entries.len(), speed);
+    let delays = replay_delays(&entries, speed);
+    let (tx, rx) = mpsc::unbounded_channel();
+    let token = CancellationToken::new();
+    let task_token = token.clone();
+    tokio::spawn(async move {
+        for (entry, delay) in entries.into_iter().zip(delays) {
+            tokio::select! {
+                _ = task_token.cancelled() => return,
+                _ = tokio::time::sleep(delay) => {}
+            }
+            if tx.send(entry.event).is_err() {
+                return;
+            }
+        }
+    });
+    Ok(DanmakuStream { rx, token })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_keeps_original_gaps_scaled_by_speed() {
+        let log = r#"{"at": 1000, "room_id": 1, "event": {"Danmaku": {"uid": 1, "user": "a", "text": "hi", "time": 1}}}
+            not json
+
+            {"at": 3000, "event": {"Enter": {"uid": 2, "user": "b"}}}
+            {"at": 2500, "event": {"Reconnected": {"attempt": 1}}}
+            {"at": 4000, "event": {"Gift": {"uid": 2, "user": "b", "gift": "辣条", "count": 3}}}"#;
+        let (entries, skipped) = parse_log(log);
+        assert_eq!((entries.len(), skipped), (4, 1));
+        assert_eq!((entries[1].room_id, &entries[1].event), (0, &DanmakuEvent::Enter { uid: 2, user: "b".into() }));
+        let ms = |speed| replay_delays(&entries, speed).iter().map(|d| d.as_millis()).collect::<Vec<_>>();
+        assert_eq!(ms(1.0), vec![0, 2000, 0, 1000]);
+        assert_eq!(ms(2.0), vec![0, 1000, 0, 500]);
+        assert_eq!(replay_delays(&entries, 1e-300)[1], Duration::MAX);
+    }
+}
@@ -0,0 +1,91 @@
+//! 主播自己的礼物收益：翻页拉取本月礼物流水，汇总为本月与本场的收益概览。
+
+use crate::models::{Context, GiftStreamItem, GiftStreamResp};
+use crate::{BiliClient, Result};
+use chrono::Datelike;
+use domain::IncomeOverview;
+
+/// 每页条数与最多翻页数，礼物很多的主播统计会被截断并标记
+const PAGE_SIZE: u32 = 20;
+const MAX_PAGES: u32 = 50;
+
+/// 本月第一天 00:00（北京时间）的日期与 Unix 秒
+fn month_start(now: i64) -> (String, i64) {
+    let beijing = chrono::FixedOffset::east_opt(8 * 3600).expect("valid offset");
+    let today = chrono::DateTime::from_timestamp(now, 0).unwrap_or_default().with_timezone(&beijing).date_naive();
+    let first = today.with_day(1).unwrap_or(today);
+    let secs = first.and_hms_opt(0, 0, 0).and_then(|t| t.and_local_timezone(beijing).single()).map(|t| t.timestamp()).unwrap_or(0);
+    (first.format("%Y-%m-%d").to_string(), secs)
+}
+
+/// 按时间把流水累加进概览，早于本月的忽略；`session_start` 为开播时间
+pub(crate) fn add_items(overview: &mut IncomeOverview, items: &[GiftStreamItem], month_start: i64, session_start: Option<i64>) {
+    for item in items {
+        let time = BiliClient::parse_live_time(&item.time);
+        if time < month_start {
+            continue;
+        }
+        overview.month_gold += item.gold;
+        overview.month_gifts += item.gift_num;
+        if session_start.is_some_and(|start| start > 0 && time >= start) {
+            overview.session_gold += item.gold;
+            overview.session_gifts += item.gift_num;
+        }
+    }
+}
+
+impl BiliClient {
+    /// 汇总当前账号本月与本场（`session_start` 起）收到的礼物收益。
+    /// 接口只对主播本人开放，未开通直播或权限不足时返回错误
+    pub async fn get_live_income_overview(&self, session_start: Option<i64>) -> Result<IncomeOverview> {
+        let now = domain::now_secs();
+        let (begin_date, begin_secs) = month_start(now);
+        let mut overview = IncomeOverview::default();
+        let mut last_id = String::new();
+        for page in 0..MAX_PAGES {
+            let url = format!(
+                "https://api.live.bilibili.com/xlive/revenue/v1/giftStream/getReceivedGiftStreamNextList?limit={}&coin_type=0&gift_id=&begin_time={}&uname=&last_id={}",
+                PAGE_SIZE, begin_date, last_id
+            );
+            let resp: GiftStreamResp = self.get_data(&url).await.context("获取礼物流水失败，该接口可能需要主播权限或额外授权")?;
+            add_items(&mut overview, &resp.list, begin_secs, session_start);
+            match resp.list.last() {
+                Some(item) if resp.has_more != 0 && !item.id.is_empty() => last_id = item.id.clone(),
+                _ => return Ok(overview),
+            }
+            if page + 1 == MAX_PAGES {
+                overview.truncated = true;
+            }
+        }
+        Ok(overview)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(time: &str, gold: u64, gift_num: u64) -> GiftStreamItem {
+        GiftStreamItem { id: String::new(), time: time.to_string(), gift_num, gold }
+    }
+
+    #[test]
+    fn month_starts_at_beijing_midnight() {
+        // 2024-03-01 00:30 +08:00 仍属于三月
+        let (date, secs) = month_start(1709224200);
+        assert_eq!(date, "2024-03-01");
+        assert_eq!(secs, 1709222400);
+    }
+
+    #[test]
+    fn items_split_into_month_and_session() {
+        let mut overview = IncomeOverview::default();
+        let items = [item("2024-03-05 20:10:00", 1000, 1), item("2024-03-05 19:00:00", 500, 5), item("2024-02-29 23:59:59", 9999, 1)];
+        add_items(&mut overview, &items, 1709222400, Some(BiliClient::parse_live_time("2024-03-05 20:00:00")));
+        assert_eq!(overview.month_gold, 1500);
+        assert_eq!(overview.month_gifts, 6);
+        assert_eq!(overview.session_gold, 1000);
+        assert_eq!(overview.session_gifts, 1);
+        assert_eq!(IncomeOverview::gold_to_battery(overview.month_gold), 15.0);
+    }
+}
@@ -1,23 +1,58 @@
 //! 与 B 站交互的 HTTP 客户端，占位实现。
 
-use anyhow::Result;
-use domain::{LoginState, RoomInfo, TokenInfo, Cookie as CookieInfo, AuthData, AreaParent, AreaChild, AuditInfo, UserInfo, LiveRoomBrief, WebQrInfo};
+use domain::{ApiExchange, Cached, PollingConfig, DanmakuStats, EndpointMetrics, OfflineSnapshot, StatsSnapshot, InteractStatus, ObsTextConfig, StreamLine, StreamLines, StreamProtocol, LiveStart, HostDiag, LiveEvent, LoginState, NetworkDiag, RoomInfo, TokenInfo, Cookie as CookieInfo, FaceAuthInfo, AuthData, ModAction, TitleDraft, AreaParent, AreaChild, AuditInfo, UserInfo, LiveRoomBrief, WebQrInfo, Settings, ProfileBundle, PROFILE_FORMAT_VERSION};
 use reqwest::Client;
-use std::collections::BTreeMap;
-use std::time::SystemTime;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant, SystemTime};
 use directories::ProjectDirs;
-use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
-use std::sync::Arc;
+use std::io::Write;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
 use reqwest::cookie::Jar;
 use rand::{seq::SliceRandom, thread_rng};
-use reqwest::header::USER_AGENT;
+use reqwest::header::{ORIGIN, REFERER, USER_AGENT};
+use reqwest::StatusCode;
 use rsa::{pkcs8::DecodePublicKey, RsaPublicKey, Oaep};
 use sha2::Sha256;
-use hex;
 use regex::Regex;
 use reqwest::cookie::CookieStore;
+use serde::de::{DeserializeOwned, IgnoredAny};
+
+/// 以 [`BiliError::Other`] 提前返回，用法同 anyhow 的 `bail!`
+macro_rules! bail {
+    ($($arg:tt)*) => {
+        return Err($crate::BiliError::Other(format!($($arg)*)))
+    };
+}
+
+mod activity;
+mod cookie_import;
+mod danmaku;
+mod danmaku_log;
+mod income;
+mod members;
+mod models;
+mod pipeline;
+mod proxy;
+mod standby;
+mod status_output;
+mod templates;
+mod wbi;
+mod ws;
+pub use cookie_import::{CookieFormat, SUPPORTED_COOKIE_FORMATS};
+pub use danmaku::{DanmakuStream, MultiDanmakuStream};
+pub use danmaku_log::{replay_danmaku, DanmakuLogWriter};
+pub use proxy::{check_proxy_rules, parse_proxy, ProxyChoice, ProxySource, PROXY_OFF};
+pub use models::{ensure_ok, error_kind, unwrap_data, BiliError, BiliResp, ErrorKind};
+use models::*;
+/// 本 crate 对外返回的结果类型
+pub type Result<T, E = BiliError> = std::result::Result<T, E>;
+
+use pipeline::{ApiRequest, Conditional, Multipart, RateLimiter, Validators};
 
 const USER_AGENTS: &[&str] = &[
     // 常见浏览器 UA
@@ -30,242 +65,1833 @@ const USER_AGENTS: &[&str] = &[
     "Mozilla/5.0 BiliTV/1110500 (Linux; Android 11) bilibili-tv;free",
 ];
 
+/// 粘住的 UA 连续被风控多少次后改回随机选择
+const UA_STICKY_MAX_FAILURES: u32 = 2;
+
+/// 重试前的默认等待，第 n 次重试等待 n 倍
+const DEFAULT_RETRY_BACKOFF: Duration = Duration::from_millis(500);
+/// 服务端 Retry-After 要求的等待上限，超过时按上限等待，避免任务长时间挂起
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+const POOL_MAX_IDLE_PER_HOST: usize = 8;
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+const TCP_KEEPALIVE: Duration = Duration::from_secs(60);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// 切到备用路线成功后继续直接走备用路线的时长，避免主路线挂掉时每个请求都先等一次连接超时
+const FAILOVER_STICKY: Duration = Duration::from_secs(300);
+const WEBHOOK_ATTEMPTS: u32 = 3;
+const WEBHOOK_UA: &str = concat!("BiliLiveTool/", env!("CARGO_PKG_VERSION"));
+/// 人脸认证的最长等待，轮询间隔见 [`PollingConfig::face_auth_secs`]
+pub const FACE_AUTH_TIMEOUT: Duration = Duration::from_secs(300);
+/// 直播间信息写入离线缓存的最短间隔
+const OFFLINE_ROOM_REFRESH: Duration = Duration::from_secs(60);
+/// 网络诊断探测的域名：主站接口、直播接口与登录
+const DIAG_HOSTS: &[&str] = &["api.bilibili.com", "api.live.bilibili.com", "passport.bilibili.com"];
+const DIAG_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 审计日志里不记录的表单字段
+const AUDIT_SKIP_KEYS: &[&str] = &["csrf", "csrf_token"];
+/// 审计日志中单个参数值保留的最大字符数
+const AUDIT_VALUE_MAX_CHARS: usize = 64;
+
+/// 登录二维码允许指向的官方域名（含子域名）
+const QR_TRUSTED_DOMAINS: &[&str] = &["bilibili.com", "bilibili.tv", "biligame.com"];
+
+/// 表单里由客户端自己填写的字段，扩展参数不允许覆盖
+const RESERVED_FORM_KEYS: &[&str] = &["csrf", "csrf_token", "room_id"];
+
 const PUB_KEY_PEM: &str = "-----BEGIN PUBLIC KEY-----\nMIGfMA0GCSqGSIb3DQEBAQUAA4GNADCBiQKBgQDLgd2OAkcGVtoE3ThUREbio0Eg\nUc/prcajMKXvkCKFCWhJYJcLkcM2DKKcSeFpD/j6Boy538YXnR6VhcuUJOhH2x71\nnzPjfdTcqMz7djHum0qSZA0AyCBDABUqCrfNgCiJ00Ra7GmRj+YCK1NJEuewlb40\nJNrRuoEUXpabUzGB8QIDAQAB\n-----END PUBLIC KEY-----";
 
 
+pub use tokio_util::sync::CancellationToken;
+
+/// 进程级的配置目录覆盖，由 [`BiliClient::set_profile`] 设置
+static CONFIG_DIR_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+/// 进程级的账号名，设置后凭据保存在 `accounts/<name>.json`
+static ACCOUNT: OnceLock<String> = OnceLock::new();
+
+/// 二维码内容是否为 B 站官方域名下的 https 链接，防止接口被污染或中间人篡改后引导扫码到钓鱼页
+pub fn is_trusted_qr_url(url: &str) -> bool {
+    let Ok(parsed) = reqwest::Url::parse(url) else { return false };
+    let Some(host) = parsed.host_str() else { return false };
+    parsed.scheme() == "https"
+        && QR_TRUSTED_DOMAINS
+            .iter()
+            .any(|d| host == *d || host.strip_suffix(d).is_some_and(|sub| sub.ends_with('.')))
+}
+
+/// 进程内各接口的调用指标，键为域名加路径
+static METRICS: Mutex<BTreeMap<String, EndpointMetrics>> = Mutex::new(BTreeMap::new());
+
+/// 开发者模式是否开启，关闭时不记录原始请求
+static CAPTURE_EXCHANGES: AtomicBool = AtomicBool::new(false);
+/// 开发者模式下各接口最近一次的请求与响应，键为域名加路径
+static EXCHANGES: Mutex<BTreeMap<String, ApiExchange>> = Mutex::new(BTreeMap::new());
+/// 原始响应保留的最大字节数，超出部分截断
+const EXCHANGE_BODY_MAX: usize = 64 * 1024;
+/// 开发者模式里打码的字段（不区分大小写）：表单、查询参数、响应 JSON 的键，以及 cookie 列表里的 name
+const EXCHANGE_SECRET_KEYS: &[&str] = &["csrf", "csrf_token", "access_key", "access_token", "refresh_token", "SESSDATA", "bili_jct"];
+
+fn is_secret_key(key: &str) -> bool {
+    EXCHANGE_SECRET_KEYS.iter().any(|k| k.eq_ignore_ascii_case(key))
+}
+
+/// 解析 Retry-After：秒数或 HTTP-date，已过去的时间视为 0
+fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let at = SystemTime::UNIX_EPOCH + Duration::from_secs(at.timestamp().max(0) as u64);
+    Some(at.duration_since(now).unwrap_or_default())
+}
+
+/// 第 `attempt` 次重试前的等待：优先服务端的 Retry-After（不超过 [`MAX_RETRY_AFTER`]），否则按退避线性增长
+fn retry_delay(headers: &reqwest::header::HeaderMap, backoff: Duration, attempt: u32) -> Duration {
+    headers
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| parse_retry_after(v, SystemTime::now()))
+        .map(|d| d.min(MAX_RETRY_AFTER))
+        .unwrap_or(backoff * attempt)
+}
+
+/// 错误信息里保留的响应片段长度
+const BODY_SNIPPET_CHARS: usize = 120;
+
+/// 把响应正文解析为 JSON。风控拦截时常返回 HTML 页面，此时报错并带上状态码与正文片段，不能当成空对象
+fn parse_json_body(status: StatusCode, body: &str) -> Result<serde_json::Value> {
+    serde_json::from_str(body).map_err(|_| {
+        let snippet: String = body.trim().chars().take(BODY_SNIPPET_CHARS).map(|c| if c.is_control() { ' ' } else { c }).collect();
+        BiliError::Blocked(format!("响应不是 JSON（HTTP {}），可能被风控拦截: {}", status.as_u16(), snippet))
+    })
+}
+
+/// 按文件头识别封面图片的类型，只接受 JPEG 与 PNG
+fn image_mime(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else {
+        None
+    }
+}
+
+/// 是否被风控拦截：HTTP 412 或 code 为 -412
+fn is_blocked(status: StatusCode, value: &serde_json::Value) -> bool {
+    status.as_u16() == 412 || value["code"].as_i64() == Some(-412)
+}
+
+/// dry-run 时打印的请求描述，csrf 等敏感字段打码
+fn dry_run_line(url: &str, params: &BTreeMap<&str, String>) -> String {
+    let form = params
+        .iter()
+        .map(|(k, v)| if AUDIT_SKIP_KEYS.contains(k) { format!("{}=***", k) } else { format!("{}={}", k, v) })
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!("[dry-run] POST {} {}", url, form)
+}
+
+/// dry-run 时写接口的模拟响应，开播等需要 data 的接口填入占位值
+fn dry_run_value(action: &str) -> serde_json::Value {
+    let data = match action {
+        "start_live" => serde_json::json!({ "rtmp": { "addr": "rtmp://dry-run.invalid/live-bvc/", "code": "dry-run" } }),
+        "create_room" => serde_json::json!({ "roomID": 0 }),
+        _ => serde_json::Value::Null,
+    };
+    serde_json::json!({ "code": 0, "message": "dry-run", "data": data })
+}
+
+/// 开播（`start`）/ 关播前按当前直播状态判断是否还需要发请求。0 未开播、1 直播中、2 轮播；
+/// 状态未知时照常请求
+fn needs_transition(live_status: Option<i32>, start: bool) -> bool {
+    live_status.map(|s| (s == 1) != start).unwrap_or(true)
+}
+
+/// 域名加路径，不含查询参数
+fn endpoint_of(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .map(|u| format!("{}{}", u.host_str().unwrap_or_default(), u.path()))
+        .unwrap_or_else(|_| url.to_string())
+}
+
+/// 记录一次请求。`status`/`value` 为 None 表示请求或解析失败
+fn record_metric(url: &str, elapsed: Duration, status: Option<StatusCode>, value: Option<&serde_json::Value>) {
+    let endpoint = endpoint_of(url);
+    let code = value.and_then(|v| v["code"].as_i64());
+    let blocked = status.is_some_and(|s| s.as_u16() == 412) || code == Some(-412);
+    let failed = blocked || !status.is_some_and(|s| s.is_success()) || code != Some(0);
+    let mut metrics = METRICS.lock().unwrap_or_else(|e| e.into_inner());
+    let m = metrics
+        .entry(endpoint.clone())
+        .or_insert_with(|| EndpointMetrics { endpoint, ..EndpointMetrics::default() });
+    m.calls += 1;
+    m.total_ms += elapsed.as_millis() as u64;
+    m.failures += failed as u64;
+    m.blocked += blocked as u64;
+}
+
+/// 开启或关闭开发者模式的请求记录，关闭时清空已记录的内容
+pub fn set_capture_exchanges(on: bool) {
+    CAPTURE_EXCHANGES.store(on, Ordering::Relaxed);
+    if !on {
+        EXCHANGES.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+}
+
+/// 查询参数中的敏感字段打码
+fn masked_url(url: &str) -> String {
+    let Ok(mut parsed) = reqwest::Url::parse(url) else { return url.to_string() };
+    if !parsed.query_pairs().any(|(k, _)| is_secret_key(&k)) {
+        return url.to_string();
+    }
+    let pairs: Vec<(String, String)> = parsed
+        .query_pairs()
+        .map(|(k, v)| {
+            let v = if is_secret_key(&k) { "***".to_string() } else { v.into_owned() };
+            (k.into_owned(), v)
+        })
+        .collect();
+    parsed.query_pairs_mut().clear().extend_pairs(pairs);
+    parsed.to_string()
+}
+
+/// 响应 JSON 中的敏感字段打码：敏感键的值、`{"name": "SESSDATA", "value": ...}` 形式的 cookie，
+/// 以及带凭据查询参数的地址（扫码登录成功后返回的跨域登录链接）
+fn mask_json(value: &mut serde_json::Value) {
+    use serde_json::Value;
+    match value {
+        Value::Object(map) => {
+            let secret_cookie = map.get("name").and_then(Value::as_str).is_some_and(is_secret_key);
+            for (key, v) in map.iter_mut() {
+                if is_secret_key(key) || (secret_cookie && key == "value") {
+                    *v = Value::String("***".into());
+                } else {
+                    mask_json(v);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(mask_json),
+        Value::String(text) if text.contains('?') => *text = masked_url(text),
+        _ => {}
+    }
+}
+
+/// 开发者模式下记录一次请求，`result` 为 HTTP 状态与正文或连接错误
+fn record_exchange(method: &str, url: &str, form: Option<&BTreeMap<&str, String>>, result: std::result::Result<(StatusCode, &str), &BiliError>) {
+    if !CAPTURE_EXCHANGES.load(Ordering::Relaxed) {
+        return;
+    }
+    let params = form
+        .into_iter()
+        .flatten()
+        .map(|(k, v)| (k.to_string(), if is_secret_key(k) { "***".to_string() } else { v.clone() }))
+        .collect();
+    let (status, response) = match result {
+        Ok((status, body)) => {
+            let pretty = serde_json::from_str::<serde_json::Value>(body).ok().and_then(|mut v| {
+                mask_json(&mut v);
+                serde_json::to_string_pretty(&v).ok()
+            });
+            let mut body = pretty.unwrap_or_else(|| body.to_string());
+            if body.len() > EXCHANGE_BODY_MAX {
+                let cut = (0..=EXCHANGE_BODY_MAX).rev().find(|i| body.is_char_boundary(*i)).unwrap_or(0);
+                body.truncate(cut);
+                body.push_str("\n…（已截断）");
+            }
+            (Some(status.as_u16()), body)
+        }
+        Err(e) => (None, e.to_string()),
+    };
+    let now = domain::now_secs();
+    let exchange = ApiExchange { time: now, method: method.to_string(), url: masked_url(url), params, status, response };
+    EXCHANGES.lock().unwrap_or_else(|e| e.into_inner()).insert(endpoint_of(url), exchange);
+}
+
+/// 开发者模式下记录的请求，最近的在前
+pub fn recent_exchanges() -> Vec<ApiExchange> {
+    let mut list: Vec<_> = EXCHANGES.lock().unwrap_or_else(|e| e.into_inner()).values().cloned().collect();
+    list.sort_by_key(|e| std::cmp::Reverse(e.time));
+    list
+}
+
+/// 路径包含 `fragment` 的接口最近一次的请求，如 `"Room/startLive"`
+pub fn last_exchange(fragment: &str) -> Option<ApiExchange> {
+    let exchanges = EXCHANGES.lock().unwrap_or_else(|e| e.into_inner());
+    exchanges.iter().filter(|(endpoint, _)| endpoint.contains(fragment)).map(|(_, e)| e).max_by_key(|e| e.time).cloned()
+}
+
+/// 当前进程内的接口调用指标，按接口排序
+pub fn metrics_snapshot() -> Vec<EndpointMetrics> {
+    METRICS.lock().unwrap_or_else(|e| e.into_inner()).values().cloned().collect()
+}
+
+/// 本场数据快照的文件名：`<房间号>_<开播时间>.jsonl`，开播时间未知时按当天切分
+fn stats_file_name(room_id: i64, live_start: i64) -> String {
+    let beijing = chrono::FixedOffset::east_opt(8 * 3600).expect("valid offset");
+    let (secs, fmt) = if live_start > 0 {
+        (live_start, "%Y%m%d-%H%M%S")
+    } else {
+        (domain::now_secs(), "%Y%m%d")
+    };
+    let stamp = chrono::DateTime::from_timestamp(secs, 0)
+        .map(|t| t.with_timezone(&beijing).format(fmt).to_string())
+        .unwrap_or_default();
+    format!("{}_{}.jsonl", room_id, stamp)
+}
+
+/// 等待 `fut` 完成，`token` 先被取消时立即返回 [`BiliError::Cancelled`]
+pub async fn cancellable<T>(token: &CancellationToken, fut: impl Future<Output = Result<T>>) -> Result<T> {
+    tokio::select! {
+        _ = token.cancelled() => Err(BiliError::Cancelled),
+        res = fut => res,
+    }
+}
+
+/// 出口连接使用的 IP 协议族
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IpFamily {
+    #[default]
+    Auto,
+    V4Only,
+    V6Only,
+}
+
+/// 各接口域名要求的 Origin，Referer 为其加 `/`。直播接口校验 live.bilibili.com，缺失时可能被拒
+pub const SITE_ORIGINS: &[(&str, &str)] = &[
+    ("api.live.bilibili.com", "https://live.bilibili.com"),
+    ("api.bilibili.com", "https://www.bilibili.com"),
+    ("passport.bilibili.com", "https://www.bilibili.com"),
+    ("www.bilibili.com", "https://www.bilibili.com"),
+];
+
+/// 请求 `url` 时应带的 Origin，非 B 站接口返回 None
+fn site_origin(url: &str) -> Option<&'static str> {
+    let host = reqwest::Url::parse(url).ok()?.host_str()?.to_ascii_lowercase();
+    SITE_ORIGINS.iter().find(|(h, _)| *h == host).map(|(_, origin)| *origin)
+}
+
+/// B 站图片 CDN 的域名后缀，这些域名有防盗链，需带站内 Referer
+const IMAGE_HOST_SUFFIXES: &[&str] = &["hdslb.com", "biliimg.com"];
+
+/// 图片被防盗链拒绝（403）后依次换用的 Referer，None 表示不带 Referer
+const IMAGE_REFERER_FALLBACKS: &[Option<&str>] = &[Some("https://live.bilibili.com/"), None];
+
+fn is_image_host(url: &str) -> bool {
+    let Some(host) = reqwest::Url::parse(url).ok().and_then(|u| u.host_str().map(str::to_ascii_lowercase)) else { return false };
+    IMAGE_HOST_SUFFIXES.iter().any(|suffix| host == *suffix || host.ends_with(&format!(".{}", suffix)))
+}
+
+/// 下载 `url` 图片时应带的 Referer，非 B 站图片域名返回 None
+fn image_referer(url: &str) -> Option<&'static str> {
+    is_image_host(url).then_some("https://www.bilibili.com/")
+}
+
+/// 接口返回的图片地址可能省略协议（`//i0.hdslb.com/...`）或是 http，统一为 https
+fn image_url(url: &str) -> String {
+    if let Some(rest) = url.strip_prefix("//") {
+        return format!("https://{}", rest);
+    }
+    match url.strip_prefix("http://") {
+        Some(rest) if is_image_host(url) => format!("https://{}", rest),
+        _ => url.to_string(),
+    }
+}
+
+/// “成功即粘滞”的 UA 选择：某个 UA 未被风控后，同会话优先继续使用它；连续被风控才换
+#[derive(Debug, Default)]
+struct UaPicker {
+    sticky: Option<&'static str>,
+    failures: u32,
+}
+
+impl UaPicker {
+    fn pick(&self) -> &'static str {
+        self.sticky.unwrap_or_else(BiliClient::random_ua)
+    }
+
+    /// 记录一次请求结果，`blocked` 表示被风控（HTTP 412 或 code -412）
+    fn report(&mut self, ua: &'static str, blocked: bool) {
+        if !blocked {
+            self.sticky = Some(ua);
+            self.failures = 0;
+        } else if self.sticky == Some(ua) {
+            self.failures += 1;
+            if self.failures >= UA_STICKY_MAX_FAILURES {
+                self.sticky = None;
+                self.failures = 0;
+            }
+        }
+    }
+}
+
+/// 上一次登录态检查的结果
+#[derive(Debug, Clone)]
+struct LoginCache {
+    state: LoginState,
+    checked_at: Instant,
+}
+
+impl LoginCache {
+    /// 距检查不超过 `max_age` 时返回缓存的登录态
+    fn fresh(&self, max_age: Duration, now: Instant) -> Option<LoginState> {
+        (now.saturating_duration_since(self.checked_at) <= max_age).then(|| self.state.clone())
+    }
+}
+
+/// 构造客户端时的网络选项，默认全部自动
+#[derive(Debug, Clone, Default)]
+pub struct ClientOptions {
+    /// 绑定的本地出口地址，多网卡时用它选择出口
+    pub local_address: Option<IpAddr>,
+    pub ip_family: IpFamily,
+    /// 覆盖所有请求的 Origin（Referer 为其加 `/`），缺省按 [`SITE_ORIGINS`] 选择
+    pub origin: Option<String>,
+    /// 响应没有 Retry-After 时的重试退避，缺省为 [`DEFAULT_RETRY_BACKOFF`]
+    pub retry_backoff: Option<Duration>,
+    /// 弹幕连接断开后的最大连续重连次数，缺省 10 次
+    pub max_reconnects: Option<u32>,
+    /// 命令行指定的代理，优先于配置文件、环境变量与系统代理
+    pub proxy: Option<String>,
+    /// 不使用任何代理
+    pub no_proxy: bool,
+    /// 写操作只打印将要发送的请求并返回模拟结果，不真正发送
+    pub dry_run: bool,
+    /// 测试时把所有请求改发到本地 mock 服务，如 `http://127.0.0.1:1234`
+    #[cfg(test)]
+    mock_origin: Option<String>,
+}
+
+impl ClientOptions {
+    /// 实际绑定的本地地址。只限定协议族时绑定该族的未指定地址，连接器随之只尝试同族的目标地址
+    fn bind_address(&self) -> Result<Option<IpAddr>> {
+        match (self.local_address, self.ip_family) {
+            (Some(addr), IpFamily::V4Only) if addr.is_ipv6() => Err(BiliError::InvalidArgument(format!("本地地址 {} 是 IPv6，与仅 IPv4 冲突", addr))),
+            (Some(addr), IpFamily::V6Only) if addr.is_ipv4() => Err(BiliError::InvalidArgument(format!("本地地址 {} 是 IPv4，与仅 IPv6 冲突", addr))),
+            (Some(addr), _) => {
+                // reqwest 到连接时才绑定，这里先试绑一次，尽早给出明确错误
+                std::net::UdpSocket::bind((addr, 0)).map_err(|source| BiliError::Bind { addr, source })?;
+                Ok(Some(addr))
+            }
+            (None, IpFamily::Auto) => Ok(None),
+            (None, IpFamily::V4Only) => Ok(Some(Ipv4Addr::UNSPECIFIED.into())),
+            (None, IpFamily::V6Only) => Ok(Some(Ipv6Addr::UNSPECIFIED.into())),
+        }
+    }
+}
+
+/// 内部均为引用计数句柄，clone 后共享同一个连接池与 cookie jar，便于交给后台任务
+#[derive(Clone)]
 pub struct BiliClient {
     client: Client,
+    /// 开启故障切换时的备用路线
+    failover: Option<Failover>,
+    /// 弹幕长连接的出口
+    socket: Arc<ws::Route>,
     jar: Arc<Jar>,
+    options: ClientOptions,
+    ua: Arc<Mutex<UaPicker>>,
+    login_cache: Arc<Mutex<Option<LoginCache>>>,
+    /// 最近一次成功获取的分区列表，用于提交前校验 area_id
+    area_cache: Arc<Mutex<Option<Vec<AreaParent>>>>,
+    /// 请求管道的按域名限速，克隆的客户端共用
+    limiter: Arc<Mutex<RateLimiter>>,
+    /// 已解析过的直播间号，见 [`Self::resolve_real_room_id`]
+    room_ids: Arc<Mutex<RoomIds>>,
+    /// 轮询间隔，构造时取自配置文件，界面修改后经 [`Self::set_polling`] 更新
+    polling: Arc<Mutex<PollingConfig>>,
+}
+
+/// 主路线连接失败时换用的备用路线（代理 ↔ 直连），两者共用 cookie jar
+#[derive(Clone)]
+struct Failover {
+    client: Client,
+    /// 日志用的路线描述
+    primary: String,
+    backup: String,
+    /// 在此之前优先走备用路线，克隆的客户端共用
+    prefer_backup_until: Arc<Mutex<Option<Instant>>>,
+}
+
+impl Failover {
+    fn prefers_backup(&self) -> bool {
+        self.prefer_backup_until.lock().unwrap_or_else(|e| e.into_inner()).is_some_and(|until| Instant::now() < until)
+    }
+
+    fn prefer_backup(&self, on: bool) {
+        *self.prefer_backup_until.lock().unwrap_or_else(|e| e.into_inner()) = on.then(|| Instant::now() + FAILOVER_STICKY);
+    }
+}
+
+/// 分区列表连同响应的 ETag / Last-Modified 一起缓存到磁盘，服务端返回 304 时直接使用
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct AreaListCache {
+    #[serde(flatten)]
+    validators: Validators,
+    areas: Vec<AreaParent>,
+}
+
+const AREA_LIST_CACHE: &str = "areas";
+
+/// 直播间短号 → 真实房间号，真实房间号映射到自身
+#[derive(Debug, Default)]
+struct RoomIds(HashMap<i64, i64>);
+
+impl RoomIds {
+    fn get(&self, room_id: i64) -> Option<i64> {
+        self.0.get(&room_id).copied()
+    }
+
+    /// `short_id` 为 0 表示没有短号
+    fn record(&mut self, real: i64, short_id: i64) {
+        if real <= 0 {
+            return;
+        }
+        self.0.insert(real, real);
+        if short_id > 0 {
+            self.0.insert(short_id, real);
+        }
+    }
+}
+
+impl Default for BiliClient {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl BiliClient {
-    fn auth_file_path() -> Option<PathBuf> {
-        ProjectDirs::from("com", "Bili", "LiveTool").map(|proj| proj.config_dir().join("auth.json"))
+    fn project_dirs() -> Option<ProjectDirs> {
+        ProjectDirs::from("com", "Bili", "LiveTool")
+    }
+
+    /// 指定配置目录与账号，供 CLI 在服务器上用不同凭据跑多个任务。
+    /// 必须在第一次访问配置之前调用，且每个进程只能设置一次。
+    pub fn set_profile(config_dir: Option<PathBuf>, account: Option<String>) -> Result<()> {
+        if let Some(dir) = config_dir {
+            CONFIG_DIR_OVERRIDE.set(dir).map_err(|_| BiliError::AlreadySet("配置目录"))?;
+        }
+        if let Some(name) = account {
+            let valid = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if !valid {
+                return Err(BiliError::InvalidArgument(format!("账号名只能包含字母、数字、- 和 _: {}", name)));
+            }
+            ACCOUNT.set(name).map_err(|_| BiliError::AlreadySet("账号"))?;
+        }
+        Ok(())
+    }
+
+    /// 配置目录。可由 [`Self::set_profile`] 覆盖；平台配置目录不可用时回退到可执行文件同目录（不可写则当前工作目录）下的 `bili_live_tool/`
+    fn config_dir() -> PathBuf {
+        static CONFIG_DIR: OnceLock<PathBuf> = OnceLock::new();
+        if let Some(dir) = CONFIG_DIR_OVERRIDE.get() {
+            return dir.clone();
+        }
+        CONFIG_DIR
+            .get_or_init(|| match Self::project_dirs() {
+                Some(proj) => proj.config_dir().to_path_buf(),
+                None => {
+                    let dir = Self::fallback_dir();
+                    println!("无法获取系统配置目录，回退到: {}", dir.display());
+                    dir
+                }
+            })
+            .clone()
+    }
+
+    /// 缓存目录，指定了配置目录或回退时位于配置目录下的 `cache/`
+    fn cache_dir() -> PathBuf {
+        if CONFIG_DIR_OVERRIDE.get().is_some() {
+            return Self::config_dir().join("cache");
+        }
+        match Self::project_dirs() {
+            Some(proj) => proj.cache_dir().to_path_buf(),
+            None => Self::config_dir().join("cache"),
+        }
+    }
+
+    fn fallback_dir() -> PathBuf {
+        let beside_exe = std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(|dir| dir.join("bili_live_tool")))
+            .filter(|dir| fs::create_dir_all(dir).is_ok());
+        beside_exe.unwrap_or_else(|| PathBuf::from(".").join("bili_live_tool"))
+    }
+
+    fn auth_file_path() -> PathBuf {
+        match ACCOUNT.get() {
+            Some(name) => Self::config_dir().join("accounts").join(format!("{}.json", name)),
+            None => Self::config_dir().join("auth.json"),
+        }
+    }
+
+    /// 从环境变量读取登录态：`BILI_SESSDATA` 与 `BILI_JCT` 必填，`BILI_DEDEUSERID`、`BILI_REFRESH_TOKEN` 可选。
+    /// 用于容器 / CI 等不方便放 auth.json 的环境
+    fn env_auth() -> Option<AuthData> {
+        let var = |name: &str| std::env::var(name).ok().map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        let sessdata = var("BILI_SESSDATA")?;
+        let jct = var("BILI_JCT")?;
+        let cookie = |name: &str, value: String| CookieInfo { name: name.to_string(), value, domain: ".bilibili.com".to_string(), expires: 0 };
+        let mut cookies = vec![cookie("SESSDATA", sessdata), cookie("bili_jct", jct)];
+        if let Some(uid) = var("BILI_DEDEUSERID") {
+            cookies.push(cookie("DedeUserID", uid));
+        }
+        let token = TokenInfo { refresh_token: var("BILI_REFRESH_TOKEN").unwrap_or_default(), ..TokenInfo::default() };
+        Some(AuthData { token, cookies })
+    }
+
+    /// 是否使用环境变量注入的登录态，此时凭据不落盘
+    pub fn env_login_active() -> bool {
+        Self::env_auth().is_some()
+    }
+
+    /// 环境变量中的登录态优先于文件
+    fn load_auth() -> Option<AuthData> {
+        if let Some(auth) = Self::env_auth() {
+            return Some(auth);
+        }
+        let content = fs::read_to_string(Self::auth_file_path()).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn save_auth(auth: &AuthData) -> Result<()> {
+        if Self::env_login_active() {
+            println!("使用环境变量登录态，跳过保存 auth.json");
+            return Ok(());
+        }
+        let path = Self::auth_file_path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let data = serde_json::to_string_pretty(auth)?;
+        fs::write(path, data)?;
+        Ok(())
+    }
+
+    fn title_draft_file_path() -> PathBuf {
+        Self::config_dir().join("title_draft.json")
+    }
+
+    /// 读取本地的标题草稿，没有或解析失败时返回 None
+    pub fn load_title_draft() -> Option<TitleDraft> {
+        let content = fs::read_to_string(Self::title_draft_file_path()).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    pub fn save_title_draft(draft: &TitleDraft) -> Result<()> {
+        let path = Self::title_draft_file_path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(draft)?)?;
+        Ok(())
+    }
+
+    /// 删除标题草稿，文件不存在时什么也不做
+    pub fn clear_title_draft() -> Result<()> {
+        let path = Self::title_draft_file_path();
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// 缓存目录下按名称和账号分文件
+    fn cache_file_path(name: &str) -> PathBuf {
+        Self::cache_dir().join(name).join(format!("{}.json", ACCOUNT.get().map(String::as_str).unwrap_or("default")))
+    }
+
+    /// 离线数据缓存
+    fn offline_file_path() -> PathBuf {
+        Self::cache_file_path("offline")
+    }
+
+    /// 读取某个读接口上次缓存的结果，没有或解析失败时返回 None
+    pub(crate) fn load_cached<T: DeserializeOwned>(name: &str) -> Option<Cached<T>> {
+        let content = fs::read_to_string(Self::cache_file_path(name)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// 记下读接口的最新结果，写入失败只打印日志
+    fn store_cached<T: serde::Serialize>(name: &str, data: T) -> Cached<T> {
+        let saved_at = domain::now_secs();
+        let cached = Cached { saved_at, data };
+        let path = Self::cache_file_path(name);
+        let res = path
+            .parent()
+            .map(fs::create_dir_all)
+            .transpose()
+            .and_then(|_| fs::write(&path, serde_json::to_string(&cached)?));
+        if let Err(e) = res {
+            println!("写入 {} 缓存失败: {}", name, e);
+        }
+        cached
+    }
+
+    /// 读取上次联网时缓存的用户、直播间与分区信息，没有或解析失败时返回 None
+    pub fn load_offline_snapshot() -> Option<OfflineSnapshot> {
+        let content = fs::read_to_string(Self::offline_file_path()).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// 更新离线缓存中的一部分，`update` 返回 false 时不写盘。写入失败只打印日志
+    fn update_offline_snapshot(update: impl FnOnce(&mut OfflineSnapshot, i64) -> bool) {
+        let mut snapshot = Self::load_offline_snapshot().unwrap_or_default();
+        let now = domain::now_secs();
+        if !update(&mut snapshot, now) {
+            return;
+        }
+        snapshot.saved_at = now;
+        let path = Self::offline_file_path();
+        let res = path
+            .parent()
+            .map(fs::create_dir_all)
+            .transpose()
+            .and_then(|_| fs::write(&path, serde_json::to_string(&snapshot)?));
+        if let Err(e) = res {
+            println!("写入离线缓存失败: {}", e);
+        }
+    }
+
+    fn audit_file_path() -> PathBuf {
+        Self::config_dir().join("audit.log")
+    }
+
+    /// 向 audit.log 追加一行：时间、操作、参数摘要（不含 csrf）、结果。写入失败只打印日志，不影响操作本身
+    fn append_audit(action: &str, params: &BTreeMap<&str, String>, outcome: &str) {
+        let summary = params
+            .iter()
+            .filter(|(k, _)| !AUDIT_SKIP_KEYS.contains(*k))
+            .map(|(k, v)| {
+                let v: String = v.chars().take(AUDIT_VALUE_MAX_CHARS).map(|c| if c.is_control() { ' ' } else { c }).collect();
+                format!("{}={}", k, v)
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        let beijing = chrono::FixedOffset::east_opt(8 * 3600).expect("valid offset");
+        let secs = domain::now_secs();
+        let time = chrono::DateTime::from_timestamp(secs, 0)
+            .map(|t| t.with_timezone(&beijing).format("%Y-%m-%d %H:%M:%S%:z").to_string())
+            .unwrap_or_default();
+        let line = format!("{}\t{}\t{}\t{}\n", time, action, summary, outcome.replace(['\n', '\t'], " "));
+        let path = Self::audit_file_path();
+        let res = path
+            .parent()
+            .map(fs::create_dir_all)
+            .transpose()
+            .and_then(|_| fs::OpenOptions::new().create(true).append(true).open(&path))
+            .and_then(|mut f| f.write_all(line.as_bytes()));
+        if let Err(e) = res {
+            println!("写入审计日志失败: {}", e);
+        }
+    }
+
+    fn settings_file_path() -> PathBuf {
+        Self::config_dir().join("settings.toml")
+    }
+
+    /// 本地文件的实际位置（名称, 路径），用于排障时告诉用户去哪里找
+    pub fn local_paths() -> Vec<(&'static str, PathBuf)> {
+        vec![
+            ("配置目录", Self::config_dir()),
+            ("登录信息", Self::auth_file_path()),
+            ("配置文件", Self::settings_file_path()),
+            ("操作日志", Self::audit_file_path()),
+            ("标题草稿", Self::title_draft_file_path()),
+            ("数据快照", Self::config_dir().join("stats")),
+            ("封面存档", Self::covers_dir()),
+            ("缓存目录", Self::cache_dir()),
+        ]
+    }
+
+    /// 读取用户配置，文件不存在或解析失败时返回默认值。
+    /// 解析失败时先把原文件备份为 settings.toml.bak，免得下次保存时被默认值覆盖
+    pub fn load_settings() -> Settings {
+        let path = Self::settings_file_path();
+        let Ok(content) = fs::read_to_string(&path) else {
+            return Settings::default();
+        };
+        match toml::from_str(&content) {
+            Ok(settings) => settings,
+            Err(e) => {
+                let backup = path.with_extension("toml.bak");
+                println!("解析配置文件 {} 失败，使用默认配置: {}", path.display(), e);
+                if let Err(e) = fs::write(&backup, &content) {
+                    println!("备份配置文件到 {} 失败: {}", backup.display(), e);
+                }
+                Settings::default()
+            }
+        }
+    }
+
+    pub fn save_settings(settings: &Settings) -> Result<()> {
+        let path = Self::settings_file_path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, toml::to_string_pretty(settings)?)?;
+        Ok(())
+    }
+
+    /// 把登录信息与用户配置导出为一个 JSON 配置包
+    pub fn export_profile(path: &Path) -> Result<()> {
+        if Self::env_login_active() {
+            return Err(BiliError::EnvLogin("不导出凭据"));
+        }
+        let bundle = ProfileBundle {
+            format_version: PROFILE_FORMAT_VERSION,
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            auth: Self::load_auth().unwrap_or_default(),
+            settings: Self::load_settings(),
+        };
+        fs::write(path, serde_json::to_string_pretty(&bundle)?)?;
+        Ok(())
+    }
+
+    /// 从配置包还原登录信息与用户配置，并用导入的 cookie 重建客户端。
+    /// 与导入 cookie 一样先用临时客户端检查登录态，失效时不改动本地的登录信息与配置
+    pub async fn import_profile(&mut self, path: &Path) -> Result<()> {
+        let content = fs::read_to_string(path)?;
+        let bundle: ProfileBundle = serde_json::from_str(&content)?;
+        if bundle.format_version > PROFILE_FORMAT_VERSION {
+            bail!(
+                "配置包版本 {} 高于当前支持的 {}（导出自 v{}），请升级后再导入",
+                bundle.format_version, PROFILE_FORMAT_VERSION, bundle.app_version
+            );
+        }
+        let client = Self::build(self.options.clone(), Some(bundle.auth.clone()))?;
+        if !matches!(client.check_login_state().await?, LoginState::LoggedIn) {
+            return Err(BiliError::ImportedLoginInvalid);
+        }
+        Self::save_auth(&bundle.auth)?;
+        Self::save_settings(&bundle.settings)?;
+        *self = Self::with_options(self.options.clone())?;
+        Ok(())
+    }
+
+    /// 使用默认网络选项创建客户端实例
+    pub fn new() -> Self {
+        Self::with_options(ClientOptions::default()).expect("reqwest client build failed")
+    }
+
+    /// 按指定网络选项创建客户端实例，本地地址不可用或与协议族冲突时返回错误
+    pub fn with_options(options: ClientOptions) -> Result<Self> {
+        // 启动时加载 cookie，环境变量优先于文件
+        Self::build(options, Self::load_auth())
+    }
+
+    /// 用给定的登录信息创建客户端，不读写 auth.json
+    fn build(options: ClientOptions, auth: Option<AuthData>) -> Result<Self> {
+        let local_address = options.bind_address()?;
+        let jar = Arc::new(Jar::default());
+        if let Some(auth) = auth {
+            if !auth.cookies.is_empty() {
+                println!("加载 {} 条cookie", auth.cookies.len());
+                for c in &auth.cookies {
+                    // 必须带 Domain 属性，否则会成为只属于 ".bilibili.com" 这个主机的 cookie，发往子域名的请求不会携带
+                    let cookie_str = format!("{}={}; Domain={}; Path=/", c.name, c.value, c.domain);
+                    if let Ok(url) = format!("https://{}", c.domain).parse() {
+                       jar.add_cookie_str(&cookie_str, &url);
+                    }
+                }
+            }
+        }
+        let settings = Self::load_settings();
+        let proxy = proxy::choose_proxy(
+            options.no_proxy,
+            options.proxy.as_deref(),
+            settings.proxy.as_deref(),
+            |var| std::env::var(var).ok(),
+            proxy::system_proxy,
+        );
+        println!("网络代理: {}", proxy.describe());
+        // --no-proxy 表示全部直连，规则也不生效；规则里的错误地址同样不能让客户端无法创建
+        let rules = match proxy::check_proxy_rules(&settings.proxy_rules) {
+            _ if options.no_proxy => Vec::new(),
+            Ok(()) => settings.proxy_rules,
+            Err(e) => {
+                println!("代理规则无效，已忽略: {}", e);
+                Vec::new()
+            }
+        };
+        // 显式指定的默认代理（Some(None) 为直连，None 为沿用 reqwest 的自动检测），以及实际生效的默认代理地址（None 为直连）
+        let (explicit, effective) = match (proxy.is_explicit(), proxy.url.as_deref()) {
+            (false, url) => (None, url.map(str::to_string)),
+            (true, None) => (Some(None), None),
+            (true, Some(url)) => match proxy::parse_proxy(url) {
+                Ok(parsed) => (Some(Some(parsed)), Some(url.to_string())),
+                // 配置文件里的错误地址不能让客户端无法创建（GUI 启动即失败），退回自动检测
+                Err(e) if proxy.source == ProxySource::Config => {
+                    println!("配置中的代理地址无效，改为自动检测: {}", e);
+                    (None, None)
+                }
+                Err(e) => return Err(e),
+            },
+        };
+        let primary_route = if rules.is_empty() { proxy::route_label(effective.as_deref()) } else { "按规则分流的代理".to_string() };
+        let primary_proxied = !rules.is_empty() || effective.is_some();
+        // 弹幕长连接不经过 reqwest，按同样的规则与默认代理自行选择出口
+        let mut socket = ws::Route { local_address, rules: rules.clone(), default: effective.clone(), backup: None };
+        let builder = Client::builder();
+        let builder = match explicit {
+            // 有规则时 reqwest 不再自动检测，未命中规则的请求用自动检测到的环境变量代理
+            _ if !rules.is_empty() => builder.proxy(proxy::rules_proxy(rules, effective)),
+            Some(Some(parsed)) => builder.proxy(parsed),
+            Some(None) => builder.no_proxy(),
+            None => builder,
+        };
+        let client = Self::http_client(builder, &jar, local_address)?;
+        // --no-proxy 明确要求直连，不切换到代理
+        let failover = if settings.proxy_failover && !options.no_proxy {
+            let detected = proxy::choose_proxy(false, None, None, |var| std::env::var(var).ok(), proxy::system_proxy).url;
+            socket.backup = proxy::failover_route(primary_proxied, detected);
+            Self::failover(socket.backup.clone(), primary_route, &jar, local_address)?
+        } else {
+            None
+        };
+        Ok(Self {
+            client,
+            failover,
+            socket: Arc::new(socket),
+            jar,
+            options,
+            ua: Arc::default(),
+            login_cache: Arc::default(),
+            area_cache: Arc::default(),
+            limiter: Arc::default(),
+            room_ids: Arc::default(),
+            polling: Arc::new(Mutex::new(settings.polling)),
+        })
+    }
+
+    /// 按备用路线构造连接，没有可切换的路线或备用代理地址无效时返回 None
+    fn failover(route: Option<Option<String>>, primary: String, jar: &Arc<Jar>, local_address: Option<IpAddr>) -> Result<Option<Failover>> {
+        let builder = match route.as_ref() {
+            Some(Some(url)) => match proxy::parse_proxy(url) {
+                Ok(p) => Client::builder().proxy(p),
+                Err(e) => {
+                    println!("备用代理地址无效，不启用故障切换: {}", e);
+                    return Ok(None);
+                }
+            },
+            Some(None) => Client::builder().no_proxy(),
+            None => return Ok(None),
+        };
+        let backup = proxy::route_label(route.flatten().as_deref());
+        println!("故障切换: {}连接失败时改用{}重试一次，之后 {} 秒内优先走{}", primary, backup, FAILOVER_STICKY.as_secs(), backup);
+        let client = Self::http_client(builder, jar, local_address)?;
+        Ok(Some(Failover { client, primary, backup, prefer_backup_until: Arc::default() }))
+    }
+
+    /// 主路线与备用路线共用的连接设置
+    fn http_client(builder: reqwest::ClientBuilder, jar: &Arc<Jar>, local_address: Option<IpAddr>) -> Result<Client> {
+        Ok(builder
+            .cookie_provider(jar.clone())
+            // 轮询类请求频繁访问同几个域名：保留空闲连接并开启 TCP keepalive 以复用连接。
+            // HTTP/2 由 TLS ALPN 自动协商，不使用 prior knowledge，以免代理或仅支持 HTTP/1.1 的域名失败。
+            .pool_max_idle_per_host(POOL_MAX_IDLE_PER_HOST)
+            .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+            .tcp_keepalive(TCP_KEEPALIVE)
+            .http2_adaptive_window(true)
+            .timeout(REQUEST_TIMEOUT)
+            .connect_timeout(CONNECT_TIMEOUT)
+            .local_address(local_address)
+            .build()?)
+    }
+
+    fn random_ua() -> &'static str {
+        USER_AGENTS.choose(&mut thread_rng()).copied().unwrap_or(USER_AGENTS[0])
+    }
+
+    fn pick_ua(&self) -> &'static str {
+        self.ua.lock().unwrap_or_else(|e| e.into_inner()).pick()
+    }
+
+    fn report_ua(&self, ua: &'static str, blocked: bool) {
+        self.ua.lock().unwrap_or_else(|e| e.into_inner()).report(ua, blocked);
+    }
+
+    /// 带上 UA 与该接口所需的 Referer / Origin
+    fn request(&self, method: reqwest::Method, url: &str, ua: &str) -> reqwest::RequestBuilder {
+        self.request_with(&self.client, method, url, ua)
+    }
+
+    /// 用指定的连接（主路线或备用路线）构造请求
+    fn request_with(&self, client: &Client, method: reqwest::Method, url: &str, ua: &str) -> reqwest::RequestBuilder {
+        #[cfg(test)]
+        let url = &match (&self.options.mock_origin, reqwest::Url::parse(url)) {
+            (Some(origin), Ok(u)) => format!("{}{}{}", origin, u.path(), u.query().map(|q| format!("?{}", q)).unwrap_or_default()),
+            _ => url.to_string(),
+        };
+        let builder = client.request(method, url).header(USER_AGENT, ua);
+        match self.options.origin.as_deref().or_else(|| site_origin(url)) {
+            Some(origin) => builder.header(REFERER, format!("{}/", origin.trim_end_matches('/'))).header(ORIGIN, origin),
+            None => builder,
+        }
+    }
+
+    /// 带重试的表单 POST，不写审计日志
+    async fn post_form_retry(&self, url: &str, params: &BTreeMap<&str, String>) -> Result<serde_json::Value> {
+        self.execute(ApiRequest::post_form(url, params)).await
+    }
+
+    /// GET 请求并解析为 [`BiliResp`]
+    async fn get_resp<T: DeserializeOwned>(&self, url: &str) -> Result<BiliResp<T>> {
+        Ok(BiliResp::from_value(self.get_json(url).await?)?)
+    }
+
+    /// GET 请求并取出 data，code 非 0 或缺少 data 时返回错误
+    async fn get_data<T: DeserializeOwned>(&self, url: &str) -> Result<T> {
+        unwrap_data(self.get_resp(url).await?)
+    }
+
+    /// GET 请求返回原始 JSON，用于需要读取非 0 code 响应内容的接口
+    async fn get_json(&self, url: &str) -> Result<serde_json::Value> {
+        self.execute(ApiRequest::get(url)).await
+    }
+
+    /// 写操作的统一入口：带重试的表单 POST，解析为 [`BiliResp`]，并把操作与结果写入审计日志
+    async fn post_resp<T: DeserializeOwned>(&self, action: &str, url: &str, params: &BTreeMap<&str, String>) -> Result<BiliResp<T>> {
+        if self.options.dry_run {
+            println!("{}", dry_run_line(url, params));
+            return Ok(BiliResp::from_value(dry_run_value(action))?);
+        }
+        let res = match self.post_form_retry(url, params).await {
+            Ok(value) => BiliResp::from_value(value).map_err(BiliError::from),
+            Err(e) => Err(e),
+        };
+        let outcome = match &res {
+            Ok(resp) if resp.code == 0 => "ok".to_string(),
+            Ok(resp) => format!("code {}: {}", resp.code, resp.message),
+            Err(e) => format!("error: {}", e),
+        };
+        Self::append_audit(action, params, &outcome);
+        res
+    }
+
+    /// 清除本地数据：删除登录信息与缓存，`keep_config` 为 false 时连同本工具写入配置目录的文件一起删除。
+    /// 配置目录可能是 `--config` 指定的任意目录，只删自己的文件，目录本身保留。
+    /// 完成后客户端回到未登录的初始状态。
+    pub fn clear_local_data(&mut self, keep_config: bool) -> Result<()> {
+        let mut paths = vec![Self::cache_dir(), Self::auth_file_path()];
+        if !keep_config {
+            let config_dir = Self::config_dir();
+            paths.extend([
+                config_dir.join("auth.json"),
+                config_dir.join("accounts"),
+                Self::settings_file_path(),
+                Self::audit_file_path(),
+                Self::title_draft_file_path(),
+                config_dir.join("stats"),
+                Self::covers_dir(),
+            ]);
+        }
+        for path in paths {
+            let res = if path.is_dir() { fs::remove_dir_all(&path) } else { fs::remove_file(&path) };
+            match res {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        // 重新构建 client，丢弃内存中的 cookie
+        *self = Self::with_options(self.options.clone())?;
+        Ok(())
+    }
+
+    pub fn polling(&self) -> PollingConfig {
+        *self.polling.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 更新轮询间隔，克隆的客户端与进行中的轮询随之生效
+    pub fn set_polling(&self, polling: PollingConfig) {
+        *self.polling.lock().unwrap_or_else(|e| e.into_inner()) = polling;
+    }
+
+    /// 按当前网络选项与配置重建客户端（如修改代理后），cookie 从登录信息重新加载
+    pub fn rebuild(&mut self) -> Result<()> {
+        *self = Self::with_options(self.options.clone())?;
+        Ok(())
+    }
+
+    /// 软登出：只删除登录信息并丢弃内存中的 cookie，配置、模板与缓存都保留。
+    /// 与 [`Self::clear_local_data`] 不同，用于登录失效后重新扫码
+    pub fn logout(&mut self) -> Result<()> {
+        let auth_path = Self::auth_file_path();
+        if auth_path.exists() {
+            fs::remove_file(auth_path)?;
+        }
+        *self = Self::with_options(self.options.clone())?;
+        Ok(())
+    }
+
+    /// 检查当前登录状态
+    pub async fn check_login_state(&self) -> Result<LoginState> {
+        let resp: BiliResp<NavResp> = self.get_resp("https://api.bilibili.com/x/web-interface/nav").await?;
+        let state = if resp.code == 0 && resp.data.is_some_and(|d| d.is_login) {
+            LoginState::LoggedIn
+        } else {
+            LoginState::NeedQrCode
+        };
+        *self.login_cache.lock().unwrap_or_else(|e| e.into_inner()) = Some(LoginCache { state: state.clone(), checked_at: Instant::now() });
+        Ok(state)
+    }
+
+    /// 上次检查在 `max_age` 内时直接返回缓存的登录态，否则重新检查。缓存随客户端重建（清除数据、导入配置）一起丢弃
+    pub async fn cached_login_state(&self, max_age: Duration) -> Result<LoginState> {
+        let cached = self.login_cache.lock().unwrap_or_else(|e| e.into_inner()).as_ref().and_then(|c| c.fresh(max_age, Instant::now()));
+        match cached {
+            Some(state) => Ok(state),
+            None => self.check_login_state().await,
+        }
+    }
+
+    /// 获取登录二维码 (Web)
+    pub async fn fetch_qr_code(&self) -> Result<WebQrInfo> {
+        let resp: BiliResp<QrGenerateResp> = self
+            .get_resp("https://passport.bilibili.com/x/passport-login/web/qrcode/generate")
+            .await?;
+        let data = unwrap_data(resp)?;
+        if !is_trusted_qr_url(&data.url) {
+            println!("拒绝非官方域名的登录二维码: {}", data.url);
+            bail!("二维码地址不属于 B 站官方域名，已拒绝显示，请检查网络环境");
+        }
+        Ok(WebQrInfo { url: data.url, qrcode_key: data.qrcode_key })
+    }
+
+    /// 轮询二维码是否扫描完成 (Web)
+    pub async fn poll_qr_login(&self, qr_info: &WebQrInfo) -> Result<LoginState> {
+        let poll_url = format!("https://passport.bilibili.com/x/passport-login/web/qrcode/poll?qrcode_key={}", qr_info.qrcode_key);
+        let resp: BiliResp<QrPollResp> = self.get_resp(&poll_url).await?;
+        let data = unwrap_data(resp)?;
+        let code = data.code;
+        println!("Web登录轮询响应码: {}", code);
+        match code {
+            0 => { // 扫码成功
+                println!("Web登录成功，保存Cookie...");
+                // 登录成功后，B站不会在poll接口返回Set-Cookie，而是由客户端再次请求返回的url来设置。
+                // reqwest的cookie_provider会自动处理这个过程，我们只需要确保后续的jar是同一个即可。
+                self.on_login_success(data.refresh_token).await?;
+                println!("Cookie保存完毕");
+                Ok(LoginState::LoggedIn)
+            }
+            86038 => { // 二维码已失效
+                println!("二维码已失效");
+                Ok(LoginState::NeedQrCode)
+            }
+            86090 => { // 二维码已扫，待确认
+                println!("二维码已扫，待确认");
+                Ok(LoginState::NeedQrCode)
+            }
+            _ => { // 其他状态，视为未登录
+                Ok(LoginState::NeedQrCode)
+            }
+        }
+    }
+
+    /// 扫码登录成功后的初始化：补齐 buvid 指纹、确认登录态，并把 cookie 与 refresh_token 一起落盘
+    async fn on_login_success(&self, refresh_token: String) -> Result<()> {
+        if let Err(e) = self.fetch_fingerprint().await {
+            println!("获取 fingerprint 失败: {}", e);
+        }
+        match self.check_login_state().await {
+            Ok(LoginState::LoggedIn) => {}
+            Ok(LoginState::NeedQrCode) => println!("警告：登录后确认登录态失败"),
+            Err(e) => println!("警告：登录后确认登录态出错: {}", e),
+        }
+        let auth_data = AuthData {
+            token: TokenInfo { refresh_token, ..TokenInfo::default() },
+            cookies: self.current_cookies(),
+        };
+        Self::save_auth(&auth_data)
+    }
+
+    /// 获取 buvid3 / buvid4 指纹 cookie，部分接口的风控依赖它们
+    async fn fetch_fingerprint(&self) -> Result<()> {
+        let resp: BiliResp<FingerSpiResp> = self.get_resp("https://api.bilibili.com/x/frontend/finger/spi").await?;
+        let data = unwrap_data(resp)?;
+        let url = "https://www.bilibili.com".parse().map_err(BiliError::other)?;
+        for (name, value) in [("buvid3", &data.b_3), ("buvid4", &data.b_4)] {
+            if !value.is_empty() {
+                self.jar.add_cookie_str(&format!("{}={}; Domain=.bilibili.com; Path=/", name, value), &url);
+            }
+        }
+        Ok(())
+    }
+
+    /// 每隔 `interval` 轮询一次扫码结果，直到登录成功；`token` 取消时返回 [`BiliError::Cancelled`]。
+    /// 单次轮询的网络错误会被忽略并继续轮询。
+    pub async fn wait_qr_login(&self, qr_info: &WebQrInfo, interval: Duration, token: &CancellationToken) -> Result<LoginState> {
+        loop {
+            match cancellable(token, self.poll_qr_login(qr_info)).await {
+                Ok(LoginState::LoggedIn) => return Ok(LoginState::LoggedIn),
+                Err(e) if e.is_cancelled() => return Err(e),
+                Err(e) => println!("扫码轮询失败: {}", e),
+                Ok(LoginState::NeedQrCode) => {}
+            }
+            cancellable(token, async {
+                tokio::time::sleep(interval).await;
+                Ok(())
+            })
+            .await?;
+        }
+    }
+
+    /// 获取直播间信息（含短号）
+    pub async fn get_room_info(&self, room_id: i64) -> Result<RoomInfo> {
+        let url = format!("https://api.live.bilibili.com/room/v1/Room/get_info?room_id={}", room_id);
+        let resp: BiliResp<RoomInfoResp> = self.get_resp(&url).await?;
+        let data = unwrap_data(resp)?;
+        let room = RoomInfo {
+            room_id: data.room_id,
+            short_id: data.short_id,
+            live_start: Self::parse_live_time(&data.live_time),
+            title: data.title,
+            cover_url: data.user_cover,
+            area_id: data.area_id,
+            area_name: data.area_name,
+            description: data.description,
+            live_status: data.live_status,
+            online: data.online,
+            interact: InteractStatus::from_pk_fields(data.pk_status, data.battle_id),
+        };
+        self.room_ids.lock().unwrap_or_else(|e| e.into_inner()).record(room.room_id as i64, room.short_id as i64);
+        // 只缓存自己的直播间；文本源等会频繁轮询，间隔 OFFLINE_ROOM_REFRESH 才写一次盘
+        let cached = room.clone();
+        Self::update_offline_snapshot(move |s, now| {
+            let own = s.user.as_ref().is_some_and(|u| u.live_room.room_id == cached.room_id as i64);
+            let due = s.room.is_none() || now - s.saved_at >= OFFLINE_ROOM_REFRESH.as_secs() as i64;
+            if own && due {
+                s.room = Some(cached);
+            }
+            own && due
+        });
+        Ok(room)
+    }
+
+    /// 直播间当前的 PK 状态
+    pub async fn get_interact_status(&self, room_id: i64) -> Result<InteractStatus> {
+        Self::ensure_room(room_id)?;
+        Ok(self.get_room_info(room_id).await?.interact)
+    }
+
+    /// 按 `config.interval_secs` 定时拉取直播间信息，渲染模板后覆盖写入 `config.path`，直到被取消。
+    /// 单次拉取或写入失败只打印日志，下一轮继续。
+    pub async fn run_obs_text(&self, room_id: i64, config: &ObsTextConfig, token: &CancellationToken) -> Result<()> {
+        let interval = Duration::from_secs(config.interval_secs.max(1));
+        loop {
+            match cancellable(token, self.get_room_info(room_id)).await {
+                Ok(room) => {
+                    let now = domain::now_secs();
+                    if let Err(e) = fs::write(&config.path, config.render(&room, now)) {
+                        println!("写入 OBS 文本文件失败: {}", e);
+                    }
+                }
+                Err(e) if e.is_cancelled() => return Err(e),
+                Err(e) => println!("获取直播间信息失败: {}", e),
+            }
+            cancellable(token, async {
+                tokio::time::sleep(interval).await;
+                Ok(())
+            })
+            .await?;
+        }
+    }
+
+    /// 数据快照文件：按场次切分，以开播时间（北京时间）命名
+    fn stats_file_path(room_id: i64, live_start: i64) -> PathBuf {
+        Self::config_dir().join("stats").join(stats_file_name(room_id, live_start))
+    }
+
+    /// 导出本场互动概况，与数据快照放在同一目录，文件名为快照文件名换成 `.summary.json`
+    pub fn save_danmaku_summary(room_id: i64, live_start: i64, stats: &DanmakuStats) -> Result<PathBuf> {
+        let name = stats_file_name(room_id, live_start).replace(".jsonl", ".summary.json");
+        let path = Self::config_dir().join("stats").join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let summary = serde_json::json!({ "room_id": room_id, "live_start": live_start, "stats": stats, "summary": stats.summary() });
+        fs::write(&path, serde_json::to_string_pretty(&summary)?)?;
+        Ok(path)
+    }
+
+    /// 采集一次数据快照。高能榜与粉丝团接口失败时对应字段记为 0，不影响其余数据
+    async fn stats_snapshot(&self, room: &RoomInfoResp) -> StatsSnapshot {
+        let online_url = format!(
+            "https://api.live.bilibili.com/xlive/general-interface/v1/rank/getOnlineGoldRank?ruid={}&roomId={}&page=1&pageSize=1",
+            room.uid, room.room_id
+        );
+        let fans_url = format!(
+            "https://api.live.bilibili.com/xlive/general-interface/v1/rank/getFansMembersRank?ruid={}&page=1&page_size=1",
+            room.uid
+        );
+        let online = match self.get_data::<OnlineRankResp>(&online_url).await {
+            Ok(data) => data.online_num,
+            Err(e) => {
+                println!("获取高能榜人数失败: {}", e);
+                0
+            }
+        };
+        let fans_club = match self.get_data::<FansMembersResp>(&fans_url).await {
+            Ok(data) => data.num,
+            Err(e) => {
+                println!("获取粉丝团人数失败: {}", e);
+                0
+            }
+        };
+        let time = domain::now_secs();
+        StatsSnapshot { time, popularity: room.online, online, fans_club }
+    }
+
+    /// 开播期间每隔 `interval` 记录一次数据快照，追加到本场的 stats 文件（jsonl），直到停播或被取消。
+    /// 每个快照同时交给 `on_snapshot`，供界面画趋势图。单次拉取失败只打印日志，下一轮继续
+    pub async fn start_stats_collector(
+        &self,
+        room_id: i64,
+        interval: Duration,
+        token: &CancellationToken,
+        mut on_snapshot: impl FnMut(StatsSnapshot) + Send,
+    ) -> Result<()> {
+        let room_id = self.resolve_real_room_id(room_id).await?;
+        let interval = interval.max(Duration::from_secs(1));
+        let url = format!("https://api.live.bilibili.com/room/v1/Room/get_info?room_id={}", room_id);
+        loop {
+            let room = cancellable(token, self.get_data::<RoomInfoResp>(&url)).await;
+            match room {
+                Ok(room) if room.live_status != 1 => {
+                    println!("直播间未在直播，停止数据采集");
+                    return Ok(());
+                }
+                Ok(room) => {
+                    let snapshot = cancellable(token, async { Ok(self.stats_snapshot(&room).await) }).await?;
+                    let path = Self::stats_file_path(room_id, Self::parse_live_time(&room.live_time));
+                    let res = path
+                        .parent()
+                        .map(fs::create_dir_all)
+                        .transpose()
+                        .and_then(|_| fs::OpenOptions::new().create(true).append(true).open(&path))
+                        .and_then(|mut f| writeln!(f, "{}", serde_json::to_string(&snapshot)?));
+                    if let Err(e) = res {
+                        println!("写入数据快照失败: {}", e);
+                    }
+                    on_snapshot(snapshot);
+                }
+                Err(e) if e.is_cancelled() => return Err(e),
+                Err(e) => println!("获取直播间信息失败: {}", e),
+            }
+            cancellable(token, async {
+                tokio::time::sleep(interval).await;
+                Ok(())
+            })
+            .await?;
+        }
+    }
+
+    /// 把直播间号（可能是短号）换成真实房间号。get_info 对短号和真实号都返回真实号，结果缓存在客户端内，
+    /// 接受 room_id 的接口内部都先经过这里，避免短号导致接口失败
+    pub async fn resolve_real_room_id(&self, input: i64) -> Result<i64> {
+        Self::ensure_room(input)?;
+        if let Some(real) = self.room_ids.lock().unwrap_or_else(|e| e.into_inner()).get(input) {
+            return Ok(real);
+        }
+        let real = self.get_room_info(input).await?.room_id as i64;
+        if real != input {
+            println!("直播间短号 {} 对应真实房间号 {}", input, real);
+        }
+        Ok(real)
+    }
+
+    /// room_id 为 0 表示账号尚未开通直播间，拒绝发出请求
+    fn ensure_room(room_id: i64) -> Result<()> {
+        if room_id == 0 {
+            return Err(BiliError::InvalidArgument("当前账号尚未开通直播间".into()));
+        }
+        Ok(())
+    }
+
+    /// 是否为 dry-run 模式，此时写操作不会真正发送
+    pub fn is_dry_run(&self) -> bool {
+        self.options.dry_run
+    }
+
+    /// 开通直播间，返回新直播间号
+    pub async fn create_live_room(&self) -> Result<i64> {
+        let csrf = self.get_cookie_value("bili_jct").ok_or(BiliError::NotLoggedIn("bili_jct"))?;
+        let mut params: BTreeMap<&str, String> = BTreeMap::new();
+        params.insert("platform", "web".to_string());
+        params.insert("visit_id", String::new());
+        params.insert("csrf", csrf.clone());
+        params.insert("csrf_token", csrf);
+        let resp: BiliResp<CreateRoomResp> = self
+            .post_resp("create_room", "https://api.live.bilibili.com/xlive/app-blink/v1/preLive/CreateRoom", &params)
+            .await?;
+        Ok(unwrap_data(resp)?.room_id)
+    }
+
+    /// 解析接口返回的开播时间（北京时间 `%Y-%m-%d %H:%M:%S`），未开播时为 `0000-00-00 00:00:00`
+    fn parse_live_time(s: &str) -> i64 {
+        let beijing = chrono::FixedOffset::east_opt(8 * 3600).expect("valid offset");
+        chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
+            .ok()
+            .and_then(|t| t.and_local_timezone(beijing).single())
+            .map(|t| t.timestamp())
+            .unwrap_or(0)
+    }
+
+    /// 把调用方传入的扩展字段合并进表单；[`RESERVED_FORM_KEYS`] 中的字段会被忽略
+    fn merge_extra<'a>(params: &mut BTreeMap<&'a str, String>, extra: Option<&'a BTreeMap<String, String>>) {
+        for (key, value) in extra.into_iter().flatten() {
+            if RESERVED_FORM_KEYS.contains(&key.as_str()) {
+                println!("忽略扩展参数中的保留字段: {}", key);
+                continue;
+            }
+            params.insert(key.as_str(), value.clone());
+        }
+    }
+
+    /// 更新直播间信息：支持修改标题与分区，`extra` 为透传的额外表单字段。返回审核信息（若有）。
+    pub async fn update_room_info(
+        &self,
+        room_id: i64,
+        title: Option<&str>,
+        area_id: Option<i64>,
+        extra: Option<&BTreeMap<String, String>>,
+    ) -> Result<Option<AuditInfo>> {
+        let room_id = self.resolve_real_room_id(room_id).await?;
+        if let Some(area_id) = area_id {
+            self.ensure_leaf_area(area_id).await?;
+        }
+        let csrf = self.get_cookie_value("bili_jct").ok_or(BiliError::NotLoggedIn("bili_jct"))?;
+        let mut params: BTreeMap<&str, String> = BTreeMap::new();
+        params.insert("csrf", csrf.clone());
+        params.insert("csrf_token", csrf.clone());
+        params.insert("room_id", room_id.to_string());
+        if let Some(t) = title {
+            params.insert("title", t.to_string());
+        }
+        if let Some(a) = area_id {
+            params.insert("area_id", a.to_string());
+        }
+        Self::merge_extra(&mut params, extra);
+        let resp: BiliResp<serde_json::Value> = self.post_resp("update_room", "https://api.live.bilibili.com/room/v1/Room/update", &params).await?;
+        let Some(data) = ensure_ok(resp)?.filter(serde_json::Value::is_object) else {
+            return Ok(None);
+        };
+        let data: RoomUpdateResp = serde_json::from_value(data)?;
+        Ok(data.audit_info.map(|audit| AuditInfo {
+            audit_title_status: audit.audit_title_status,
+            audit_title_reason: audit.audit_title_reason,
+        }))
+    }
+
+    /// 更新直播间简介，同样走 `room/v1/Room/update`；简介需要审核，通过前观众看到的仍是旧内容
+    pub async fn update_room_description(&self, room_id: i64, desc: &str) -> Result<()> {
+        domain::check_room_description(desc).map_err(BiliError::InvalidArgument)?;
+        let extra = BTreeMap::from([("description".to_string(), desc.to_string())]);
+        // 返回的审核信息只针对标题，与简介无关
+        self.update_room_info(room_id, None, None, Some(&extra)).await.map(drop)
+    }
+
+    /// 更新直播间公告
+    pub async fn update_room_news(&self, room_id: i64, content: &str) -> Result<()> {
+        let room_id = self.resolve_real_room_id(room_id).await?;
+        let csrf = self.get_cookie_value("bili_jct").ok_or(BiliError::NotLoggedIn("bili_jct"))?;
+        let uid = self.get_cookie_value("DedeUserID").ok_or(BiliError::NotLoggedIn("DedeUserID"))?;
+        let mut params: BTreeMap<&str, String> = BTreeMap::new();
+        params.insert("room_id", room_id.to_string());
+        params.insert("uid", uid);
+        params.insert("content", content.to_string());
+        params.insert("csrf", csrf.clone());
+        params.insert("csrf_token", csrf);
+        let resp: BiliResp<IgnoredAny> = self
+            .post_resp("update_room_news", "https://api.live.bilibili.com/xlive/app-blink/v1/index/updateRoomNews", &params)
+            .await?;
+        ensure_ok(resp)?;
+        Ok(())
+    }
+
+    /// 上传图片并设为直播间封面，返回新封面地址。`image` 为 JPEG 或 PNG 文件内容，封面需审核后对观众生效
+    pub async fn upload_cover(&self, room_id: i64, image: &[u8]) -> Result<String> {
+        let room_id = self.resolve_real_room_id(room_id).await?;
+        let mime = image_mime(image).ok_or_else(|| BiliError::InvalidArgument("封面只支持 JPG / PNG 图片".into()))?;
+        let csrf = self.get_cookie_value("bili_jct").ok_or(BiliError::NotLoggedIn("bili_jct"))?;
+        let cover = if self.options.dry_run {
+            println!("[dry-run] 上传封面图片 {} 字节", image.len());
+            "https://dry-run.invalid/cover.jpg".to_string()
+        } else {
+            let filename = if mime == "image/png" { "cover.png" } else { "cover.jpg" };
+            let form = Multipart::new()
+                .text("bucket", "live")
+                .text("dir", "new_room_cover")
+                .text("csrf", &csrf)
+                .file("file", filename, mime, image);
+            // 上传不是幂等的，超时重发可能留下多份图片，只发一次
+            let value = self.execute(ApiRequest::post_multipart("https://api.bilibili.com/x/upload/web/image", &form).attempts(1)).await?;
+            let resp: BiliResp<UploadImageResp> = BiliResp::from_value(value)?;
+            unwrap_data(resp)?.location
+        };
+        let mut params: BTreeMap<&str, String> = BTreeMap::new();
+        params.insert("room_id", room_id.to_string());
+        params.insert("platform", "web".to_string());
+        params.insert("mobi_app", "web".to_string());
+        params.insert("build", "1".to_string());
+        params.insert("cover", cover.clone());
+        params.insert("coverVertical", String::new());
+        params.insert("liveDirectionType", "0".to_string());
+        params.insert("csrf", csrf.clone());
+        params.insert("csrf_token", csrf);
+        let resp: BiliResp<IgnoredAny> = self
+            .post_resp("update_cover", "https://api.live.bilibili.com/xlive/app-blink/v1/preLive/UpdatePreLiveInfo", &params)
+            .await?;
+        ensure_ok(resp)?;
+        Ok(cover)
+    }
+
+    /// 在直播间禁言用户，`hour` 为禁言时长（小时），0 表示本场直播，-1 表示永久
+    pub async fn add_silent_user(&self, room_id: i64, uid: u64, hour: i32) -> Result<()> {
+        let room_id = self.resolve_real_room_id(room_id).await?;
+        let csrf = self.get_cookie_value("bili_jct").ok_or(BiliError::NotLoggedIn("bili_jct"))?;
+        let mut params: BTreeMap<&str, String> = BTreeMap::new();
+        params.insert("room_id", room_id.to_string());
+        params.insert("tuid", uid.to_string());
+        params.insert("hour", hour.to_string());
+        params.insert("mobile_app", "web".to_string());
+        params.insert("csrf", csrf.clone());
+        params.insert("csrf_token", csrf);
+        let resp: BiliResp<IgnoredAny> = self
+            .post_resp("add_silent_user", "https://api.live.bilibili.com/xlive/web-ucenter/v1/banned/AddSilentUser", &params)
+            .await?;
+        ensure_ok(resp)?;
+        Ok(())
+    }
+
+    /// 解除禁言
+    pub async fn del_silent_user(&self, room_id: i64, uid: u64) -> Result<()> {
+        let room_id = self.resolve_real_room_id(room_id).await?;
+        let csrf = self.get_cookie_value("bili_jct").ok_or(BiliError::NotLoggedIn("bili_jct"))?;
+        let mut params: BTreeMap<&str, String> = BTreeMap::new();
+        params.insert("room_id", room_id.to_string());
+        params.insert("tuid", uid.to_string());
+        params.insert("csrf", csrf.clone());
+        params.insert("csrf_token", csrf);
+        let resp: BiliResp<IgnoredAny> = self
+            .post_resp("del_silent_user", "https://api.live.bilibili.com/xlive/web-ucenter/v1/banned/DelSilentUser", &params)
+            .await?;
+        ensure_ok(resp)?;
+        Ok(())
+    }
+
+    /// 任命房管，房管属于主播本人而不是某个直播间
+    pub async fn appoint_room_admin(&self, uid: u64) -> Result<()> {
+        let csrf = self.get_cookie_value("bili_jct").ok_or(BiliError::NotLoggedIn("bili_jct"))?;
+        let mut params: BTreeMap<&str, String> = BTreeMap::new();
+        params.insert("admin", uid.to_string());
+        params.insert("csrf", csrf.clone());
+        params.insert("csrf_token", csrf);
+        let resp: BiliResp<IgnoredAny> = self
+            .post_resp("appoint_room_admin", "https://api.live.bilibili.com/xlive/web-ucenter/v1/roomAdmin/appoint", &params)
+            .await?;
+        ensure_ok(resp)?;
+        Ok(())
     }
 
-    fn load_auth() -> Option<AuthData> {
-        let path = Self::auth_file_path()?;
-        let content = fs::read_to_string(path).ok()?;
-        serde_json::from_str(&content).ok()
+    /// 撤销房管
+    pub async fn remove_room_admin(&self, uid: u64) -> Result<()> {
+        let csrf = self.get_cookie_value("bili_jct").ok_or(BiliError::NotLoggedIn("bili_jct"))?;
+        let mut params: BTreeMap<&str, String> = BTreeMap::new();
+        params.insert("uid", uid.to_string());
+        params.insert("csrf", csrf.clone());
+        params.insert("csrf_token", csrf);
+        let resp: BiliResp<IgnoredAny> = self
+            .post_resp("remove_room_admin", "https://api.live.bilibili.com/xlive/app-ucenter/v1/roomAdmin/dismiss", &params)
+            .await?;
+        ensure_ok(resp)?;
+        Ok(())
     }
 
-    fn save_auth(auth: &AuthData) -> anyhow::Result<()> {
-        if let Some(path) = Self::auth_file_path() {
-            if let Some(parent) = path.parent() {
-                fs::create_dir_all(parent)?;
-            }
-            let data = serde_json::to_string_pretty(auth)?;
-            fs::write(path, data)?;
+    /// 执行房管操作的反向操作：解除禁言或撤掉房管
+    pub async fn undo_mod_action(&self, action: &ModAction) -> Result<()> {
+        match *action {
+            ModAction::Silence { room_id, uid } => self.del_silent_user(room_id, uid).await,
+            ModAction::AppointAdmin { uid, .. } => self.remove_room_admin(uid).await,
         }
-        Ok(())
     }
 
-    /// 创建客户端实例，稍后可注入 Cookie / Token
-    pub fn new() -> Self {
-        let jar = Arc::new(Jar::default());
-        // 启动时从文件加载 cookie
-        if let Some(auth) = Self::load_auth() {
-            if !auth.cookies.is_empty() {
-                println!("加载 {} 条cookie", auth.cookies.len());
-                for c in &auth.cookies {
-                    let cookie_str = format!("{}={}", c.name, c.value);
-                    if let Ok(url) = format!("https://{}", c.domain).parse() {
-                       jar.add_cookie_str(&cookie_str, &url);
-                    }
-                }
+    /// 开始直播，`extra` 为透传的额外表单字段（如活动 id），返回推流信息
+    pub async fn start_live(&self, room_id: i64, area_id: i64, extra: Option<&BTreeMap<String, String>>) -> Result<LiveStart> {
+        let room_id = self.resolve_real_room_id(room_id).await?;
+        // 连点或定时任务重复触发时不再开播一次，直接返回当前推流信息，避免密钥变化
+        let preferred = Self::load_settings().stream_protocol;
+        if !needs_transition(self.current_live_status(room_id).await, true) {
+            println!("直播间 {} 已在直播，不重复开播", room_id);
+            let lines = self.current_stream_lines().await.map_err(|e| BiliError::Other(format!("直播间已在直播，但获取当前推流地址失败: {}", e)))?;
+            return LiveStart::pick(lines, preferred, true).ok_or_else(|| BiliError::MissingData);
+        }
+        self.ensure_leaf_area(area_id).await?;
+        let csrf = self.get_cookie_value("bili_jct").ok_or(BiliError::NotLoggedIn("bili_jct"))?;
+        let mut params: BTreeMap<&str, String> = BTreeMap::new();
+        params.insert("room_id", room_id.to_string());
+        params.insert("area_v2", area_id.to_string());
+        params.insert("platform", "pc_link".to_string());
+        params.insert("csrf", csrf.clone());
+        Self::merge_extra(&mut params, extra);
+
+        let resp: BiliResp<StartLiveResp> = self.post_resp("start_live", "https://api.live.bilibili.com/room/v1/Room/startLive", &params).await?;
+        if let Some(info) = face_auth_info(&resp, room_id) {
+            return Err(BiliError::FaceAuthRequired(info));
+        }
+        if let Some(start) = Self::valid_stream_lines(ensure_ok(resp)?).and_then(|lines| LiveStart::pick(lines, preferred, false)) {
+            if start.protocol != preferred {
+                println!("{} 推流地址不可用，改用 {}", preferred.label(), start.protocol.label());
             }
+            return Ok(start);
+        }
+        // 服务端可能已进入直播状态，回滚以免界面显示未开播而实际在播
+        if !Self::load_settings().rollback_failed_start {
+            bail!("开播接口未返回有效推流地址，直播间可能已进入直播状态");
+        }
+        println!("开播接口未返回有效推流地址，自动关播回滚");
+        // 刚发过开播请求，查到的直播状态可能还没更新，回滚不能按状态跳过
+        match self.send_stop_live(room_id).await {
+            Ok(_) => bail!("开播接口未返回有效推流地址，已自动关播回滚"),
+            Err(e) => bail!("开播接口未返回有效推流地址，回滚关播失败: {}，请到直播中心确认直播状态", e),
         }
-        let client = Client::builder()
-            .cookie_provider(jar.clone())
-            .user_agent("BiliLiveTool/0.1")
-            .build()
-            .expect("reqwest client build failed");
-        Self { client, jar }
     }
 
-    fn random_ua() -> &'static str {
-        USER_AGENTS.choose(&mut thread_rng()).copied().unwrap_or(USER_AGENTS[0])
+    /// 开播；需要人脸认证时先调用 `on_auth` 展示二维码，轮询到认证通过后自动重试开播。
+    /// 认证超时（[`FACE_AUTH_TIMEOUT`]）、被取消或重试仍要求认证时返回错误
+    pub async fn start_live_with_face_auth(
+        &self,
+        room_id: i64,
+        area_id: i64,
+        extra: Option<&BTreeMap<String, String>>,
+        token: &CancellationToken,
+        on_auth: impl FnOnce(&FaceAuthInfo),
+    ) -> Result<LiveStart> {
+        let err = match cancellable(token, self.start_live(room_id, area_id, extra)).await {
+            Err(e) => e,
+            ok => return ok,
+        };
+        let BiliError::FaceAuthRequired(info) = err.root() else { return Err(err) };
+        on_auth(info);
+        self.poll_face_auth(info, token).await?;
+        println!("人脸认证已通过，重新开播");
+        cancellable(token, self.start_live(room_id, area_id, extra)).await
     }
 
-    async fn post_form_retry(&self, url: &str, params: &BTreeMap<&str, String>) -> anyhow::Result<serde_json::Value> {
-        let mut attempts = 0;
-        let mut last_err: anyhow::Error = anyhow::anyhow!("unknown");
-        while attempts < 3 {
-            let ua = Self::random_ua();
-            let resp = self
-                .client
-                .post(url)
-                .header(USER_AGENT, ua)
-                .form(params)
-                .send()
-                .await;
-            match resp {
-                Ok(r) => {
-                    let status = r.status();
-                    let json_val: serde_json::Value = r.json().await.unwrap_or_default();
-                    // 如果 HTTP 被拦截（412）或 code == -412，尝试更换 UA
-                    if status.as_u16() == 412 || json_val["code"].as_i64().unwrap_or(0) == -412 {
-                        attempts += 1;
-                        continue;
-                    }
-                    return Ok(json_val);
-                }
-                Err(e) => {
-                    last_err = e.into();
-                    attempts += 1;
-                }
+    /// 轮询人脸认证结果，直到认证通过、超时或被取消。查询失败（含风控拦截）只记录日志，接口明确报错时终止
+    pub async fn poll_face_auth(&self, auth: &FaceAuthInfo, token: &CancellationToken) -> Result<()> {
+        let deadline = Instant::now() + FACE_AUTH_TIMEOUT;
+        loop {
+            tokio::select! {
+                _ = token.cancelled() => return Err(BiliError::Cancelled),
+                _ = tokio::time::sleep(PollingConfig::interval(self.polling().face_auth_secs)) => {}
+            }
+            match self.is_face_auth_done(auth.room_id).await {
+                Ok(true) => return Ok(()),
+                Ok(false) => {}
+                Err(e) if e.is_api_error() => bail!("人脸认证失败: {}", e),
+                Err(e) => println!("查询人脸认证结果失败: {}", e),
+            }
+            if Instant::now() >= deadline {
+                return Err(BiliError::Timeout(format!("人脸认证超时（{} 分钟内未完成），请重新开播", FACE_AUTH_TIMEOUT.as_secs() / 60)));
             }
         }
-        Err(last_err)
     }
 
-    /// 检查当前登录状态
-    pub async fn check_login_state(&self) -> Result<LoginState> {
-        let check_url = "https://api.bilibili.com/x/web-interface/nav";
-        let resp_json: serde_json::Value = self
-            .client
-            .get(check_url)
-            .header(USER_AGENT, Self::random_ua())
-            .send()
-            .await?
-            .json()
-            .await?;
-        if resp_json["code"].as_i64().unwrap_or(-1) == 0 {
-            if resp_json["data"]["isLogin"].as_bool().unwrap_or(false) {
-                return Ok(LoginState::LoggedIn);
+    /// 开播接口成功不代表推流端已连上：轮询直播间状态直到变为直播中，超时或被取消时返回错误。
+    /// 单次查询失败只记录日志，dry-run 时直接返回
+    pub async fn wait_until_live(&self, room_id: i64, timeout: Duration, token: &CancellationToken) -> Result<()> {
+        Self::ensure_room(room_id)?;
+        if self.options.dry_run {
+            return Ok(());
+        }
+        let deadline = Instant::now() + timeout;
+        loop {
+            match cancellable(token, self.get_room_info(room_id)).await {
+                Ok(room) if room.live_status == 1 => return Ok(()),
+                Ok(_) => {}
+                Err(e) if e.is_cancelled() => return Err(e),
+                Err(e) => println!("查询直播状态失败: {}", e),
+            }
+            if Instant::now() >= deadline {
+                return Err(BiliError::Timeout(format!("{} 秒内未检测到直播上线，请检查推流软件是否已开始推流", timeout.as_secs())));
+            }
+            tokio::select! {
+                _ = token.cancelled() => return Err(BiliError::Cancelled),
+                _ = tokio::time::sleep(PollingConfig::interval(self.polling().live_check_secs)) => {}
             }
         }
-        Ok(LoginState::NeedQrCode)
     }
 
-    /// 获取登录二维码 (Web)
-    pub async fn fetch_qr_code(&self) -> Result<WebQrInfo> {
-        let resp = self
-            .client
-            .get("https://passport.bilibili.com/x/passport-login/web/qrcode/generate")
-            .header(USER_AGENT, Self::random_ua())
-            .send()
-            .await?
-            .json::<serde_json::Value>()
-            .await?;
+    /// 查询当前账号是否已完成开播人脸认证
+    async fn is_face_auth_done(&self, room_id: i64) -> Result<bool> {
+        let csrf = self.get_cookie_value("bili_jct").ok_or(BiliError::NotLoggedIn("bili_jct"))?;
+        let mut params: BTreeMap<&str, String> = BTreeMap::new();
+        params.insert("room_id", room_id.to_string());
+        params.insert("face_auth_code", models::FACE_AUTH_CODE.to_string());
+        params.insert("csrf", csrf.clone());
+        params.insert("csrf_token", csrf);
+        let value = self.post_form_retry("https://api.live.bilibili.com/xlive/app-blink/v1/preLive/IsUserIdentifiedByFaceAuth", &params).await?;
+        let resp: BiliResp<FaceAuthResultResp> = BiliResp::from_value(value)?;
+        Ok(unwrap_data(resp)?.is_identified)
+    }
 
-        if resp["code"].as_i64().unwrap_or(-1) != 0 {
-            anyhow::bail!("获取二维码失败: {}", resp["message"].as_str().unwrap_or(""));
-        }
-        let data = &resp["data"];
-        Ok(WebQrInfo {
-            url: data["url"].as_str().unwrap_or("").to_string(),
-            qrcode_key: data["qrcode_key"].as_str().unwrap_or("").to_string(),
-        })
+    /// RTMP 与 SRT 分别校验，至少一个可用才算开播成功
+    fn valid_stream_lines(data: Option<StartLiveResp>) -> Option<StreamLines> {
+        let data = data?;
+        let lines = StreamLines { rtmp: Self::stream_line(data.rtmp, StreamProtocol::Rtmp), srt: Self::stream_line(data.srt, StreamProtocol::Srt) };
+        (!lines.is_empty()).then_some(lines)
     }
 
-    /// 轮询二维码是否扫描完成 (Web)
-    pub async fn poll_qr_login(&self, qr_info: &WebQrInfo) -> Result<LoginState> {
-        let poll_url = format!("https://passport.bilibili.com/x/passport-login/web/qrcode/poll?qrcode_key={}", qr_info.qrcode_key);
-        let resp = self
-            .client
-            .get(&poll_url)
-            .header(USER_AGENT, Self::random_ua())
-            .send()
-            .await?
-            .json::<serde_json::Value>()
-            .await?;
+    /// 推流地址与密钥都非空、且地址的协议头与协议相符才可用
+    fn stream_line(resp: Option<RtmpResp>, protocol: StreamProtocol) -> Option<StreamLine> {
+        let resp = resp?;
+        (protocol.matches_addr(&resp.addr) && !resp.code.is_empty()).then_some(StreamLine { addr: resp.addr, code: resp.code })
+    }
 
-        let data = &resp["data"];
-        let code = data["code"].as_i64().unwrap_or(-1);
-        println!("Web登录轮询响应码: {}", code);
-        match code {
-            0 => { // 扫码成功
-                println!("Web登录成功，保存Cookie...");
-                // 登录成功后，B站不会在poll接口返回Set-Cookie，而是由客户端再次请求返回的url来设置。
-                // reqwest的cookie_provider会自动处理这个过程，我们只需要确保后续的jar是同一个即可。
-                // 手动保存最新的cookie到文件
-                let cookies = self.build_cookie_list();
-                let auth_data = AuthData { token: TokenInfo::default(), cookies };
-                Self::save_auth(&auth_data)?;
-                println!("Cookie保存完毕");
-                Ok(LoginState::LoggedIn)
-            }
-            86038 => { // 二维码已失效
-                println!("二维码已失效");
-                Ok(LoginState::NeedQrCode)
-            }
-            86090 => { // 二维码已扫，待确认
-                println!("二维码已扫，待确认");
-                Ok(LoginState::NeedQrCode)
-            }
-            _ => { // 其他状态，视为未登录
-                Ok(LoginState::NeedQrCode)
+    /// 直播间当前的直播状态，查询失败时返回 None，由调用方照常发出开播 / 关播请求
+    async fn current_live_status(&self, room_id: i64) -> Option<i32> {
+        match self.get_room_info(room_id).await {
+            Ok(room) => Some(room.live_status),
+            Err(e) => {
+                println!("查询直播状态失败，照常请求: {}", e);
+                None
             }
         }
     }
 
-    /// 获取直播间信息
-    pub async fn get_room_info(&self) -> Result<RoomInfo> {
-        // TODO: 实现真正的逻辑
-        Ok(RoomInfo::default())
+    /// 正在直播时的推流地址，`reset_key=false` 不会重置密钥
+    async fn current_stream_lines(&self) -> Result<StreamLines> {
+        let url = "https://api.live.bilibili.com/xlive/app-blink/v1/live/getWebUpStreamAddr?platform=pc&backup_stream=0&reset_key=false";
+        let data: UpStreamAddrResp = self.get_data(url).await?;
+        let lines = StreamLines { rtmp: Self::stream_line(data.addr, StreamProtocol::Rtmp), srt: Self::stream_line(data.srt_addr, StreamProtocol::Srt) };
+        if lines.is_empty() {
+            return Err(BiliError::MissingData);
+        }
+        Ok(lines)
     }
 
-    /// 更新直播间信息：支持修改标题与分区。返回审核信息（若有）。
-    pub async fn update_room_info(&self, room_id: i64, title: Option<&str>, area_id: Option<i64>) -> anyhow::Result<Option<AuditInfo>> {
-        let csrf = self.get_cookie_value("bili_jct").ok_or_else(|| anyhow::anyhow!("缺少 csrf cookie"))?;
-        let mut params: BTreeMap<&str, String> = BTreeMap::new();
-        params.insert("csrf", csrf.clone());
-        params.insert("csrf_token", csrf.clone());
-        params.insert("room_id", room_id.to_string());
-        if let Some(t) = title {
-            params.insert("title", t.to_string());
-        }
-        if let Some(a) = area_id {
-            params.insert("area_id", a.to_string());
-        }
-        let resp = self.post_form_retry("https://api.live.bilibili.com/room/v1/Room/update", &params).await?;
-        let code = resp["code"].as_i64().unwrap_or(-1);
-        if code != 0 {
-            anyhow::bail!("更新失败: {}", resp["message"].as_str().unwrap_or(""));
-        }
-        let audit = &resp["data"]["audit_info"];
-        if audit.is_object() {
-            Ok(Some(AuditInfo {
-                audit_title_status: audit["audit_title_status"].as_i64().unwrap_or(0) as i32,
-                audit_title_reason: audit["audit_title_reason"].as_str().unwrap_or("").to_string(),
-            }))
-        } else {
-            Ok(None)
+    /// 停止直播
+    pub async fn stop_live(&self, room_id: i64) -> Result<bool> {
+        let room_id = self.resolve_real_room_id(room_id).await?;
+        if !needs_transition(self.current_live_status(room_id).await, false) {
+            println!("直播间 {} 未在直播，无需关播", room_id);
+            return Ok(false);
         }
+        self.send_stop_live(room_id).await?;
+        Ok(true)
     }
 
-    /// 开始直播，返回 (addr, code)
-    pub async fn start_live(&self, room_id: i64, area_id: i64) -> anyhow::Result<(String, String)> {
-        let csrf = self.get_cookie_value("bili_jct").ok_or_else(|| anyhow::anyhow!("缺少 csrf cookie"))?;
+    /// 不看当前直播状态，直接发关播请求
+    async fn send_stop_live(&self, room_id: i64) -> Result<()> {
+        let csrf = self.get_cookie_value("bili_jct").ok_or(BiliError::NotLoggedIn("bili_jct"))?;
         let mut params: BTreeMap<&str, String> = BTreeMap::new();
         params.insert("room_id", room_id.to_string());
-        params.insert("area_v2", area_id.to_string());
         params.insert("platform", "pc_link".to_string());
         params.insert("csrf", csrf.clone());
+        let resp: BiliResp<IgnoredAny> = self.post_resp("stop_live", "https://api.live.bilibili.com/room/v1/Room/stopLive", &params).await?;
+        ensure_ok(resp)?;
+        Ok(())
+    }
 
-        let resp = self.post_form_retry("https://api.live.bilibili.com/room/v1/Room/startLive", &params).await?;
-        if resp["code"].as_i64().unwrap_or(-1) != 0 {
-            anyhow::bail!("开播失败: {}", resp["message"].as_str().unwrap_or(""));
+    /// 把事件 POST 到配置的 webhook，失败按递增间隔重试；最终失败只记录日志，不影响主流程
+    pub async fn emit_event(&self, event: &LiveEvent) {
+        let Some(url) = Self::load_settings().webhook_url.filter(|u| !u.trim().is_empty()) else {
+            return;
+        };
+        if self.options.dry_run {
+            println!("[dry-run] webhook {} 事件 {}", url, event.event);
+            return;
+        }
+        for attempt in 1..=WEBHOOK_ATTEMPTS {
+            // webhook 不是 B 站接口，如实表明身份而不冒充浏览器
+            let res = self.client.post(&url).header(USER_AGENT, WEBHOOK_UA).json(event).send().await.and_then(|r| r.error_for_status());
+            match res {
+                Ok(_) => return,
+                Err(e) => println!("webhook 推送失败（第 {} 次）: {}", attempt, e),
+            }
+            if attempt < WEBHOOK_ATTEMPTS {
+                tokio::time::sleep(Duration::from_secs(attempt as u64)).await;
+            }
         }
-        let rtmp = &resp["data"]["rtmp"];
-        let addr = rtmp["addr"].as_str().unwrap_or("").to_string();
-        let code = rtmp["code"].as_str().unwrap_or("").to_string();
-        Ok((addr, code))
     }
 
-    /// 停止直播
-    pub async fn stop_live(&self, room_id: i64) -> anyhow::Result<()> {
-        let csrf = self.get_cookie_value("bili_jct").ok_or_else(|| anyhow::anyhow!("缺少 csrf cookie"))?;
-        let mut params: BTreeMap<&str, String> = BTreeMap::new();
-        params.insert("room_id", room_id.to_string());
-        params.insert("platform", "pc_link".to_string());
-        params.insert("csrf", csrf.clone());
-        let resp = self.post_form_retry("https://api.live.bilibili.com/room/v1/Room/stopLive", &params).await?;
-        if resp["code"].as_i64().unwrap_or(-1) != 0 {
-            anyhow::bail!("关播失败: {}", resp["message"].as_str().unwrap_or(""));
+    /// 并发探测关键域名的连通性与延迟。任何 HTTP 响应（包括 4xx）都算可达，只有连接层失败才算不可达
+    pub async fn diagnose_network(&self) -> NetworkDiag {
+        let probes: Vec<_> = DIAG_HOSTS
+            .iter()
+            .map(|host| tokio::spawn(Self::probe_host(self.client.clone(), host)))
+            .collect();
+        let mut hosts = Vec::with_capacity(probes.len());
+        for (probe, host) in probes.into_iter().zip(DIAG_HOSTS) {
+            hosts.push(probe.await.unwrap_or_else(|e| HostDiag {
+                host: host.to_string(),
+                error: Some(e.to_string()),
+                ..HostDiag::default()
+            }));
         }
-        Ok(())
+        NetworkDiag { hosts }
+    }
+
+    async fn probe_host(client: Client, host: &str) -> HostDiag {
+        let start = std::time::Instant::now();
+        let res = client.head(format!("https://{}/", host)).timeout(DIAG_TIMEOUT).send().await;
+        let mut diag = HostDiag { host: host.to_string(), ..HostDiag::default() };
+        match res {
+            Ok(_) => {
+                diag.reachable = true;
+                diag.latency_ms = start.elapsed().as_millis() as u64;
+            }
+            Err(e) if e.is_timeout() => diag.error = Some("连接超时".to_string()),
+            Err(e) if e.is_connect() => diag.error = Some(format!("无法建立连接（检查 DNS / 代理 / 防火墙）: {}", e)),
+            Err(e) => diag.error = Some(e.to_string()),
+        }
+        diag
+    }
+
+    /// 下载静态资源，复用客户端的连接池、cookie 与浏览器 UA
+    pub async fn fetch_bytes(&self, url: &str) -> Result<Vec<u8>> {
+        let resp = self.request(reqwest::Method::GET, url, self.pick_ua()).send().await?.error_for_status()?;
+        Ok(resp.bytes().await?.to_vec())
+    }
+
+    /// 下载封面、头像等图片。B 站图床有 Referer 防盗链：先带 `https://www.bilibili.com/` 与会话 UA，
+    /// 403 时换 UA 并依次改用其它 Referer 重试；返回的不是图片（如防盗链占位页）时报错
+    pub async fn download_image(&self, url: &str) -> Result<Vec<u8>> {
+        let url = image_url(url);
+        if !is_image_host(&url) {
+            return self.fetch_bytes(&url).await;
+        }
+        let attempts = std::iter::once((self.pick_ua(), image_referer(&url))).chain(IMAGE_REFERER_FALLBACKS.iter().map(|r| (Self::random_ua(), *r)));
+        let mut last_status = StatusCode::FORBIDDEN;
+        for (ua, referer) in attempts {
+            // 不走 request()：图片请求不带 Origin，Referer 由这里单独决定
+            let mut req = self.client.get(&url).header(USER_AGENT, ua);
+            if let Some(referer) = referer {
+                req = req.header(REFERER, referer);
+            }
+            let resp = req.send().await?;
+            last_status = resp.status();
+            if last_status == StatusCode::FORBIDDEN {
+                println!("图片被防盗链拒绝（Referer {:?}），换用其它 Referer 重试: {}", referer, url);
+                continue;
+            }
+            let resp = resp.error_for_status()?;
+            let is_image = resp
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.starts_with("image/"))
+                .unwrap_or(true);
+            if !is_image {
+                bail!("图片地址返回的不是图片: {}", url);
+            }
+            return Ok(resp.bytes().await?.to_vec());
+        }
+        bail!("图片下载被拒绝（HTTP {}）: {}", last_status.as_u16(), url)
     }
 
     /// 从活动的 cookie jar 中获取指定名称的 cookie 值
@@ -284,17 +1910,14 @@ impl BiliClient {
         None
     }
 
-    fn build_cookie_list(&self) -> Vec<CookieInfo> {
-        // 仅简单解析常用 cookie 并存储
+    /// 当前 cookie jar 的快照，用于持久化与诊断
+    pub fn current_cookies(&self) -> Vec<CookieInfo> {
         let url = "https://bilibili.com".parse().unwrap();
         if let Some(cookies_jar) = self.jar.cookies(&url) {
             if let Ok(s) = cookies_jar.to_str() {
                 return s.split(';')
                     .filter_map(|item| {
-                        let item = item.trim();
-                        let mut kv = item.splitn(2, '=');
-                        let name = kv.next()?;
-                        let value = kv.next()?;
+                        let (name, value) = item.trim().split_once('=')?;
                         Some(CookieInfo {
                             name: name.to_string(),
                             value: value.to_string(),
@@ -308,16 +1931,18 @@ impl BiliClient {
         Vec::new()
     }
 
-    fn generate_correspond_path(ts: i64) -> anyhow::Result<String> {
-        let public_key = RsaPublicKey::from_public_key_pem(PUB_KEY_PEM)?;
+    fn generate_correspond_path(ts: i64) -> Result<String> {
+        let public_key = RsaPublicKey::from_public_key_pem(PUB_KEY_PEM).map_err(BiliError::other)?;
         let plaintext = format!("refresh_{}", ts);
         let padding = Oaep::new::<Sha256>();
         let mut rng = rand::thread_rng();
-        let encrypted = public_key.encrypt(&mut rng, padding, plaintext.as_bytes())?;
+        let encrypted = public_key.encrypt(&mut rng, padding, plaintext.as_bytes()).map_err(BiliError::other)?;
         Ok(hex::encode(encrypted))
     }
 
-    pub async fn refresh_cookies_if_needed(&self) -> anyhow::Result<()> {
+    /// 服务端提示需要时刷新 cookie 并保存。`confirm_old` 为 true 时再调用 confirm 接口让旧 refresh_token 失效，
+    /// 为 false 时旧凭据继续有效，多端共用账号时不会互相踢下线，但旧凭据泄露的风险也一直存在
+    pub async fn refresh_cookies_if_needed(&self, confirm_old: bool) -> Result<()> {
         // 1. 获取 csrf
         let csrf = match self.get_cookie_value("bili_jct") {
             Some(c) => c,
@@ -325,24 +1950,17 @@ impl BiliClient {
         };
 
         // 2. 检查是否需要刷新
-        let check_url = "https://passport.bilibili.com/x/passport-login/web/cookie/info";
-        let resp_json: serde_json::Value = self
-            .client
-            .get(check_url)
-            .header(USER_AGENT, Self::random_ua())
-            .send()
-            .await?
-            .json()
+        let resp: BiliResp<CookieInfoResp> = self
+            .get_resp("https://passport.bilibili.com/x/passport-login/web/cookie/info")
             .await?;
-        if resp_json["code"].as_i64().unwrap_or(-1) != 0 {
-            return Ok(()); // 无法检查，忽略
-        }
-        let data = &resp_json["data"];
-        let need_refresh = data["refresh"].as_bool().unwrap_or(false);
-        if !need_refresh {
+        let data = match resp.data {
+            Some(data) if resp.code == 0 => data,
+            _ => return Ok(()), // 无法检查，忽略
+        };
+        if !data.refresh {
             return Ok(());
         }
-        let timestamp = data["timestamp"].as_i64().unwrap_or_else(|| {
+        let timestamp = data.timestamp.unwrap_or_else(|| {
             let now = SystemTime::now();
             let since_the_epoch = now.duration_since(SystemTime::UNIX_EPOCH).expect("Time went backwards");
             since_the_epoch.as_millis() as i64
@@ -353,18 +1971,11 @@ impl BiliClient {
 
         // 4. 获取 refresh_csrf
         let correspond_url = format!("https://www.bilibili.com/correspond/1/{}", correspond_path);
-        let html_text = self
-            .client
-            .get(&correspond_url)
-            .header(USER_AGENT, Self::random_ua())
-            .send()
-            .await?
-            .text()
-            .await?;
+        let html_text = self.execute_text(ApiRequest::get(&correspond_url)).await?;
         let re = Regex::new(r#"<div id=['\"]1-name['\"]>([0-9a-f]{32})</div>"#).unwrap();
         let refresh_csrf = match re.captures(&html_text) {
             Some(caps) => caps.get(1).unwrap().as_str().to_string(),
-            None => anyhow::bail!("无法解析 refresh_csrf"),
+            None => bail!("无法解析 refresh_csrf"),
         };
 
         // 5. 准备刷新 cookie
@@ -374,7 +1985,7 @@ impl BiliClient {
             None => String::new(),
         };
         if refresh_token_old.is_empty() {
-            anyhow::bail!("缺少 refresh_token，无法刷新 cookie");
+            bail!("缺少 refresh_token，无法刷新 cookie");
         }
 
         let mut form: BTreeMap<&str, String> = BTreeMap::new();
@@ -383,35 +1994,28 @@ impl BiliClient {
         form.insert("source", "main_web".into());
         form.insert("refresh_token", refresh_token_old.clone());
 
-        let refresh_resp: serde_json::Value = self
-            .client
-            .post("https://passport.bilibili.com/x/passport-login/web/cookie/refresh")
-            .header(USER_AGENT, Self::random_ua())
-            .form(&form)
-            .send()
-            .await?
-            .json()
+        // 刷新会作废旧 refresh_token，不能重放
+        let refresh_value = self
+            .execute(ApiRequest::post_form("https://passport.bilibili.com/x/passport-login/web/cookie/refresh", &form).attempts(1))
             .await?;
-        if refresh_resp["code"].as_i64().unwrap_or(-1) != 0 {
-            anyhow::bail!("刷新 cookie 失败: {}", refresh_resp["message"].as_str().unwrap_or(""));
-        }
-        let new_refresh_token = refresh_resp["data"]["refresh_token"].as_str().unwrap_or("").to_string();
+        let refresh_resp: BiliResp<CookieRefreshResp> = BiliResp::from_value(refresh_value)?;
+        let new_refresh_token = unwrap_data(refresh_resp)?.refresh_token;
 
         // 6. 确认更新，让旧 refresh_token 失效
-        let csrf_new = match self.get_cookie_value("bili_jct") {
-            Some(c) => c,
-            None => csrf.clone(),
-        };
-        let mut confirm_form: BTreeMap<&str, String> = BTreeMap::new();
-        confirm_form.insert("csrf", csrf_new);
-        confirm_form.insert("refresh_token", refresh_token_old.clone());
-        let _ = self
-            .client
-            .post("https://passport.bilibili.com/x/passport-login/web/confirm/refresh")
-            .header(USER_AGENT, Self::random_ua())
-            .form(&confirm_form)
-            .send()
-            .await;
+        if confirm_old {
+            let csrf_new = match self.get_cookie_value("bili_jct") {
+                Some(c) => c,
+                None => csrf.clone(),
+            };
+            let mut confirm_form: BTreeMap<&str, String> = BTreeMap::new();
+            confirm_form.insert("csrf", csrf_new);
+            confirm_form.insert("refresh_token", refresh_token_old.clone());
+            let _ = self
+                .execute(ApiRequest::post_form("https://passport.bilibili.com/x/passport-login/web/confirm/refresh", &confirm_form).attempts(1))
+                .await;
+        } else {
+            println!("已跳过 cookie 刷新确认，旧 refresh_token 仍然有效");
+        }
 
         // 7. 保存最新 auth 数据
         let (old_access, old_expire) = match &auth_opt {
@@ -423,7 +2027,7 @@ impl BiliClient {
             refresh_token: new_refresh_token,
             expires_in: old_expire,
         };
-        let cookies_vec = self.build_cookie_list();
+        let cookies_vec = self.current_cookies();
         let auth_data = AuthData { token: token_info, cookies: cookies_vec };
         let _ = Self::save_auth(&auth_data);
 
@@ -433,96 +2037,619 @@ impl BiliClient {
     /// 获取当前登录用户信息（Web端API）
     pub async fn get_self_info(&self) -> Result<UserInfo> {
         println!("开始获取当前登录用户信息 (Web)");
-        let nav_resp: serde_json::Value = self
-            .client
-            .get("https://api.bilibili.com/x/web-interface/nav")
-            .header(USER_AGENT, Self::random_ua())
-            .send()
-            .await?
-            .json()
-            .await?;
-        
-        if nav_resp["code"].as_i64().unwrap_or(-1) != 0 {
-            anyhow::bail!("获取用户信息失败: {}", nav_resp["message"].as_str().unwrap_or(""));
+        let nav_resp: BiliResp<NavResp> = self.get_resp("https://api.bilibili.com/x/web-interface/nav").await?;
+        let data = unwrap_data(nav_resp)?;
+        if !data.is_login {
+            return Err(BiliError::LoggedOut);
         }
-
-        let data = &nav_resp["data"];
-        if !data["isLogin"].as_bool().unwrap_or(false) {
-            anyhow::bail!("用户未登录");
+        if data.mid == 0 {
+            bail!("无法获取有效的用户ID");
         }
 
-        let mid = data["mid"].as_u64().unwrap_or(0);
-        if mid == 0 {
-            anyhow::bail!("无法获取有效的用户ID");
-        }
-        
         // 从 /nav 获取基本信息
         let mut user_info = UserInfo {
-            mid,
-            name: data["uname"].as_str().unwrap_or("").to_string(),
-            face: data["face"].as_str().unwrap_or("").to_string(),
+            mid: data.mid,
+            name: data.uname,
+            face: data.face,
             live_room: LiveRoomBrief::default(),
+            ..UserInfo::default()
         };
-        
-        // 从 space/acc/info 获取直播间信息
-        let space_url = format!("https://api.bilibili.com/x/space/acc/info?mid={}", mid);
-        let space_resp: serde_json::Value = self.client.get(&space_url)
-            .header(USER_AGENT, Self::random_ua())
-            .send().await?.json().await?;
-            
-        if space_resp["code"].as_i64().unwrap_or(-1) == 0 {
-            if let Some(live_room_data) = space_resp["data"]["live_room"].as_object() {
-                 user_info.live_room = LiveRoomBrief {
-                    room_status: live_room_data["roomStatus"].as_i64().unwrap_or(0) as i32,
-                    live_status: live_room_data["liveStatus"].as_i64().unwrap_or(0) as i32,
-                    title: live_room_data["title"].as_str().unwrap_or("").to_string(),
-                    cover: live_room_data["cover"].as_str().unwrap_or("").to_string(),
-                    room_id: live_room_data["roomid"].as_i64().unwrap_or(0),
-                };
+
+        // 关注 / 粉丝数只用于展示，失败不影响登录流程
+        let stat_url = format!("https://api.bilibili.com/x/relation/stat?vmid={}", data.mid);
+        match self.get_data::<RelationStatResp>(&stat_url).await {
+            Ok(stat) => {
+                user_info.following = stat.following;
+                user_info.follower = stat.follower;
             }
-        } else {
-            println!("警告：获取直播间信息失败: {}", space_resp["message"].as_str().unwrap_or("未知错误"));
+            Err(e) => println!("获取关注 / 粉丝数失败: {}", e),
+        }
+
+        // 从 space/acc/info 获取直播间信息
+        let space_url = format!("https://api.bilibili.com/x/space/acc/info?mid={}", data.mid);
+        let space_resp: BiliResp<SpaceInfoResp> = self.get_resp(&space_url).await?;
+        // 接口失败时不能当作"未开通直播间"，交给调用方重试
+        if let Some(room) = unwrap_data(space_resp)?.live_room {
+            user_info.live_room = LiveRoomBrief {
+                room_status: room.room_status,
+                live_status: room.live_status,
+                title: room.title,
+                cover: room.cover,
+                room_id: room.roomid,
+            };
+            // space 接口返回的是真实房间号，省去之后的一次解析
+            self.room_ids.lock().unwrap_or_else(|e| e.into_inner()).record(room.roomid, 0);
         }
 
         println!("用户信息获取完成: {:?}", user_info);
+        let cached = user_info.clone();
+        Self::update_offline_snapshot(move |s, _| {
+            // 换了账号时旧的直播间信息不再适用
+            if s.user.as_ref().is_some_and(|u| u.mid != cached.mid) {
+                s.room = None;
+            }
+            s.user = Some(cached);
+            true
+        });
         Ok(user_info)
     }
 
-    pub async fn get_area_list(&self) -> anyhow::Result<Vec<AreaParent>> {
-        let resp: serde_json::Value = self
-            .client
-            .get("https://api.live.bilibili.com/room/v1/Area/getList")
-            .header(USER_AGENT, Self::random_ua())
-            .send()
-            .await?
-            .json()
-            .await?;
-        if resp["code"].as_i64().unwrap_or(-1) != 0 {
-            anyhow::bail!("获取分区失败: {}", resp["message"].as_str().unwrap_or(""));
-        }
-        let mut parents = Vec::new();
-        if let Some(arr) = resp["data"].as_array() {
-            for p in arr {
-                let mut children = Vec::new();
-                if let Some(list) = p["list"].as_array() {
-                    for c in list {
-                        children.push(AreaChild {
-                            id: c["id"].as_str().unwrap_or("0").parse().unwrap_or(0),
-                            name: c["name"].as_str().unwrap_or("").to_string(),
-                        });
-                    }
-                }
-                parents.push(AreaParent {
-                    id: p["id"].as_i64().unwrap_or(0),
-                    name: p["name"].as_str().unwrap_or("").to_string(),
-                    children,
-                });
+    /// 获取分区列表。上次响应带了 ETag / Last-Modified 时发条件请求，304 时改用本地缓存；
+    /// 接口不返回这两个头时每次都是普通请求，也不写缓存文件
+    pub async fn get_area_list(&self) -> Result<Vec<AreaParent>> {
+        const URL: &str = "https://api.live.bilibili.com/room/v1/Area/getList";
+        let cache = Self::load_cached::<AreaListCache>(AREA_LIST_CACHE).map(|c| c.data).filter(|c| !c.areas.is_empty());
+        let validators = cache.as_ref().map(|c| c.validators.clone()).unwrap_or_default();
+        let (value, validators) = match self.execute_conditional(ApiRequest::get(URL).conditional(&validators)).await? {
+            Conditional::Modified { value, validators } => (value, validators),
+            Conditional::NotModified => {
+                println!("分区列表未变化，使用本地缓存");
+                let areas = cache.map(|c| c.areas).ok_or_else(|| BiliError::Other("分区列表返回 304，但本地没有缓存".into()))?;
+                return Ok(self.remember_area_list(areas));
+            }
+        };
+        let resp: BiliResp<AreaListResp> = BiliResp::from_value(value)?;
+        let mut parents = unwrap_data(resp)?
+            .into_iter()
+            .map(|p| AreaParent {
+                id: p.id,
+                name: p.name,
+                pic: p.pic,
+                sort: p.sort,
+                children: p
+                    .list
+                    .into_iter()
+                    .map(|c| AreaChild { id: c.id, name: c.name, locked: c.lock_status != 0 })
+                    .collect(),
+            })
+            .collect();
+        domain::normalize_area_list(&mut parents);
+        if !validators.is_empty() {
+            Self::store_cached(AREA_LIST_CACHE, AreaListCache { validators, areas: parents.clone() });
+        }
+        Ok(self.remember_area_list(parents))
+    }
+
+    /// 记入内存缓存与离线快照
+    fn remember_area_list(&self, parents: Vec<AreaParent>) -> Vec<AreaParent> {
+        *self.area_cache.lock().unwrap_or_else(|e| e.into_inner()) = Some(parents.clone());
+        let cached = parents.clone();
+        Self::update_offline_snapshot(move |s, _| {
+            s.areas = cached;
+            true
+        });
+        parents
+    }
+
+    /// 优先使用缓存的分区列表，没有缓存时获取一次
+    async fn cached_area_list(&self) -> Result<Vec<AreaParent>> {
+        let cached = self.area_cache.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        match cached {
+            Some(list) => Ok(list),
+            None => self.get_area_list().await,
+        }
+    }
+
+    /// 提交前确认 area_id 是子分区。缓存里找不到时重新获取一次再判断，以免新增分区被旧缓存误拦；
+    /// 分区列表获取失败时不拦截，交给服务端判断
+    async fn ensure_leaf_area(&self, area_id: i64) -> Result<()> {
+        if self.cached_area_list().await.is_ok_and(|areas| domain::leaf_area_error(&areas, area_id).is_none()) {
+            return Ok(());
+        }
+        match self.get_area_list().await {
+            Ok(areas) => match domain::leaf_area_error(&areas, area_id) {
+                Some(reason) => Err(BiliError::InvalidArgument(reason)),
+                None => Ok(()),
+            },
+            Err(e) => {
+                println!("获取分区列表失败，跳过分区校验: {}", e);
+                Ok(())
+            }
+        }
+    }
+
+    /// 检查分区是否允许开播，允许时返回 `None`，否则返回原因
+    pub async fn check_area(&self, area_id: i64) -> Result<Option<String>> {
+        let areas = self.get_area_list().await?;
+        let reason = match domain::find_area(&areas, area_id) {
+            None => Some(format!("分区 {} 不存在或不是子分区", area_id)),
+            Some((pi, ci)) if areas[pi].children[ci].locked => {
+                Some(format!("分区「{}」暂不允许当前账号开播", areas[pi].children[ci].name))
             }
+            Some(_) => None,
+        };
+        Ok(reason)
+    }
+
+    /// 开播前检查，返回所有不满足的条件，为空表示可以开播
+    pub async fn pre_live_check(&self, room_id: i64, area_id: i64) -> Result<Vec<String>> {
+        let mut problems = Vec::new();
+        if room_id == 0 {
+            problems.push("当前账号尚未开通直播间".to_string());
+        }
+        if let Some(reason) = self.check_area(area_id).await? {
+            problems.push(reason);
         }
-        Ok(parents)
+        Ok(problems)
     }
 
     pub fn client(&self) -> &Client {
         &self.client
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试用客户端：配置写到临时目录，不读 auth.json 与 BILI_* 环境变量，不走代理；
+    /// 给了 `mock` 时所有请求改发到该地址
+    fn test_client(mock: Option<&str>, auth: Option<AuthData>) -> BiliClient {
+        static DIR: OnceLock<()> = OnceLock::new();
+        DIR.get_or_init(|| {
+            let dir = std::env::temp_dir().join(format!("bili-live-tool-test-{}", std::process::id()));
+            BiliClient::set_profile(Some(dir), None).unwrap();
+        });
+        let options = ClientOptions {
+            no_proxy: true,
+            retry_backoff: Some(Duration::from_millis(10)),
+            mock_origin: mock.map(str::to_string),
+            ..Default::default()
+        };
+        BiliClient::build(options, auth).unwrap()
+    }
+
+    /// 读写配置目录的测试共用同一个临时目录，串行执行
+    static CONFIG_FILES: Mutex<()> = Mutex::new(());
+
+    fn test_auth() -> AuthData {
+        let cookie = |name: &str| domain::Cookie { name: name.into(), value: "v".into(), domain: ".bilibili.com".into(), expires: 0 };
+        AuthData { token: Default::default(), cookies: vec![cookie("bili_jct"), cookie("DedeUserID")] }
+    }
+
+    /// 本地 HTTP 服务：按 (方法, 路径) 交给 `reply` 生成 JSON 正文，记录收到的每个请求。每个连接只处理一个请求
+    async fn mock_server(reply: fn(&str, &str) -> String) -> (String, Arc<Mutex<Vec<String>>>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let origin = format!("http://{}", listener.local_addr().unwrap());
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = seen.clone();
+        tokio::spawn(async move {
+            while let Ok((mut sock, _)) = listener.accept().await {
+                let mut buf = Vec::new();
+                let mut chunk = [0u8; 4096];
+                let head_end = loop {
+                    let n = sock.read(&mut chunk).await.unwrap_or(0);
+                    buf.extend_from_slice(&chunk[..n]);
+                    if let Some(i) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                        break i + 4;
+                    }
+                    if n == 0 {
+                        break buf.len();
+                    }
+                };
+                let head = String::from_utf8_lossy(&buf[..head_end]).to_string();
+                let length = head
+                    .lines()
+                    .find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse().unwrap_or(0)))
+                    .unwrap_or(0);
+                while buf.len() < head_end + length {
+                    let n = sock.read(&mut chunk).await.unwrap_or(0);
+                    if n == 0 {
+                        break;
+                    }
+                    buf.extend_from_slice(&chunk[..n]);
+                }
+                let mut parts = head.split_whitespace();
+                let (method, target) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+                let path = target.split('?').next().unwrap_or_default();
+                log.lock().unwrap().push(format!("{} {}", method, path));
+                let body = reply(method, path);
+                let resp = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = sock.write_all(resp.as_bytes()).await;
+            }
+        });
+        (origin, seen)
+    }
+
+    fn posts(seen: &Mutex<Vec<String>>) -> Vec<String> {
+        seen.lock().unwrap().iter().filter(|r| r.starts_with("POST")).cloned().collect()
+    }
+
+    /// `--config` 可能指向用户自己的目录，清除时只删本工具的文件
+    #[test]
+    fn clear_local_data_keeps_foreign_files() {
+        let _guard = CONFIG_FILES.lock().unwrap_or_else(|e| e.into_inner());
+        let mut client = test_client(None, None);
+        let dir = BiliClient::config_dir();
+        fs::create_dir_all(dir.join("stats")).unwrap();
+        fs::write(dir.join("notes.txt"), "keep").unwrap();
+        fs::write(dir.join("stats").join("1.jsonl"), "{}").unwrap();
+        BiliClient::save_settings(&Settings::default()).unwrap();
+        client.clear_local_data(false).unwrap();
+        assert!(dir.join("notes.txt").exists());
+        assert!(!dir.join("stats").exists());
+        assert!(!BiliClient::settings_file_path().exists());
+    }
+
+    #[test]
+    fn unparsable_settings_are_backed_up() {
+        let _guard = CONFIG_FILES.lock().unwrap_or_else(|e| e.into_inner());
+        test_client(None, None);
+        let path = BiliClient::settings_file_path();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "stats_interval_secs = [").unwrap();
+        let settings = BiliClient::load_settings();
+        assert_eq!(settings.stats_interval_secs, Settings::default().stats_interval_secs);
+        assert_eq!(fs::read_to_string(path.with_extension("toml.bak")).unwrap(), "stats_interval_secs = [");
+    }
+
+    #[test]
+    fn non_json_body_is_an_error_with_snippet() {
+        let err = parse_json_body(StatusCode::OK, "<html><body>访问受限</body></html>").unwrap_err().to_string();
+        assert!(err.contains("HTTP 200") && err.contains("访问受限"), "{}", err);
+        assert!(parse_json_body(StatusCode::OK, "").is_err());
+        assert_eq!(parse_json_body(StatusCode::OK, r#"{"code":0}"#).unwrap()["code"], 0);
+    }
+
+    #[test]
+    fn retry_after_accepts_seconds_and_http_date() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1445412480); // 2015-10-21 07:28:00 UTC
+        assert_eq!(parse_retry_after(" 120 ", now), Some(Duration::from_secs(120)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:30 GMT", now), Some(Duration::from_secs(30)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:00:00 GMT", now), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[test]
+    fn retry_delay_prefers_retry_after_header() {
+        let backoff = Duration::from_millis(500);
+        let mut headers = reqwest::header::HeaderMap::new();
+        assert_eq!(retry_delay(&headers, backoff, 2), Duration::from_secs(1));
+        headers.insert(reqwest::header::RETRY_AFTER, "3".parse().unwrap());
+        assert_eq!(retry_delay(&headers, backoff, 2), Duration::from_secs(3));
+        headers.insert(reqwest::header::RETRY_AFTER, "86400".parse().unwrap());
+        assert_eq!(retry_delay(&headers, backoff, 2), MAX_RETRY_AFTER);
+    }
+
+    /// 本地起一个先返回 412 + Retry-After: 1 再返回成功的服务，确认重试前确实等待了（暂停时钟，不真的睡 1 秒）
+    #[tokio::test(start_paused = true)]
+    async fn post_retry_waits_for_retry_after() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let replies = [
+                "HTTP/1.1 412 Precondition Failed\r\nRetry-After: 1\r\nContent-Type: application/json\r\nContent-Length: 26\r\nConnection: close\r\n\r\n{\"code\":-412,\"message\":\"\"}",
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 23\r\nConnection: close\r\n\r\n{\"code\":0,\"message\":\"\"}",
+            ];
+            for reply in replies {
+                let (mut sock, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 4096];
+                let _ = sock.read(&mut buf).await.unwrap();
+                sock.write_all(reply.as_bytes()).await.unwrap();
+            }
+        });
+        let client = test_client(None, None);
+        let start = tokio::time::Instant::now();
+        let value = client.post_form_retry(&format!("http://{}/", addr), &BTreeMap::new()).await.unwrap();
+        assert_eq!(value["code"], 0);
+        assert!(start.elapsed() >= Duration::from_secs(1));
+        server.await.unwrap();
+    }
+
+    /// 主路线的代理连不上时改走备用的直连，之后一段时间内直接走备用路线
+    #[tokio::test]
+    async fn unreachable_proxy_falls_back_to_direct() {
+        let (origin, seen) = mock_server(|_, _| room_reply(1)).await;
+        let dead = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let mut client = test_client(Some(&origin), None);
+        let proxied = Client::builder().proxy(reqwest::Proxy::all(format!("http://{}", dead)).unwrap());
+        client.client = BiliClient::http_client(proxied, &client.jar, None).unwrap();
+        client.failover = BiliClient::failover(Some(None), proxy::route_label(Some(&format!("http://{}", dead))), &client.jar, None).unwrap();
+
+        let url = "https://api.live.bilibili.com/room/v1/Room/get_info";
+        assert_eq!(client.execute(ApiRequest::get(url)).await.unwrap()["code"], 0);
+        assert!(client.failover.as_ref().unwrap().prefers_backup());
+        assert_eq!(client.execute(ApiRequest::get(url)).await.unwrap()["code"], 0);
+        assert_eq!(seen.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn start_and_stop_are_idempotent() {
+        assert!(!needs_transition(Some(1), true));
+        assert!(needs_transition(Some(0), true));
+        assert!(needs_transition(Some(2), true));
+        assert!(needs_transition(Some(1), false));
+        assert!(!needs_transition(Some(0), false));
+        assert!(!needs_transition(Some(2), false));
+        assert!(needs_transition(None, true));
+        assert!(needs_transition(None, false));
+    }
+
+    fn room_reply(live_status: i32) -> String {
+        format!(r#"{{"code":0,"message":"","data":{{"room_id":1000,"short_id":0,"live_status":{},"title":"t","live_time":"0000-00-00 00:00:00"}}}}"#, live_status)
+    }
+
+    /// 已在直播时再开播、未开播时再关播都不发 POST
+    #[tokio::test]
+    async fn repeated_start_and_stop_skip_the_post() {
+        let (origin, seen) = mock_server(|_, path| match path {
+            "/room/v1/Room/get_info" => room_reply(1),
+            "/xlive/app-blink/v1/live/getWebUpStreamAddr" => {
+                r#"{"code":0,"data":{"addr":{"addr":"rtmp://push.test/live/","code":"?key=k"}}}"#.into()
+            }
+            _ => r#"{"code":-1,"message":"unexpected"}"#.into(),
+        })
+        .await;
+        let client = test_client(Some(&origin), Some(test_auth()));
+        let start = client.start_live(1000, 1, None).await.unwrap();
+        assert!(start.already_live);
+        assert!(posts(&seen).is_empty(), "{:?}", seen.lock().unwrap());
+
+        let (origin, seen) = mock_server(|_, path| match path {
+            "/room/v1/Room/get_info" => room_reply(0),
+            _ => r#"{"code":-1,"message":"unexpected"}"#.into(),
+        })
+        .await;
+        let client = test_client(Some(&origin), Some(test_auth()));
+        assert!(!client.stop_live(1000).await.unwrap());
+        assert!(posts(&seen).is_empty(), "{:?}", seen.lock().unwrap());
+    }
+
+    /// 开播没有返回推流地址时，即使查到的状态仍是未开播，回滚也要真正发出关播请求
+    #[tokio::test]
+    async fn failed_start_rollback_always_sends_stop() {
+        let (origin, seen) = mock_server(|_, path| match path {
+            "/room/v1/Room/get_info" => room_reply(0),
+            "/room/v1/Room/startLive" => r#"{"code":0,"message":"","data":{}}"#.into(),
+            "/room/v1/Room/stopLive" => r#"{"code":0,"message":""}"#.into(),
+            _ => r#"{"code":-1,"message":"unexpected"}"#.into(),
+        })
+        .await;
+        let client = test_client(Some(&origin), Some(test_auth()));
+        let err = client.start_live(1000, 1, None).await.unwrap_err().to_string();
+        assert!(err.contains("已自动关播回滚"), "{}", err);
+        assert_eq!(posts(&seen), vec!["POST /room/v1/Room/startLive", "POST /room/v1/Room/stopLive"]);
+    }
+
+    #[test]
+    fn current_stream_line_requires_addr_and_code() {
+        let data: UpStreamAddrResp = serde_json::from_value(serde_json::json!({
+            "addr": { "addr": "rtmp://live-push.bilivideo.com/live-bvc/", "code": "?streamname=live_1&key=k" },
+            "line": []
+        }))
+        .unwrap();
+        let line = BiliClient::stream_line(data.addr, StreamProtocol::Rtmp).unwrap();
+        assert_eq!(line.full_url(), "rtmp://live-push.bilivideo.com/live-bvc/?streamname=live_1&key=k");
+        let empty: UpStreamAddrResp = serde_json::from_value(serde_json::json!({ "addr": { "addr": "", "code": "" } })).unwrap();
+        assert_eq!(BiliClient::stream_line(empty.addr, StreamProtocol::Rtmp), None);
+        let missing: UpStreamAddrResp = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert_eq!(BiliClient::stream_line(missing.addr, StreamProtocol::Rtmp), None);
+    }
+
+    #[test]
+    fn short_and_real_room_ids_map_to_real() {
+        let mut ids = RoomIds::default();
+        ids.record(21452505, 6);
+        ids.record(100, 0);
+        ids.record(0, 7);
+        assert_eq!(ids.get(6), Some(21452505));
+        assert_eq!(ids.get(21452505), Some(21452505));
+        assert_eq!(ids.get(100), Some(100));
+        assert_eq!(ids.get(0), None);
+        assert_eq!(ids.get(7), None);
+    }
+
+    #[test]
+    fn login_cache_expires_after_max_age() {
+        let checked_at = Instant::now();
+        let cache = LoginCache { state: LoginState::LoggedIn, checked_at };
+        let max_age = Duration::from_secs(60);
+        assert!(matches!(cache.fresh(max_age, checked_at + Duration::from_secs(30)), Some(LoginState::LoggedIn)));
+        assert!(cache.fresh(max_age, checked_at + Duration::from_secs(61)).is_none());
+    }
+
+    #[test]
+    fn ua_sticks_after_success_until_repeated_blocks() {
+        let mut picker = UaPicker::default();
+        let (a, b) = (USER_AGENTS[0], USER_AGENTS[1]);
+        picker.report(a, false);
+        assert_eq!(picker.pick(), a);
+        // 其它 UA 被拦截不影响粘住的 UA
+        picker.report(b, true);
+        assert_eq!(picker.sticky, Some(a));
+        picker.report(a, true);
+        assert_eq!(picker.pick(), a);
+        // 中途成功一次会重新计数
+        picker.report(a, false);
+        picker.report(a, true);
+        assert_eq!(picker.sticky, Some(a));
+        picker.report(a, true);
+        assert_eq!(picker.sticky, None);
+        picker.report(b, false);
+        assert_eq!(picker.pick(), b);
+    }
+
+    #[test]
+    fn site_origin_matches_api_host() {
+        assert_eq!(site_origin("https://api.live.bilibili.com/room/v1/Room/startLive"), Some("https://live.bilibili.com"));
+        assert_eq!(site_origin("https://api.bilibili.com/x/web-interface/nav"), Some("https://www.bilibili.com"));
+        assert_eq!(site_origin("https://i0.hdslb.com/bfs/live/cover.jpg"), None);
+        assert_eq!(site_origin("https://evil.api.live.bilibili.com.example/"), None);
+    }
+
+    #[test]
+    fn image_cdn_gets_site_referer() {
+        assert_eq!(image_referer("https://i0.hdslb.com/bfs/live/cover.jpg"), Some("https://www.bilibili.com/"));
+        assert_eq!(image_referer("https://i0.biliimg.com/a.png"), Some("https://www.bilibili.com/"));
+        assert_eq!(image_referer("https://nothdslb.com/a.png"), None);
+        assert_eq!(image_referer("https://example.com/a.png"), None);
+    }
+
+    #[test]
+    fn image_url_is_upgraded_to_https() {
+        assert_eq!(image_url("//i0.hdslb.com/bfs/face/a.jpg"), "https://i0.hdslb.com/bfs/face/a.jpg");
+        assert_eq!(image_url("http://i1.hdslb.com/bfs/live/b.jpg"), "https://i1.hdslb.com/bfs/live/b.jpg");
+        assert_eq!(image_url("http://example.com/c.jpg"), "http://example.com/c.jpg");
+        assert_eq!(image_url("https://i2.hdslb.com/d.png"), "https://i2.hdslb.com/d.png");
+    }
+
+    #[test]
+    fn stats_file_is_named_after_live_start() {
+        // 2024-01-02 03:04:05 +08:00
+        assert_eq!(stats_file_name(42, 1704135845), "42_20240102-030405.jsonl");
+        assert!(stats_file_name(42, 0).starts_with("42_"));
+    }
+
+    #[test]
+    fn ip_family_maps_to_unspecified_bind_address() {
+        let v4 = ClientOptions { ip_family: IpFamily::V4Only, ..ClientOptions::default() };
+        assert_eq!(v4.bind_address().unwrap(), Some(Ipv4Addr::UNSPECIFIED.into()));
+        let v6 = ClientOptions { ip_family: IpFamily::V6Only, ..ClientOptions::default() };
+        assert_eq!(v6.bind_address().unwrap(), Some(Ipv6Addr::UNSPECIFIED.into()));
+        assert_eq!(ClientOptions::default().bind_address().unwrap(), None);
+    }
+
+    #[test]
+    fn local_address_conflicting_with_family_is_rejected() {
+        let opts = ClientOptions { local_address: Some(Ipv6Addr::LOCALHOST.into()), ip_family: IpFamily::V4Only, ..ClientOptions::default() };
+        assert!(opts.bind_address().is_err());
+        let opts = ClientOptions { local_address: Some(Ipv4Addr::LOCALHOST.into()), ip_family: IpFamily::V6Only, ..ClientOptions::default() };
+        assert!(opts.bind_address().is_err());
+    }
+
+    #[test]
+    fn merge_extra_keeps_reserved_fields() {
+        let mut params: BTreeMap<&str, String> = BTreeMap::new();
+        params.insert("room_id", "100".to_string());
+        params.insert("csrf", "token".to_string());
+        let extra: BTreeMap<String, String> = [("room_id", "1"), ("csrf", "x"), ("csrf_token", "x"), ("activity_id", "42")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        BiliClient::merge_extra(&mut params, Some(&extra));
+        assert_eq!(params["room_id"], "100");
+        assert_eq!(params["csrf"], "token");
+        assert!(!params.contains_key("csrf_token"));
+        assert_eq!(params["activity_id"], "42");
+    }
+
+    #[test]
+    fn metrics_count_calls_blocks_and_failures() {
+        let ok = serde_json::json!({ "code": 0 });
+        let blocked = serde_json::json!({ "code": -412 });
+        record_metric("https://metrics.test/x?a=1", Duration::from_millis(10), Some(StatusCode::OK), Some(&ok));
+        record_metric("https://metrics.test/x?a=2", Duration::from_millis(30), Some(StatusCode::OK), Some(&blocked));
+        record_metric("https://metrics.test/x", Duration::from_millis(20), None, None);
+        let m = metrics_snapshot().into_iter().find(|m| m.endpoint == "metrics.test/x").unwrap();
+        assert_eq!((m.calls, m.blocked, m.failures, m.avg_ms()), (3, 1, 2, 20));
+    }
+
+    #[test]
+    fn exchanges_mask_secrets_and_keep_raw_response() {
+        set_capture_exchanges(true);
+        let mut params: BTreeMap<&str, String> = BTreeMap::new();
+        params.insert("csrf", "secret".into());
+        params.insert("title", "标题".into());
+        record_exchange("POST", "https://dev.test/x?access_key=k&a=1", Some(&params), Ok((StatusCode::OK, r#"{"code":0}"#)));
+        let e = last_exchange("dev.test/x").unwrap();
+        assert_eq!(e.url, "https://dev.test/x?access_key=***&a=1");
+        assert_eq!(e.params, vec![("csrf".to_string(), "***".to_string()), ("title".to_string(), "标题".to_string())]);
+        assert_eq!((e.status, e.response.as_str()), (Some(200), "{\n  \"code\": 0\n}"));
+        record_exchange("GET", "https://dev.test/y", None, Err(&BiliError::Other("连接失败".into())));
+        let e = last_exchange("dev.test/y").unwrap();
+        assert_eq!((e.status, e.response.as_str()), (None, "连接失败"));
+        assert!(last_exchange("dev.test/z").is_none());
+
+        let mut params: BTreeMap<&str, String> = BTreeMap::new();
+        params.insert("refresh_token", "r".into());
+        let body = r#"{"code":0,"data":{"url":"https://passport.test/cross?DedeUserID=1&SESSDATA=s&bili_jct=j","refresh_token":"r",
+            "cookie_info":{"cookies":[{"name":"SESSDATA","value":"s"},{"name":"buvid3","value":"b"}]}}}"#;
+        record_exchange("POST", "https://dev.test/login", Some(&params), Ok((StatusCode::OK, body)));
+        let e = last_exchange("dev.test/login").unwrap();
+        set_capture_exchanges(false);
+        assert_eq!(e.params, vec![("refresh_token".to_string(), "***".to_string())]);
+        assert!(!e.response.contains("\"s\"") && !e.response.contains("=s") && !e.response.contains("\"r\""), "{}", e.response);
+        assert!(e.response.contains("DedeUserID=1&SESSDATA=***&bili_jct=***"), "{}", e.response);
+        assert!(e.response.contains("\"b\""), "{}", e.response);
+    }
+
+    #[test]
+    fn dry_run_masks_csrf_and_simulates_stream_line() {
+        let mut params: BTreeMap<&str, String> = BTreeMap::new();
+        params.insert("csrf", "secret".into());
+        params.insert("room_id", "1".into());
+        assert_eq!(dry_run_line("https://a.test/x", &params), "[dry-run] POST https://a.test/x csrf=*** room_id=1");
+        let resp: BiliResp<StartLiveResp> = BiliResp::from_value(dry_run_value("start_live")).unwrap();
+        assert!(BiliClient::valid_stream_lines(resp.data).is_some());
+        let resp: BiliResp<CreateRoomResp> = BiliResp::from_value(dry_run_value("create_room")).unwrap();
+        assert_eq!(resp.data.map(|d| d.room_id), Some(0));
+    }
+
+    #[test]
+    fn start_live_without_stream_address_is_invalid() {
+        let parse = |v: serde_json::Value| serde_json::from_value::<StartLiveResp>(v).ok();
+        assert!(BiliClient::valid_stream_lines(None).is_none());
+        assert!(BiliClient::valid_stream_lines(parse(serde_json::json!({ "change": 1 }))).is_none());
+        assert!(BiliClient::valid_stream_lines(parse(serde_json::json!({ "rtmp": { "addr": "", "code": "" } }))).is_none());
+        let lines = BiliClient::valid_stream_lines(parse(serde_json::json!({ "rtmp": { "addr": "rtmp://a/", "code": "k" } }))).unwrap();
+        assert_eq!(lines.rtmp.unwrap().full_url(), "rtmp://a/k");
+    }
+
+    #[test]
+    fn rtmp_and_srt_are_validated_separately() {
+        let parse = |v: serde_json::Value| serde_json::from_value::<StartLiveResp>(v).ok();
+        let lines = BiliClient::valid_stream_lines(parse(serde_json::json!({
+            "rtmp": { "addr": "", "code": "" },
+            "srt": { "addr": "srt://a:1937", "code": "#!::r=live-bvc/live_1" }
+        })))
+        .unwrap();
+        assert_eq!((lines.rtmp.is_none(), lines.srt.is_some()), (true, true));
+        // 协议头对不上的地址不可用
+        let swapped = parse(serde_json::json!({ "rtmp": { "addr": "srt://a:1937", "code": "k" }, "srt": { "addr": "rtmp://a/", "code": "k" } }));
+        assert!(BiliClient::valid_stream_lines(swapped).is_none());
+    }
+
+    #[test]
+    fn cover_must_be_jpeg_or_png() {
+        assert_eq!(image_mime(&[0xFF, 0xD8, 0xFF, 0xE0, 0x00]), Some("image/jpeg"));
+        assert_eq!(image_mime(b"\x89PNG\r\n\x1a\n\x00\x00"), Some("image/png"));
+        assert_eq!(image_mime(b"GIF89a"), None);
+        assert_eq!(image_mime(b""), None);
+    }
+
+    #[test]
+    fn qr_url_must_be_official_https() {
+        assert!(is_trusted_qr_url("https://account.bilibili.com/h5/account-h5/auth/scan-web?qrcode_key=abc"));
+        assert!(is_trusted_qr_url("https://passport.bilibili.com/x/passport-tv-login/h5/qrcode/auth?auth_code=abc"));
+        assert!(!is_trusted_qr_url("http://passport.bilibili.com/qrcode"));
+        assert!(!is_trusted_qr_url("https://bilibili.com.evil.example/qrcode"));
+        assert!(!is_trusted_qr_url("https://evilbilibili.com/qrcode"));
+        assert!(!is_trusted_qr_url("not a url"));
+    }
+}
@@ -0,0 +1,94 @@
+//! 房管与大航海成员：拉取后写入磁盘缓存，界面先展示缓存再在后台刷新。
+
+use crate::models::{AdminItem, AdminListResp, BiliError, Context, GuardItem, GuardListResp};
+use crate::{BiliClient, Result};
+use domain::{Cached, GuardMember, RoomAdmin, RoomMembers};
+use std::collections::HashSet;
+
+const CACHE_NAME: &str = "members";
+/// 最多翻页数，成员特别多时只取前面的部分
+const MAX_PAGES: u32 = 50;
+const GUARD_PAGE_SIZE: u32 = 30;
+
+fn admin(item: AdminItem) -> RoomAdmin {
+    RoomAdmin { uid: item.uid, name: item.uname, appointed_at: item.ctime }
+}
+
+/// 合并各页的成员，同一 uid 只保留第一次出现的，按总督、提督、舰长排序
+pub(crate) fn collect_guards(items: Vec<GuardItem>) -> Vec<GuardMember> {
+    let mut seen = HashSet::new();
+    let mut guards: Vec<GuardMember> = items
+        .into_iter()
+        .filter(|item| seen.insert(item.uid))
+        .map(|item| GuardMember { uid: item.uid, name: item.username, level: item.guard_level, medal_level: item.medal_info.medal_level })
+        .collect();
+    guards.sort_by_key(|g| g.level);
+    guards
+}
+
+impl BiliClient {
+    /// 当前账号任命的全部房管
+    pub async fn get_room_admins(&self) -> Result<Vec<RoomAdmin>> {
+        let mut admins = Vec::new();
+        for page in 1..=MAX_PAGES {
+            let url = format!("https://api.live.bilibili.com/xlive/web-ucenter/v1/roomAdmin/get_by_anchor?page={}", page);
+            let resp: AdminListResp = self.get_data(&url).await.context("获取房管列表失败")?;
+            admins.extend(resp.data.into_iter().map(admin));
+            if page >= resp.page.total_page {
+                break;
+            }
+        }
+        Ok(admins)
+    }
+
+    /// 当前账号直播间的大航海成员，第一页的前三名在 `top3` 里
+    pub async fn get_guards(&self, room_id: i64) -> Result<Vec<GuardMember>> {
+        let room_id = self.resolve_real_room_id(room_id).await?;
+        let ruid = self.get_cookie_value("DedeUserID").ok_or(BiliError::NotLoggedIn("DedeUserID"))?;
+        let mut items = Vec::new();
+        for page in 1..=MAX_PAGES {
+            let url = format!(
+                "https://api.live.bilibili.com/xlive/app-room/v2/guardTab/topList?roomid={}&ruid={}&page={}&page_size={}",
+                room_id, ruid, page, GUARD_PAGE_SIZE
+            );
+            let resp: GuardListResp = self.get_data(&url).await.context("获取大航海列表失败")?;
+            items.extend(resp.top3);
+            items.extend(resp.list);
+            if page >= resp.info.page {
+                break;
+            }
+        }
+        Ok(collect_guards(items))
+    }
+
+    /// 拉取房管与大航海成员并写入缓存
+    pub async fn refresh_room_members(&self, room_id: i64) -> Result<Cached<RoomMembers>> {
+        let (admins, guards) = tokio::try_join!(self.get_room_admins(), self.get_guards(room_id))?;
+        Ok(Self::store_cached(CACHE_NAME, RoomMembers { admins, guards }))
+    }
+
+    /// 上次缓存的房管与大航海成员，离线时也能查看
+    pub fn load_room_members_cache() -> Option<Cached<RoomMembers>> {
+        Self::load_cached(CACHE_NAME)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guards_merge_top3_and_sort_by_level() {
+        let page: GuardListResp = serde_json::from_str(
+            r#"{"info": {"num": 3, "page": 1, "now": 1},
+                "top3": [{"uid": 2, "username": "b", "guard_level": 3, "medal_info": {"medal_level": 21}},
+                         {"uid": 1, "username": "a", "guard_level": 1}],
+                "list": [{"uid": 3, "username": "c", "guard_level": 2}, {"uid": 2, "username": "b", "guard_level": 3}]}"#,
+        )
+        .unwrap();
+        assert_eq!(page.info.page, 1);
+        let guards = collect_guards(page.top3.into_iter().chain(page.list).collect());
+        let summary: Vec<_> = guards.iter().map(|g| (g.uid, g.level_name(), g.medal_level)).collect();
+        assert_eq!(summary, vec![(1, "总督", 0), (3, "提督", 0), (2, "舰长", 21)]);
+    }
+}
@@ -0,0 +1,759 @@
+//! 接口响应的强类型模型。外层统一为 [`BiliResp`]，`data` 的结构按接口分别定义。
+
+use serde::de::{self, DeserializeOwned, Deserializer};
+use serde::Deserialize;
+use serde_json::Value;
+use domain::FaceAuthInfo;
+use tokio_tungstenite::tungstenite::Error as WsError;
+
+/// B 站接口通用的外层结构
+#[derive(Debug)]
+pub struct BiliResp<T> {
+    pub code: i64,
+    pub message: String,
+    pub data: Option<T>,
+    /// code 非 0 时原样保留的 data，部分错误（如 60024 人脸认证）在其中附带额外信息
+    pub error_data: Option<Value>,
+}
+
+impl<T: DeserializeOwned> BiliResp<T> {
+    /// 解析外层结构。只有 code 为 0 时才按 `T` 解析 data，出错时 data 的形状往往与正常响应不同。
+    /// 直播相关接口常同时带 `msg` 与 `message`，取非空的那个。
+    pub fn from_value(mut value: Value) -> serde_json::Result<Self> {
+        let code = value
+            .get("code")
+            .and_then(Value::as_i64)
+            .ok_or_else(|| <serde_json::Error as de::Error>::missing_field("code"))?;
+        let message = ["message", "msg"]
+            .iter()
+            .filter_map(|key| value.get(*key)?.as_str())
+            .find(|m| !m.is_empty())
+            .unwrap_or("")
+            .to_string();
+        let (data, error_data) = match value.get_mut("data").map(Value::take) {
+            Some(data) if code == 0 && !data.is_null() => (Some(serde_json::from_value(data)?), None),
+            Some(data) if code != 0 && !data.is_null() => (None, Some(data)),
+            _ => (None, None),
+        };
+        Ok(Self { code, message, data, error_data })
+    }
+}
+
+/// 本 crate 对外返回的错误
+#[derive(Debug, thiserror::Error)]
+pub enum BiliError {
+    #[error("接口返回错误 {code}: {message}")]
+    ApiError { code: i64, message: String },
+    #[error("接口响应缺少 data")]
+    MissingData,
+    #[error("开播需要人脸认证，请使用 B 站 App 扫码完成认证")]
+    FaceAuthRequired(FaceAuthInfo),
+    #[error("未登录或登录已失效（缺少 {0} cookie）")]
+    NotLoggedIn(&'static str),
+    /// 导入的 cookie 未通过登录检查，本地原有的登录信息保持不变
+    #[error("导入的 cookie 已失效，原有登录信息未改动")]
+    ImportedLoginInvalid,
+    /// 接口确认当前 cookie 没有登录
+    #[error("用户未登录")]
+    LoggedOut,
+    /// 登录态来自环境变量时拒绝读写本地凭据，参数为被拒绝的操作
+    #[error("当前使用环境变量中的登录态，{0}")]
+    EnvLogin(&'static str),
+    /// 进程级的设置（配置目录、账号）只能指定一次
+    #[error("{0}已设置，不能重复指定")]
+    AlreadySet(&'static str),
+    #[error("无法绑定本地地址 {addr}: {source}")]
+    Bind { addr: std::net::IpAddr, source: std::io::Error },
+    /// 等待人脸认证、直播上线等超时
+    #[error("{0}")]
+    Timeout(String),
+    #[error("{0}")]
+    Blocked(String),
+    #[error("{0}")]
+    InvalidArgument(String),
+    /// 长任务被 [`CancellationToken`](crate::CancellationToken) 取消
+    #[error("操作已取消")]
+    Cancelled,
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    TomlDe(#[from] toml::de::Error),
+    #[error(transparent)]
+    TomlSer(#[from] toml::ser::Error),
+    /// 弹幕 WebSocket 的错误，体积较大所以装箱
+    #[error(transparent)]
+    WebSocket(Box<WsError>),
+    #[error("{0}")]
+    Other(String),
+    /// 附加了说明的错误，`{:#}` 打印时会带上原因
+    #[error("{context}")]
+    Context { context: String, source: Box<BiliError> },
+}
+
+impl From<WsError> for BiliError {
+    fn from(err: WsError) -> Self {
+        BiliError::WebSocket(Box::new(err))
+    }
+}
+
+/// 给错误附加说明，用法同 anyhow 的 `Context`
+pub(crate) trait Context<T> {
+    fn context(self, context: impl Into<String>) -> Result<T, BiliError>;
+}
+
+impl<T, E: Into<BiliError>> Context<T> for Result<T, E> {
+    fn context(self, context: impl Into<String>) -> Result<T, BiliError> {
+        self.map_err(|e| BiliError::Context { context: context.into(), source: Box::new(e.into()) })
+    }
+}
+
+/// 错误的大类，供调用方（如 CLI 退出码）分支处理
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    NotLoggedIn,
+    Network,
+    Blocked,
+    InvalidArgument,
+    Other,
+}
+
+/// 未登录 / 登录失效时接口返回的错误码
+const NOT_LOGGED_IN_CODE: i64 = -101;
+/// 风控拦截时接口返回的错误码
+const BLOCKED_CODE: i64 = -412;
+
+/// 连接层面的 IO 错误（弹幕 WebSocket 的 TCP 连接），读写本地文件的错误不算网络错误
+fn is_connection_error(e: &std::io::Error) -> bool {
+    use std::io::ErrorKind::*;
+    matches!(
+        e.kind(),
+        TimedOut | ConnectionRefused | ConnectionReset | ConnectionAborted | NotConnected | BrokenPipe | UnexpectedEof | AddrNotAvailable
+    )
+}
+
+impl BiliError {
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self, BiliError::Cancelled)
+    }
+
+    /// 接口明确返回的错误，区别于网络、解析等偶发错误。风控拦截（-412 等）多为暂时的，不算在内
+    pub fn is_api_error(&self) -> bool {
+        self.kind() != ErrorKind::Blocked
+            && matches!(
+                self.root(),
+                BiliError::ApiError { .. }
+                    | BiliError::MissingData
+                    | BiliError::FaceAuthRequired(_)
+                    | BiliError::NotLoggedIn(_)
+                    | BiliError::InvalidArgument(_)
+            )
+    }
+
+    /// 第三方库的其它错误只保留描述
+    pub(crate) fn other(err: impl std::fmt::Display) -> Self {
+        BiliError::Other(err.to_string())
+    }
+
+    /// 去掉 [`BiliError::Context`] 包装后的原始错误
+    pub fn root(&self) -> &BiliError {
+        match self {
+            BiliError::Context { source, .. } => source.root(),
+            e => e,
+        }
+    }
+
+    /// 错误的大类
+    pub fn kind(&self) -> ErrorKind {
+        match self.root() {
+            BiliError::NotLoggedIn(_) | BiliError::ImportedLoginInvalid | BiliError::LoggedOut => ErrorKind::NotLoggedIn,
+            BiliError::ApiError { code: NOT_LOGGED_IN_CODE, .. } => ErrorKind::NotLoggedIn,
+            BiliError::Blocked(_) | BiliError::ApiError { code: BLOCKED_CODE, .. } => ErrorKind::Blocked,
+            BiliError::InvalidArgument(_) | BiliError::Bind { .. } => ErrorKind::InvalidArgument,
+            BiliError::Http(_) => ErrorKind::Network,
+            BiliError::Io(e) if is_connection_error(e) => ErrorKind::Network,
+            BiliError::WebSocket(e) => match &**e {
+                WsError::Io(e) if is_connection_error(e) => ErrorKind::Network,
+                WsError::ConnectionClosed | WsError::AlreadyClosed => ErrorKind::Network,
+                _ => ErrorKind::Other,
+            },
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+/// 沿错误链找到第一个可识别的错误并归类，都不认识时为 [`ErrorKind::Other`]。
+/// 调用方可以把 [`BiliError`] 包进自己的错误类型（如 anyhow）后再传进来
+pub fn error_kind(err: &(dyn std::error::Error + 'static)) -> ErrorKind {
+    for cause in std::iter::successors(Some(err), |e| e.source()) {
+        if let Some(e) = cause.downcast_ref::<BiliError>() {
+            return e.kind();
+        }
+        if cause.is::<reqwest::Error>() || cause.downcast_ref::<std::io::Error>().is_some_and(is_connection_error) {
+            return ErrorKind::Network;
+        }
+    }
+    ErrorKind::Other
+}
+
+/// 开播要求人脸认证时返回的错误码
+pub(crate) const FACE_AUTH_CODE: i64 = 60024;
+
+/// 从开播响应中取出人脸认证信息，不是 60024 或缺少二维码地址时返回 None
+pub(crate) fn face_auth_info<T>(resp: &BiliResp<T>, room_id: i64) -> Option<FaceAuthInfo> {
+    if resp.code != FACE_AUTH_CODE {
+        return None;
+    }
+    let qr_url = resp.error_data.as_ref()?["qr"].as_str().filter(|u| !u.is_empty())?;
+    Some(FaceAuthInfo { room_id, qr_url: qr_url.to_string() })
+}
+
+/// code 不为 0 时返回 [`BiliError::ApiError`]，data 缺失时返回 [`BiliError::MissingData`]
+pub fn unwrap_data<T>(resp: BiliResp<T>) -> Result<T, BiliError> {
+    ensure_ok(resp)?.ok_or(BiliError::MissingData)
+}
+
+/// 只检查 code，用于不关心 data 的接口
+pub fn ensure_ok<T>(resp: BiliResp<T>) -> Result<Option<T>, BiliError> {
+    if resp.code != 0 {
+        return Err(BiliError::ApiError { code: resp.code, message: resp.message });
+    }
+    Ok(resp.data)
+}
+
+/// 部分接口的数字字段以字符串返回
+fn num_or_str<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NumOrStr {
+        Num(i64),
+        Str(String),
+    }
+    match NumOrStr::deserialize(deserializer)? {
+        NumOrStr::Num(n) => Ok(n),
+        NumOrStr::Str(s) => s.parse().map_err(de::Error::custom),
+    }
+}
+
+/// `x/web-interface/nav`
+#[derive(Debug, Deserialize)]
+pub(crate) struct NavResp {
+    #[serde(rename = "isLogin")]
+    pub is_login: bool,
+    #[serde(default)]
+    pub mid: u64,
+    #[serde(default)]
+    pub uname: String,
+    #[serde(default)]
+    pub face: String,
+}
+
+/// `x/space/acc/info`
+#[derive(Debug, Deserialize)]
+pub(crate) struct SpaceInfoResp {
+    #[serde(default)]
+    pub live_room: Option<SpaceLiveRoom>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct SpaceLiveRoom {
+    #[serde(rename = "roomStatus", default)]
+    pub room_status: i32,
+    #[serde(rename = "liveStatus", default)]
+    pub live_status: i32,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub cover: String,
+    #[serde(default)]
+    pub roomid: i64,
+}
+
+/// `passport-login/web/qrcode/generate`
+#[derive(Debug, Deserialize)]
+pub(crate) struct QrGenerateResp {
+    pub url: String,
+    pub qrcode_key: String,
+}
+
+/// `passport-login/web/qrcode/poll`，扫码状态在 data 内的 code 里
+#[derive(Debug, Deserialize)]
+pub(crate) struct QrPollResp {
+    pub code: i64,
+    #[serde(default)]
+    pub refresh_token: String,
+}
+
+/// `x/frontend/finger/spi`
+#[derive(Debug, Deserialize)]
+pub(crate) struct FingerSpiResp {
+    #[serde(default)]
+    pub b_3: String,
+    #[serde(default)]
+    pub b_4: String,
+}
+
+/// `room/v1/Room/get_info`
+#[derive(Debug, Deserialize)]
+pub(crate) struct RoomInfoResp {
+    pub room_id: u64,
+    /// 主播 UID
+    #[serde(default)]
+    pub uid: u64,
+    #[serde(default)]
+    pub short_id: u64,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub user_cover: String,
+    #[serde(default)]
+    pub area_id: u64,
+    #[serde(default)]
+    pub area_name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub live_status: i32,
+    #[serde(default)]
+    pub live_time: String,
+    /// 人气值
+    #[serde(default)]
+    pub online: u64,
+    /// 非 0 表示处于 PK 中
+    #[serde(default)]
+    pub pk_status: i64,
+    #[serde(default)]
+    pub battle_id: i64,
+}
+
+/// `room/v1/Room/update`，无需审核时 data 可能是空数组，调用方需先判断是否为对象
+#[derive(Debug, Deserialize)]
+pub(crate) struct RoomUpdateResp {
+    #[serde(default)]
+    pub audit_info: Option<AuditInfoResp>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct AuditInfoResp {
+    #[serde(default)]
+    pub audit_title_status: i32,
+    #[serde(default)]
+    pub audit_title_reason: String,
+}
+
+/// `preLive/CreateRoom`
+#[derive(Debug, Deserialize)]
+pub(crate) struct CreateRoomResp {
+    #[serde(rename = "roomID", deserialize_with = "num_or_str")]
+    pub room_id: i64,
+}
+
+/// `room/v1/Room/startLive`，偶发进入直播状态却不带推流地址，故 rtmp 可缺省；srt 只有部分账号返回
+#[derive(Debug, Deserialize)]
+pub(crate) struct StartLiveResp {
+    #[serde(default)]
+    pub rtmp: Option<RtmpResp>,
+    #[serde(default)]
+    pub srt: Option<RtmpResp>,
+}
+
+/// getWebUpStreamAddr：正在直播时的推流地址
+#[derive(Debug, Deserialize)]
+pub(crate) struct UpStreamAddrResp {
+    #[serde(default)]
+    pub addr: Option<RtmpResp>,
+    #[serde(default)]
+    pub srt_addr: Option<RtmpResp>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct RtmpResp {
+    #[serde(default)]
+    pub addr: String,
+    #[serde(default)]
+    pub code: String,
+}
+
+/// `xlive/app-blink/v1/preLive/IsUserIdentifiedByFaceAuth`
+#[derive(Debug, Deserialize)]
+pub(crate) struct FaceAuthResultResp {
+    #[serde(default)]
+    pub is_identified: bool,
+}
+
+/// `xlive/revenue/v1/giftStream/getReceivedGiftStreamNextList`，按时间倒序
+#[derive(Debug, Deserialize, Default)]
+pub(crate) struct GiftStreamResp {
+    #[serde(default)]
+    pub list: Vec<GiftStreamItem>,
+    #[serde(default)]
+    pub has_more: i64,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub(crate) struct GiftStreamItem {
+    #[serde(default)]
+    pub id: String,
+    /// `YYYY-MM-DD HH:MM:SS`，北京时间
+    #[serde(default)]
+    pub time: String,
+    #[serde(default)]
+    pub gift_num: u64,
+    /// 本条流水的金瓜子价值，免费礼物为 0
+    #[serde(default)]
+    pub gold: u64,
+}
+
+/// `xlive/web-ucenter/v1/roomAdmin/get_by_anchor`，`data` 为本页的房管
+#[derive(Debug, Deserialize, Default)]
+pub(crate) struct AdminListResp {
+    #[serde(default)]
+    pub page: AdminListPage,
+    #[serde(default)]
+    pub data: Vec<AdminItem>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub(crate) struct AdminListPage {
+    #[serde(default)]
+    pub total_page: u32,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub(crate) struct AdminItem {
+    pub uid: u64,
+    #[serde(default)]
+    pub uname: String,
+    /// 任命时间 `YYYY-MM-DD HH:MM:SS`
+    #[serde(default)]
+    pub ctime: String,
+}
+
+/// `xlive/app-room/v2/guardTab/topList`，第一页的前三名单独放在 `top3`
+#[derive(Debug, Deserialize, Default)]
+pub(crate) struct GuardListResp {
+    #[serde(default)]
+    pub info: GuardListInfo,
+    #[serde(default)]
+    pub list: Vec<GuardItem>,
+    #[serde(default)]
+    pub top3: Vec<GuardItem>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub(crate) struct GuardListInfo {
+    /// 总页数
+    #[serde(default)]
+    pub page: u32,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub(crate) struct GuardItem {
+    pub uid: u64,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub guard_level: u8,
+    #[serde(default)]
+    pub medal_info: GuardMedal,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub(crate) struct GuardMedal {
+    #[serde(default)]
+    pub medal_level: u32,
+}
+
+/// `xlive/activity-interface/v1/anchorCenter/GetActivityList`
+#[derive(Debug, Deserialize, Default)]
+pub(crate) struct ActivityListResp {
+    #[serde(default)]
+    pub list: Vec<ActivityItem>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub(crate) struct ActivityItem {
+    #[serde(default)]
+    pub title: String,
+    /// Unix 秒，部分活动以字符串返回
+    #[serde(default, deserialize_with = "num_or_str")]
+    pub start_time: i64,
+    #[serde(default, deserialize_with = "num_or_str")]
+    pub end_time: i64,
+    #[serde(default)]
+    pub award_desc: String,
+    #[serde(default)]
+    pub jump_url: String,
+}
+
+/// `x/upload/web/image`
+#[derive(Debug, Deserialize)]
+pub(crate) struct UploadImageResp {
+    /// 上传后的图片地址
+    pub location: String,
+}
+
+/// `room/v1/Area/getList`
+pub(crate) type AreaListResp = Vec<AreaParentResp>;
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct AreaParentResp {
+    #[serde(deserialize_with = "num_or_str")]
+    pub id: i64,
+    pub name: String,
+    #[serde(default)]
+    pub pic: String,
+    #[serde(default, alias = "parent_sort", deserialize_with = "num_or_str")]
+    pub sort: i64,
+    #[serde(default)]
+    pub list: Vec<AreaChildResp>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct AreaChildResp {
+    #[serde(deserialize_with = "num_or_str")]
+    pub id: i64,
+    pub name: String,
+    #[serde(default, deserialize_with = "num_or_str")]
+    pub lock_status: i64,
+}
+
+/// `passport-login/web/cookie/info`
+#[derive(Debug, Deserialize)]
+pub(crate) struct CookieInfoResp {
+    pub refresh: bool,
+    #[serde(default)]
+    pub timestamp: Option<i64>,
+}
+
+/// `passport-login/web/cookie/refresh`
+#[derive(Debug, Deserialize)]
+pub(crate) struct CookieRefreshResp {
+    pub refresh_token: String,
+}
+
+/// `x/relation/stat`
+#[derive(Debug, Deserialize)]
+pub(crate) struct RelationStatResp {
+    #[serde(default)]
+    pub following: u64,
+    #[serde(default)]
+    pub follower: u64,
+}
+
+/// `xlive/general-interface/v1/rank/getOnlineGoldRank`
+#[derive(Debug, Deserialize)]
+pub(crate) struct OnlineRankResp {
+    #[serde(default, rename = "onlineNum")]
+    pub online_num: u64,
+}
+
+/// `xlive/general-interface/v1/rank/getFansMembersRank`
+#[derive(Debug, Deserialize)]
+pub(crate) struct FansMembersResp {
+    #[serde(default)]
+    pub num: u64,
+}
+
+/// `xlive/web-room/v1/dM/gethistory`，`room` 为观众弹幕，`admin` 为房管弹幕
+#[derive(Debug, Deserialize)]
+pub(crate) struct DanmakuHistoryResp {
+    #[serde(default)]
+    pub admin: Vec<HistoryItem>,
+    #[serde(default)]
+    pub room: Vec<HistoryItem>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct HistoryItem {
+    #[serde(default)]
+    pub uid: u64,
+    #[serde(default)]
+    pub nickname: String,
+    #[serde(default)]
+    pub text: String,
+    /// 北京时间 `%Y-%m-%d %H:%M:%S`
+    #[serde(default)]
+    pub timeline: String,
+    #[serde(default)]
+    pub check_info: Option<HistoryCheckInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct HistoryCheckInfo {
+    /// Unix 秒
+    #[serde(default)]
+    pub ts: i64,
+}
+
+/// `xlive/web-room/v1/index/getDanmuInfo`
+#[derive(Debug, Deserialize)]
+pub(crate) struct DanmuInfoResp {
+    pub token: String,
+    #[serde(default)]
+    pub host_list: Vec<DanmuHost>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct DanmuHost {
+    pub host: String,
+    pub wss_port: u16,
+}
+
+/// GetDMConfigByGroup：按分组列出弹幕颜色及当前账号是否可用
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct DanmakuColorResp {
+    #[serde(default)]
+    pub group: Vec<DanmakuColorGroup>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct DanmakuColorGroup {
+    #[serde(default)]
+    pub color: Vec<DanmakuColorItem>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct DanmakuColorItem {
+    #[serde(default)]
+    pub name: String,
+    /// 十进制字符串，如 "16777215"
+    #[serde(default)]
+    pub color: String,
+    /// 如 "FFFFFF"
+    #[serde(default)]
+    pub color_hex: String,
+    /// 1 表示当前账号可用
+    #[serde(default)]
+    pub status: i64,
+}
+
+/// getInfoByUser 中与弹幕相关的部分
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct RoomUserInfoResp {
+    #[serde(default)]
+    pub property: RoomUserProperty,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct RoomUserProperty {
+    #[serde(default)]
+    pub danmu: RoomUserDanmu,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct RoomUserDanmu {
+    /// 单条弹幕最多字数
+    #[serde(default)]
+    pub length: usize,
+}
+
+/// 直播间表情列表，按表情包分组
+#[derive(Debug, Deserialize)]
+pub(crate) struct EmoticonResp {
+    #[serde(default)]
+    pub data: Vec<EmoticonPkg>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct EmoticonPkg {
+    #[serde(default)]
+    pub pkg_name: String,
+    /// 没有表情的包返回 null
+    #[serde(default)]
+    pub emoticons: Option<Vec<EmoticonItem>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct EmoticonItem {
+    #[serde(default)]
+    pub emoticon_unique: String,
+    #[serde(default)]
+    pub emoji: String,
+    #[serde(default)]
+    pub url: String,
+    /// 1 表示当前账号可用
+    #[serde(default)]
+    pub perm: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn error_kind_follows_the_chain() {
+        let not_logged_in = Err::<(), _>(BiliError::NotLoggedIn("bili_jct")).context("开播失败").unwrap_err();
+        assert_eq!(error_kind(&not_logged_in), ErrorKind::NotLoggedIn);
+        assert_eq!(not_logged_in.to_string(), "开播失败");
+        let expired = BiliError::ApiError { code: -101, message: "账号未登录".into() };
+        assert_eq!(error_kind(&expired), ErrorKind::NotLoggedIn);
+        let blocked = BiliError::ApiError { code: -412, message: "请求被拦截".into() };
+        assert_eq!(error_kind(&blocked), ErrorKind::Blocked);
+        assert!(!blocked.is_api_error());
+        assert_eq!(error_kind(&BiliError::InvalidArgument("x".into())), ErrorKind::InvalidArgument);
+        let io = BiliError::from(std::io::Error::new(std::io::ErrorKind::TimedOut, "timeout"));
+        assert_eq!(error_kind(&io), ErrorKind::Network);
+        let file = BiliError::from(std::io::Error::new(std::io::ErrorKind::NotFound, "settings.json"));
+        assert_eq!(error_kind(&file), ErrorKind::Other);
+        assert_eq!(error_kind(&BiliError::Other("其他".into())), ErrorKind::Other);
+        assert_eq!(error_kind(&BiliError::LoggedOut), ErrorKind::NotLoggedIn);
+        let bind = BiliError::Bind { addr: std::net::Ipv4Addr::LOCALHOST.into(), source: std::io::ErrorKind::AddrNotAvailable.into() };
+        assert_eq!(error_kind(&bind), ErrorKind::InvalidArgument);
+        let closed = BiliError::from(tokio_tungstenite::tungstenite::Error::ConnectionClosed);
+        assert_eq!(error_kind(&closed), ErrorKind::Network);
+        assert_eq!(BiliError::AlreadySet("账号").to_string(), "账号已设置，不能重复指定");
+    }
+
+    #[test]
+    fn unwrap_data_returns_api_error_on_nonzero_code() {
+        let resp: BiliResp<StartLiveResp> =
+            BiliResp::from_value(json!({ "code": 60024, "msg": "", "message": "目标分区需要人脸认证", "data": [] })).unwrap();
+        match unwrap_data(resp) {
+            Err(BiliError::ApiError { code, message }) => {
+                assert_eq!(code, 60024);
+                assert_eq!(message, "目标分区需要人脸认证");
+            }
+            other => panic!("unexpected: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn face_auth_info_reads_qr_from_error_data() {
+        let resp: BiliResp<StartLiveResp> = BiliResp::from_value(json!({
+            "code": 60024, "message": "目标分区需要人脸认证",
+            "data": { "qr": "https://www.bilibili.com/blackboard/live/face-auth-middle.html?source_event=400&mid=1", "need_face_auth": true }
+        }))
+        .unwrap();
+        let info = face_auth_info(&resp, 42).unwrap();
+        assert_eq!(info.room_id, 42);
+        assert!(info.qr_url.contains("face-auth-middle"));
+        let no_qr: BiliResp<StartLiveResp> = BiliResp::from_value(json!({ "code": 60024, "message": "", "data": [] })).unwrap();
+        assert!(face_auth_info(&no_qr, 42).is_none());
+        let other: BiliResp<StartLiveResp> = BiliResp::from_value(json!({ "code": -400, "message": "", "data": { "qr": "x" } })).unwrap();
+        assert!(face_auth_info(&other, 42).is_none());
+    }
+
+    #[test]
+    fn unwrap_data_returns_missing_data() {
+        let resp: BiliResp<NavResp> = BiliResp::from_value(json!({ "code": 0, "message": "0", "data": null })).unwrap();
+        assert!(matches!(unwrap_data(resp), Err(BiliError::MissingData)));
+    }
+
+    #[test]
+    fn unwrap_data_returns_data_on_success() {
+        let resp: BiliResp<StartLiveResp> = BiliResp::from_value(json!({
+            "code": 0,
+            "msg": "ok",
+            "data": { "rtmp": { "addr": "rtmp://live-push.bilivideo.com/live-bvc/", "code": "?streamname=live_1" } }
+        }))
+        .unwrap();
+        let rtmp = unwrap_data(resp).unwrap().rtmp.unwrap();
+        assert_eq!(rtmp.addr, "rtmp://live-push.bilivideo.com/live-bvc/");
+        assert_eq!(rtmp.code, "?streamname=live_1");
+    }
+}
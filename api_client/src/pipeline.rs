@@ -0,0 +1,391 @@
+//! 请求发送管道。所有 B 站接口都经过同一条链路：
+//! 限速 → 选 UA → 签名（可选）→ 发送 → 重试 → 错误解析 → 指标记录。
+//! 图片下载、webhook 与网络诊断不是接口调用，不走这里。
+
+use crate::models::BiliError;
+use crate::{is_blocked, parse_json_body, record_exchange, record_metric, retry_delay, wbi, BiliClient, DEFAULT_RETRY_BACKOFF, Result};
+use reqwest::header::{HeaderMap, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{Client, Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+/// 同一域名两次请求之间的最小间隔，避免轮询叠加时短时间内打出一串请求触发风控
+pub(crate) const MIN_REQUEST_INTERVAL: Duration = Duration::from_millis(100);
+/// 读请求的默认尝试次数：被拦截时换 UA 再试一次
+const GET_ATTEMPTS: u32 = 2;
+/// 写请求的默认尝试次数
+const POST_ATTEMPTS: u32 = 3;
+
+/// 一次接口请求的描述
+pub(crate) struct ApiRequest<'a> {
+    method: Method,
+    url: &'a str,
+    form: Option<&'a BTreeMap<&'a str, String>>,
+    multipart: Option<&'a Multipart>,
+    /// 需要 WBI 签名的查询参数，签名后拼到 `url` 后面
+    wbi_query: Option<Vec<(&'a str, String)>>,
+    /// 条件请求带上的上次校验值
+    validators: Option<&'a Validators>,
+    attempts: u32,
+}
+
+/// 响应里的 ETag / Last-Modified，下次请求时原样带回做条件请求
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Validators {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+}
+
+impl Validators {
+    fn from_headers(headers: &HeaderMap) -> Self {
+        let get = |name| headers.get(name).and_then(|v| v.to_str().ok()).map(str::to_string);
+        Self { etag: get(ETAG), last_modified: get(LAST_MODIFIED) }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+}
+
+/// 手工拼接的 multipart/form-data 正文，用于上传图片（reqwest 的 multipart 特性需要额外依赖）
+pub(crate) struct Multipart {
+    boundary: String,
+    body: Vec<u8>,
+}
+
+impl Multipart {
+    pub(crate) fn new() -> Self {
+        Self { boundary: format!("----BiliLiveTool{:016x}", rand::random::<u64>()), body: Vec::new() }
+    }
+
+    pub(crate) fn text(mut self, name: &str, value: &str) -> Self {
+        self.part_header(name, None, None);
+        self.body.extend_from_slice(value.as_bytes());
+        self.body.extend_from_slice(b"\r\n");
+        self
+    }
+
+    pub(crate) fn file(mut self, name: &str, filename: &str, content_type: &str, data: &[u8]) -> Self {
+        self.part_header(name, Some(filename), Some(content_type));
+        self.body.extend_from_slice(data);
+        self.body.extend_from_slice(b"\r\n");
+        self
+    }
+
+    fn part_header(&mut self, name: &str, filename: Option<&str>, content_type: Option<&str>) {
+        let mut header = format!("--{}\r\nContent-Disposition: form-data; name=\"{}\"", self.boundary, name);
+        if let Some(filename) = filename {
+            header.push_str(&format!("; filename=\"{}\"", filename));
+        }
+        if let Some(content_type) = content_type {
+            header.push_str(&format!("\r\nContent-Type: {}", content_type));
+        }
+        header.push_str("\r\n\r\n");
+        self.body.extend_from_slice(header.as_bytes());
+    }
+
+    fn content_type(&self) -> String {
+        format!("multipart/form-data; boundary={}", self.boundary)
+    }
+
+    /// 加上结束分隔符的完整正文
+    fn finish(&self) -> Vec<u8> {
+        let mut body = self.body.clone();
+        body.extend_from_slice(format!("--{}--\r\n", self.boundary).as_bytes());
+        body
+    }
+}
+
+/// 条件请求的结果：304 时为 `NotModified`，调用方改用本地缓存
+pub(crate) enum Conditional {
+    NotModified,
+    Modified { value: serde_json::Value, validators: Validators },
+}
+
+impl<'a> ApiRequest<'a> {
+    pub(crate) fn get(url: &'a str) -> Self {
+        Self { method: Method::GET, url, form: None, multipart: None, wbi_query: None, validators: None, attempts: GET_ATTEMPTS }
+    }
+
+    pub(crate) fn post_form(url: &'a str, form: &'a BTreeMap<&'a str, String>) -> Self {
+        Self { method: Method::POST, url, form: Some(form), multipart: None, wbi_query: None, validators: None, attempts: POST_ATTEMPTS }
+    }
+
+    pub(crate) fn post_multipart(url: &'a str, multipart: &'a Multipart) -> Self {
+        Self { method: Method::POST, url, form: None, multipart: Some(multipart), wbi_query: None, validators: None, attempts: POST_ATTEMPTS }
+    }
+
+    /// 查询参数在发送前做 WBI 签名
+    pub(crate) fn wbi(mut self, query: Vec<(&'a str, String)>) -> Self {
+        self.wbi_query = Some(query);
+        self
+    }
+
+    /// 带上上次的 ETag / Last-Modified 做条件请求，校验值为空时与普通请求相同
+    pub(crate) fn conditional(mut self, validators: &'a Validators) -> Self {
+        self.validators = Some(validators).filter(|v| !v.is_empty());
+        self
+    }
+
+    /// 覆盖尝试次数，如刷新 cookie 这类不能重放的请求只试一次
+    pub(crate) fn attempts(mut self, attempts: u32) -> Self {
+        self.attempts = attempts.max(1);
+        self
+    }
+}
+
+/// 按域名排队的限速器：每个请求预约一个不早于上一个 + 间隔的时间片
+#[derive(Debug, Default)]
+pub(crate) struct RateLimiter {
+    next_slot: HashMap<String, Instant>,
+}
+
+impl RateLimiter {
+    /// 为 `host` 预约时间片，返回需要等待的时长
+    pub(crate) fn reserve(&mut self, host: &str, now: Instant, interval: Duration) -> Duration {
+        let slot = self.next_slot.get(host).copied().filter(|s| *s > now).unwrap_or(now);
+        self.next_slot.insert(host.to_string(), slot + interval);
+        slot - now
+    }
+}
+
+fn host_of(url: &str) -> &str {
+    let rest = url.split_once("://").map(|(_, r)| r).unwrap_or(url);
+    rest.split(['/', '?']).next().unwrap_or(rest)
+}
+
+/// 单次发送的结果
+struct Sent {
+    status: StatusCode,
+    /// 下次重试前的等待，服务端给了 Retry-After 时以其为准，否则按退避
+    delay: Duration,
+    validators: Validators,
+    body: String,
+}
+
+impl BiliClient {
+    /// 执行一次 JSON 接口请求。被风控拦截（HTTP 412、code -412 或非 JSON 响应）时换 UA 重试，
+    /// 重试用尽后返回最后一次的错误
+    pub(crate) async fn execute(&self, req: ApiRequest<'_>) -> Result<serde_json::Value> {
+        let url = self.signed_url(&req).await?;
+        self.execute_url(&req, &url).await
+    }
+
+    /// 执行一次条件请求，服务端返回 304 时不读正文。接口不支持条件请求时总是返回 `Modified`
+    pub(crate) async fn execute_conditional(&self, req: ApiRequest<'_>) -> Result<Conditional> {
+        let url = self.signed_url(&req).await?;
+        match self.fetch_url(&req, &url).await? {
+            (None, _) => Ok(Conditional::NotModified),
+            (Some(value), validators) => Ok(Conditional::Modified { value, validators }),
+        }
+    }
+
+    /// 签名阶段：需要 WBI 签名时取密钥并把签过名的查询串拼到 URL 上
+    async fn signed_url(&self, req: &ApiRequest<'_>) -> Result<String> {
+        let Some(query) = &req.wbi_query else { return Ok(req.url.to_string()) };
+        let (img_key, sub_key) = self.wbi_keys().await?;
+        let wts = domain::now_secs();
+        Ok(format!("{}?{}", req.url, wbi::sign(query, &img_key, &sub_key, wts)))
+    }
+
+    async fn execute_url(&self, req: &ApiRequest<'_>, url: &str) -> Result<serde_json::Value> {
+        match self.fetch_url(req, url).await? {
+            (Some(value), _) => Ok(value),
+            (None, _) => bail!("非条件请求收到 304 响应"),
+        }
+    }
+
+    /// 发送 → 重试 → 错误解析 → 指标记录。条件请求命中 304 时返回 None
+    async fn fetch_url(&self, req: &ApiRequest<'_>, url: &str) -> Result<(Option<serde_json::Value>, Validators)> {
+        let backoff = self.options.retry_backoff.unwrap_or(DEFAULT_RETRY_BACKOFF);
+        let mut last_err = BiliError::Other("unknown".into());
+        for attempt in 1..=req.attempts {
+            let ua = self.pick_ua();
+            let start = Instant::now();
+            let sent = match self.send_once(req, url, ua, attempt).await {
+                Ok(sent) => sent,
+                Err(e) => {
+                    record_metric(req.url, start.elapsed(), None, None);
+                    last_err = e;
+                    self.wait_retry(backoff * attempt, attempt, req.attempts).await;
+                    continue;
+                }
+            };
+            if sent.status == StatusCode::NOT_MODIFIED && req.validators.is_some() {
+                record_metric(req.url, start.elapsed(), Some(sent.status), None);
+                self.report_ua(ua, false);
+                return Ok((None, sent.validators));
+            }
+            let value = match parse_json_body(sent.status, &sent.body) {
+                Ok(value) => value,
+                Err(e) => {
+                    // 非 JSON 响应按风控处理：换 UA 重试，重试用尽时把响应片段带给调用方
+                    record_metric(req.url, start.elapsed(), Some(sent.status), None);
+                    self.report_ua(ua, true);
+                    last_err = e;
+                    self.wait_retry(sent.delay, attempt, req.attempts).await;
+                    continue;
+                }
+            };
+            record_metric(req.url, start.elapsed(), Some(sent.status), Some(&value));
+            let blocked = is_blocked(sent.status, &value);
+            self.report_ua(ua, blocked);
+            if !blocked {
+                return Ok((Some(value), sent.validators));
+            }
+            last_err = BiliError::Blocked(format!("请求被风控拦截（HTTP {}，code {}）", sent.status.as_u16(), value["code"]));
+            self.wait_retry(sent.delay, attempt, req.attempts).await;
+        }
+        Err(last_err)
+    }
+
+    /// 执行一次返回网页的请求（如刷新 cookie 时的 correspond 页面），只尝试一次，原样返回正文
+    pub(crate) async fn execute_text(&self, req: ApiRequest<'_>) -> Result<String> {
+        let ua = self.pick_ua();
+        let start = Instant::now();
+        let url = self.signed_url(&req).await?;
+        let res = self.send_once(&req, &url, ua, 1).await;
+        record_metric(req.url, start.elapsed(), res.as_ref().ok().map(|s| s.status), None);
+        let sent = res?;
+        self.report_ua(ua, sent.status.as_u16() == 412);
+        Ok(sent.body)
+    }
+
+    async fn wait_retry(&self, delay: Duration, attempt: u32, attempts: u32) {
+        if attempt < attempts {
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// 限速 → 发送，读出完整正文，开发者模式下记录原始请求与响应。`attempt` 从 1 开始，用于计算退避
+    async fn send_once(&self, req: &ApiRequest<'_>, url: &str, ua: &str, attempt: u32) -> Result<Sent> {
+        let wait = self.limiter.lock().unwrap_or_else(|e| e.into_inner()).reserve(host_of(req.url), Instant::now(), MIN_REQUEST_INTERVAL);
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+        let res = self.send_request(req, url, ua, attempt).await;
+        record_exchange(req.method.as_str(), url, req.form, res.as_ref().map(|sent| (sent.status, sent.body.as_str())));
+        res
+    }
+
+    /// 发送并读出完整正文，主路线连接失败且开启了故障切换时改走备用路线
+    async fn send_request(&self, req: &ApiRequest<'_>, url: &str, ua: &str, attempt: u32) -> Result<Sent> {
+        let build = |client: &Client| {
+            let mut builder = self.request_with(client, req.method.clone(), url, ua);
+            if let Some(form) = req.form {
+                builder = builder.form(form);
+            }
+            if let Some(multipart) = req.multipart {
+                builder = builder.header(CONTENT_TYPE, multipart.content_type()).body(multipart.finish());
+            }
+            if let Some(validators) = req.validators {
+                if let Some(etag) = &validators.etag {
+                    builder = builder.header(IF_NONE_MATCH, etag);
+                }
+                if let Some(modified) = &validators.last_modified {
+                    builder = builder.header(IF_MODIFIED_SINCE, modified);
+                }
+            }
+            builder
+        };
+        let Some(failover) = &self.failover else {
+            return Self::read_sent(build(&self.client).send().await?, self.options.retry_backoff, attempt).await;
+        };
+        // 最近切换过时先走备用路线，连不上再回到主路线
+        let on_backup = failover.prefers_backup();
+        let (first, second) = if on_backup { (&failover.client, &self.client) } else { (&self.client, &failover.client) };
+        let (from, to) = if on_backup { (&failover.backup, &failover.primary) } else { (&failover.primary, &failover.backup) };
+        let resp = match build(first).send().await {
+            Ok(resp) => resp,
+            // 只有连接层失败才换路线，已经连上的请求换路线也无济于事
+            Err(e) if e.is_connect() => {
+                println!("{}连接 {} 失败（{}），改用{}重试", from, host_of(req.url), e, to);
+                let resp = build(second).send().await?;
+                failover.prefer_backup(!on_backup);
+                println!("请求 {} 经{}完成", host_of(req.url), to);
+                resp
+            }
+            Err(e) => return Err(e.into()),
+        };
+        Self::read_sent(resp, self.options.retry_backoff, attempt).await
+    }
+
+    async fn read_sent(resp: reqwest::Response, backoff: Option<Duration>, attempt: u32) -> Result<Sent> {
+        let status = resp.status();
+        let delay = retry_delay(resp.headers(), backoff.unwrap_or(DEFAULT_RETRY_BACKOFF), attempt);
+        let validators = Validators::from_headers(resp.headers());
+        let body = resp.text().await?;
+        Ok(Sent { status, delay, validators, body })
+    }
+
+    /// nav 接口里的 WBI 密钥，未登录时 code 为 -101 但 data 里仍有 wbi_img。
+    /// 直接走 [`Self::execute_url`]，nav 本身不需要签名
+    async fn wbi_keys(&self) -> Result<(String, String)> {
+        const NAV: &str = "https://api.bilibili.com/x/web-interface/nav";
+        let value = self.execute_url(&ApiRequest::get(NAV), NAV).await?;
+        let img = &value["data"]["wbi_img"];
+        match (
+            img["img_url"].as_str().and_then(wbi::key_from_url),
+            img["sub_url"].as_str().and_then(wbi::key_from_url),
+        ) {
+            (Some(img_key), Some(sub_key)) => Ok((img_key, sub_key)),
+            _ => bail!("无法获取 WBI 密钥"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_limiter_spaces_requests_per_host() {
+        let mut limiter = RateLimiter::default();
+        let now = Instant::now();
+        let gap = Duration::from_millis(100);
+        assert_eq!(limiter.reserve("api.bilibili.com", now, gap), Duration::ZERO);
+        assert_eq!(limiter.reserve("api.bilibili.com", now, gap), gap);
+        assert_eq!(limiter.reserve("api.bilibili.com", now, gap), gap * 2);
+        assert_eq!(limiter.reserve("api.live.bilibili.com", now, gap), Duration::ZERO);
+        // 空闲足够久后不再等待
+        assert_eq!(limiter.reserve("api.bilibili.com", now + gap * 5, gap), Duration::ZERO);
+    }
+
+    #[test]
+    fn validators_come_from_response_headers() {
+        let mut headers = HeaderMap::new();
+        assert!(Validators::from_headers(&headers).is_empty());
+        headers.insert(ETAG, "W/\"abc\"".parse().unwrap());
+        headers.insert(LAST_MODIFIED, "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap());
+        let validators = Validators::from_headers(&headers);
+        assert_eq!(validators.etag.as_deref(), Some("W/\"abc\""));
+        assert_eq!(validators.last_modified.as_deref(), Some("Wed, 21 Oct 2015 07:28:00 GMT"));
+        // 校验值为空时不做条件请求
+        let empty = Validators::default();
+        assert!(ApiRequest::get("https://a.test/x").conditional(&empty).validators.is_none());
+        assert!(ApiRequest::get("https://a.test/x").conditional(&validators).validators.is_some());
+    }
+
+    #[test]
+    fn multipart_body_has_parts_and_closing_boundary() {
+        let form = Multipart::new().text("bucket", "live").file("file", "cover.jpg", "image/jpeg", b"\xff\xd8");
+        let body = form.finish();
+        let b = &form.boundary;
+        let expected = format!(
+            "--{b}\r\nContent-Disposition: form-data; name=\"bucket\"\r\n\r\nlive\r\n\
+             --{b}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"cover.jpg\"\r\nContent-Type: image/jpeg\r\n\r\n"
+        );
+        assert!(body.starts_with(expected.as_bytes()));
+        assert!(body.ends_with(&[&[0xff, 0xd8][..], format!("\r\n--{b}--\r\n").as_bytes()].concat()));
+        assert_eq!(form.content_type(), format!("multipart/form-data; boundary={}", b));
+    }
+
+    #[test]
+    fn host_is_taken_from_url() {
+        assert_eq!(host_of("https://api.live.bilibili.com/room/v1/Room/get_info?room_id=1"), "api.live.bilibili.com");
+        assert_eq!(host_of("http://127.0.0.1:8080"), "127.0.0.1:8080");
+    }
+}
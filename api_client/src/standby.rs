@@ -0,0 +1,82 @@
+//! 未开播时的待机封面与开播预告。B 站接口原生支持的只有一个封面（UpdatePreLiveInfo）、公告与简介，
+//! 没有单独的未开播封面或文案：两套封面保存在本地，切换时重新上传；预告写进公告或简介的第一行。
+
+use crate::models::{BiliError, Context};
+use crate::{image_mime, BiliClient, Result};
+use domain::{CoverSlot, NoticeTarget, StandbyConfig};
+use std::fs;
+use std::path::PathBuf;
+
+impl BiliClient {
+    pub(crate) fn covers_dir() -> PathBuf {
+        Self::config_dir().join("covers")
+    }
+
+    fn cover_slot_path(slot: CoverSlot) -> PathBuf {
+        Self::covers_dir().join(slot.file_name())
+    }
+
+    /// 保存一份封面存档，`image` 为 JPEG 文件内容（存档文件名固定为 .jpg）
+    pub fn save_cover_slot(slot: CoverSlot, image: &[u8]) -> Result<()> {
+        if image_mime(image) != Some("image/jpeg") {
+            return Err(BiliError::InvalidArgument("封面存档只支持 JPG 图片".into()));
+        }
+        fs::create_dir_all(Self::covers_dir())?;
+        fs::write(Self::cover_slot_path(slot), image)?;
+        Ok(())
+    }
+
+    /// 读取封面存档，未保存过时为 None
+    pub fn load_cover_slot(slot: CoverSlot) -> Option<Vec<u8>> {
+        fs::read(Self::cover_slot_path(slot)).ok()
+    }
+
+    pub fn has_cover_slot(slot: CoverSlot) -> bool {
+        Self::cover_slot_path(slot).is_file()
+    }
+
+    /// 把封面存档上传为直播间封面，返回新封面地址
+    pub async fn apply_cover_slot(&self, room_id: i64, slot: CoverSlot) -> Result<String> {
+        let image = Self::load_cover_slot(slot).ok_or_else(|| BiliError::InvalidArgument(format!("还没有保存{}", slot.label())))?;
+        self.upload_cover(room_id, &image).await
+    }
+
+    /// 写入开播预告：公告直接替换为预告，简介只替换带前缀的第一行。`notice` 为空时清除简介里的预告
+    pub async fn write_standby_notice(&self, room_id: i64, target: NoticeTarget, notice: &str) -> Result<()> {
+        match target {
+            NoticeTarget::News => self.update_room_news(room_id, notice.trim()).await,
+            NoticeTarget::Description => {
+                let current = self.get_room_info(room_id).await?.description;
+                let desc = domain::with_standby_notice(&current, notice);
+                if desc != current {
+                    self.update_room_description(room_id, &desc).await?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// 关播后切到待机状态：有待机封面时换上，有预告时写入。返回新封面地址（若换了封面）
+    pub async fn switch_to_standby(&self, room_id: i64, config: &StandbyConfig) -> Result<Option<String>> {
+        let cover = match Self::load_cover_slot(CoverSlot::Standby) {
+            Some(_) => Some(self.apply_cover_slot(room_id, CoverSlot::Standby).await.context("换上待机封面失败")?),
+            None => None,
+        };
+        if !config.notice.trim().is_empty() {
+            self.write_standby_notice(room_id, config.target, &config.notice).await.context("写入开播预告失败")?;
+        }
+        Ok(cover)
+    }
+
+    /// 开播后换回开播封面并移除简介里的预告；公告无法判断是否被改过，保持不动
+    pub async fn switch_to_live(&self, room_id: i64, config: &StandbyConfig) -> Result<Option<String>> {
+        let cover = match Self::load_cover_slot(CoverSlot::Live) {
+            Some(_) => Some(self.apply_cover_slot(room_id, CoverSlot::Live).await.context("换回开播封面失败")?),
+            None => None,
+        };
+        if config.target == NoticeTarget::Description {
+            self.write_standby_notice(room_id, NoticeTarget::Description, "").await.context("移除简介里的开播预告失败")?;
+        }
+        Ok(cover)
+    }
+}
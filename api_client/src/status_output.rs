@@ -0,0 +1,194 @@
+//! 把直播状态（一行 JSON）持续输出给本地 overlay / 脚本：定时覆盖写文件，或写入命名管道。
+//! 与 webhook、OBS 文本源不同，这里只做本地低延迟输出，不经过网络。
+
+use crate::{cancellable, BiliClient, CancellationToken, Result};
+use domain::{LiveStatus, StatusOutputConfig, StatusOutputMode};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 输出目标
+enum StatusSink {
+    File(PathBuf),
+    Pipe(pipe::PipeSink),
+}
+
+impl StatusSink {
+    fn open(config: &StatusOutputConfig) -> io::Result<Self> {
+        let path = Path::new(config.path.trim());
+        match config.mode {
+            StatusOutputMode::File => Ok(StatusSink::File(path.to_path_buf())),
+            StatusOutputMode::Pipe => Ok(StatusSink::Pipe(pipe::PipeSink::new(path)?)),
+        }
+    }
+
+    async fn write_line(&mut self, line: &str) -> io::Result<()> {
+        match self {
+            StatusSink::File(path) => write_atomic(path, line),
+            StatusSink::Pipe(pipe) => pipe.write_line(line).await,
+        }
+    }
+}
+
+/// 先写同目录的临时文件再改名，读取方不会读到写了一半的内容
+fn write_atomic(path: &Path, content: &str) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    fs::write(&tmp, content)?;
+    fs::rename(&tmp, path)
+}
+
+#[cfg(unix)]
+mod pipe {
+    use std::io;
+    use std::path::{Path, PathBuf};
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::unix::pipe;
+
+    /// 没有读取方时打开 FIFO 的写端会失败（ENXIO）
+    const ENXIO: i32 = 6;
+
+    pub(super) struct PipeSink {
+        path: PathBuf,
+        sender: Option<pipe::Sender>,
+    }
+
+    impl PipeSink {
+        /// FIFO 不存在时用 mkfifo 创建
+        pub(super) fn new(path: &Path) -> io::Result<Self> {
+            if !path.exists() {
+                let status = std::process::Command::new("mkfifo").arg(path).status()?;
+                if !status.success() {
+                    return Err(io::Error::other(format!("mkfifo {} 失败: {}", path.display(), status)));
+                }
+            }
+            Ok(Self { path: path.to_path_buf(), sender: None })
+        }
+
+        /// 没有读取方时跳过本次输出；读取方断开后下次重新打开
+        pub(super) async fn write_line(&mut self, line: &str) -> io::Result<()> {
+            let sender = match &mut self.sender {
+                Some(sender) => sender,
+                None => match pipe::OpenOptions::new().open_sender(&self.path) {
+                    Ok(sender) => self.sender.insert(sender),
+                    Err(e) if e.raw_os_error() == Some(ENXIO) => return Ok(()),
+                    Err(e) => return Err(e),
+                },
+            };
+            let res = sender.write_all(format!("{}\n", line).as_bytes()).await;
+            if res.is_err() {
+                self.sender = None;
+            }
+            match res {
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+                res => res,
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+mod pipe {
+    use std::io;
+    use std::path::Path;
+    use std::time::Duration;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::windows::named_pipe::{NamedPipeServer, ServerOptions};
+
+    pub(super) struct PipeSink {
+        name: String,
+        server: NamedPipeServer,
+        connected: bool,
+    }
+
+    impl PipeSink {
+        /// 只给名称时补全为 `\\.\pipe\名称`
+        pub(super) fn new(path: &Path) -> io::Result<Self> {
+            let path = path.to_string_lossy();
+            let name = if path.starts_with(r"\\.\pipe\") { path.into_owned() } else { format!(r"\\.\pipe\{}", path) };
+            let server = ServerOptions::new().create(&name)?;
+            Ok(Self { name, server, connected: false })
+        }
+
+        /// 还没有客户端连上时跳过本次输出；客户端断开后重建管道等待下一个
+        pub(super) async fn write_line(&mut self, line: &str) -> io::Result<()> {
+            if !self.connected {
+                match tokio::time::timeout(Duration::from_millis(10), self.server.connect()).await {
+                    Ok(res) => res?,
+                    Err(_) => return Ok(()),
+                }
+                self.connected = true;
+            }
+            if self.server.write_all(format!("{}\n", line).as_bytes()).await.is_err() {
+                self.server = ServerOptions::new().create(&self.name)?;
+                self.connected = false;
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod pipe {
+    use std::io;
+    use std::path::Path;
+
+    pub(super) struct PipeSink;
+
+    impl PipeSink {
+        pub(super) fn new(_path: &Path) -> io::Result<Self> {
+            Err(io::Error::new(io::ErrorKind::Unsupported, "当前平台不支持命名管道输出"))
+        }
+
+        pub(super) async fn write_line(&mut self, _line: &str) -> io::Result<()> {
+            Ok(())
+        }
+    }
+}
+
+impl BiliClient {
+    /// 按 `config.interval_secs` 定时拉取直播间信息，把 [`LiveStatus`] 写成一行 JSON 输出到文件或命名管道，直到被取消。
+    /// 单次拉取或写入失败只打印日志，下一轮继续；读取方长时间不读时本次写入在一个间隔后放弃
+    pub async fn run_status_output(&self, room_id: i64, config: &StatusOutputConfig, token: &CancellationToken) -> Result<()> {
+        let interval = Duration::from_secs(config.interval_secs.max(1));
+        let mut sink = StatusSink::open(config)?;
+        loop {
+            match cancellable(token, self.get_room_info(room_id)).await {
+                Ok(room) => {
+                    let now = domain::now_secs();
+                    let line = serde_json::to_string(&LiveStatus::from_room(&room, now))?;
+                    match tokio::time::timeout(interval, sink.write_line(&line)).await {
+                        Ok(Ok(())) => {}
+                        Ok(Err(e)) => println!("写入直播状态失败: {}", e),
+                        Err(_) => println!("写入直播状态超时，读取方可能已停止读取"),
+                    }
+                }
+                Err(e) if e.is_cancelled() => return Err(e),
+                Err(e) => println!("获取直播间信息失败: {}", e),
+            }
+            cancellable(token, async {
+                tokio::time::sleep(interval).await;
+                Ok(())
+            })
+            .await?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn atomic_write_replaces_content_without_leftovers() {
+        let dir = std::env::temp_dir().join(format!("bili_status_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("status.json");
+        write_atomic(&path, "{\"live_status\":0}").unwrap();
+        write_atomic(&path, "{\"live_status\":1}").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{\"live_status\":1}");
+        assert!(!dir.join("status.json.tmp").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
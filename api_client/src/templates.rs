@@ -0,0 +1,99 @@
+//! 开播模板的批量导入 / 导出，便于在多台机器间同步预设。
+//! 文件为 JSON（`{"templates": [...]}` 或直接是数组）或 TOML（`[[templates]]`），按扩展名区分，未知扩展名两种都试。
+
+use crate::models::BiliError;
+use crate::{BiliClient, Result};
+use domain::{LiveTemplate, TemplateMerge};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+#[derive(Serialize, Deserialize)]
+struct TemplateFile {
+    templates: Vec<LiveTemplate>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonTemplates {
+    List(Vec<LiveTemplate>),
+    File(TemplateFile),
+}
+
+fn is_toml(path: &Path) -> Option<bool> {
+    match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+        "toml" => Some(true),
+        "json" => Some(false),
+        _ => None,
+    }
+}
+
+fn parse_json(content: &str) -> Result<Vec<LiveTemplate>> {
+    Ok(match serde_json::from_str(content)? {
+        JsonTemplates::List(list) => list,
+        JsonTemplates::File(file) => file.templates,
+    })
+}
+
+fn parse_toml(content: &str) -> Result<Vec<LiveTemplate>> {
+    Ok(toml::from_str::<TemplateFile>(content)?.templates)
+}
+
+/// 解析模板文件，`toml` 为 None 时先按 JSON 再按 TOML 尝试。名称为空的模板视为文件有误
+pub(crate) fn parse_templates(content: &str, toml: Option<bool>) -> Result<Vec<LiveTemplate>> {
+    let templates = match toml {
+        Some(true) => parse_toml(content),
+        Some(false) => parse_json(content),
+        None => parse_json(content).or_else(|_| parse_toml(content)),
+    }
+    .map_err(|e| BiliError::InvalidArgument(format!("无法解析模板文件: {}", e)))?;
+    if let Some(i) = templates.iter().position(|t| t.name.trim().is_empty()) {
+        return Err(BiliError::InvalidArgument(format!("第 {} 个模板缺少名称", i + 1)));
+    }
+    Ok(templates)
+}
+
+impl BiliClient {
+    /// 从文件批量导入模板并保存，按名称与现有模板合并；同名时 `overwrite` 决定覆盖还是跳过
+    pub fn import_templates(path: &Path, overwrite: bool) -> Result<TemplateMerge> {
+        let templates = parse_templates(&fs::read_to_string(path)?, is_toml(path))?;
+        let mut settings = Self::load_settings();
+        let merge = settings.merge_templates(templates, overwrite);
+        Self::save_settings(&settings)?;
+        Ok(merge)
+    }
+
+    /// 把全部模板导出到文件，扩展名为 .toml 时写 TOML，否则写 JSON。返回导出的个数
+    pub fn export_templates(path: &Path) -> Result<usize> {
+        let file = TemplateFile { templates: Self::load_settings().templates };
+        let content = match is_toml(path) {
+            Some(true) => toml::to_string_pretty(&file)?,
+            _ => serde_json::to_string_pretty(&file)?,
+        };
+        fs::write(path, content)?;
+        Ok(file.templates.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_json_and_toml_templates() {
+        let list = r#"[{"name": "歌回", "title": "{date} 歌回", "area_id": 190}]"#;
+        assert_eq!(parse_templates(list, None).unwrap()[0].area_id, 190);
+        let wrapped = r#"{"templates": [{"name": "杂谈", "title": "杂谈", "area_id": 192, "room_news": "晚八点"}]}"#;
+        assert_eq!(parse_templates(wrapped, Some(false)).unwrap()[0].room_news.as_deref(), Some("晚八点"));
+
+        let toml = "[[templates]]\nname = \"歌回\"\ntitle = \"歌回\"\narea_id = 190\n\n[[templates]]\nname = \"杂谈\"\ntitle = \"杂谈\"\narea_id = 192\n";
+        let names: Vec<_> = parse_templates(toml, None).unwrap().into_iter().map(|t| t.name).collect();
+        assert_eq!(names, vec!["歌回", "杂谈"]);
+        let exported = toml::to_string_pretty(&TemplateFile { templates: parse_templates(toml, Some(true)).unwrap() }).unwrap();
+        assert_eq!(parse_templates(&exported, Some(true)).unwrap().len(), 2);
+
+        let err = parse_templates(r#"[{"name": " ", "title": "t", "area_id": 1}]"#, None).unwrap_err();
+        assert_eq!(err.to_string(), "第 1 个模板缺少名称");
+        assert!(parse_templates("not a template", None).unwrap_err().to_string().starts_with("无法解析模板文件"));
+    }
+}
@@ -0,0 +1,184 @@
+//! 弹幕连接用的 WebSocket 客户端：帧协议交给 tokio-tungstenite（校验握手、拼接分片、穿插的控制帧），
+//! 这里只负责按与 HTTP 请求相同的出口建立 TCP（本地地址 / 协议族、代理规则、故障切换）与 TLS。
+
+use crate::proxy;
+use crate::{BiliError, Result};
+use base64::Engine;
+use domain::ProxyRule;
+use futures_util::stream::{SplitSink, SplitStream};
+use futures_util::{SinkExt, StreamExt};
+use percent_encoding::percent_decode_str;
+use std::net::IpAddr;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpSocket, TcpStream};
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::rustls::{self, OwnedTrustAnchor, RootCertStore, ServerName};
+use tokio_rustls::TlsConnector;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::protocol::WebSocketConfig;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+use tokio_tungstenite::WebSocketStream;
+
+/// 单帧负载上限，弹幕包远小于此，超过视为异常连接
+const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+/// 代理 CONNECT 响应头上限
+const MAX_HANDSHAKE_LEN: usize = 16 * 1024;
+
+type Stream = WebSocketStream<TlsStream<TcpStream>>;
+
+pub(crate) enum Message {
+    Binary(Vec<u8>),
+    /// 弹幕协议只用二进制帧，文本帧内容不保留
+    Text,
+    Ping(Vec<u8>),
+    Close,
+}
+
+pub(crate) struct WsReader {
+    inner: SplitStream<Stream>,
+}
+
+pub(crate) struct WsWriter {
+    inner: SplitSink<Stream, WsMessage>,
+}
+
+/// 弹幕连接的出口，与 HTTP 请求使用同一套本地地址、代理规则与故障切换设置
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Route {
+    pub local_address: Option<IpAddr>,
+    pub rules: Vec<ProxyRule>,
+    /// 未命中规则时的代理，None 为直连
+    pub default: Option<String>,
+    /// 主路线连不上时的备用路线，见 [`proxy::failover_route`]
+    pub backup: Option<Option<String>>,
+}
+
+fn tls_connector() -> TlsConnector {
+    let mut roots = RootCertStore::empty();
+    roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|ta| {
+        OwnedTrustAnchor::from_subject_spki_name_constraints(ta.subject, ta.spki, ta.name_constraints)
+    }));
+    let config = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    TlsConnector::from(Arc::new(config))
+}
+
+/// 直连 `host:port`。指定了本地地址时只尝试同协议族的目标地址，并从该地址发起连接
+async fn tcp_connect(host: &str, port: u16, local: Option<IpAddr>) -> Result<TcpStream> {
+    let Some(local) = local else {
+        return Ok(TcpStream::connect((host, port)).await?);
+    };
+    let mut last_err = None;
+    for addr in tokio::net::lookup_host((host, port)).await?.filter(|a| a.is_ipv4() == local.is_ipv4()) {
+        let socket = if addr.is_ipv4() { TcpSocket::new_v4()? } else { TcpSocket::new_v6()? };
+        socket.bind((local, 0).into())?;
+        match socket.connect(addr).await {
+            Ok(tcp) => return Ok(tcp),
+            Err(e) => last_err = Some(e),
+        }
+    }
+    match last_err {
+        Some(e) => Err(e.into()),
+        None => bail!("{} 没有与本地地址 {} 同协议族的地址", host, local),
+    }
+}
+
+/// 经 HTTP 代理的 CONNECT 隧道连到 `host:port`，`proxy` 为 None 时直连
+async fn open_tcp(host: &str, port: u16, proxy: Option<&str>, local: Option<IpAddr>) -> Result<TcpStream> {
+    let Some(proxy) = proxy else {
+        return tcp_connect(host, port, local).await;
+    };
+    let url = reqwest::Url::parse(proxy).map_err(|e| BiliError::InvalidArgument(format!("代理地址无效: {}", e)))?;
+    if url.scheme() != "http" {
+        return Err(BiliError::InvalidArgument(format!("弹幕连接只支持 HTTP 代理，{} 不可用", proxy::route_label(Some(proxy)))));
+    }
+    let proxy_host = url.host_str().unwrap_or_default();
+    let mut tcp = tcp_connect(proxy_host, url.port_or_known_default().unwrap_or(80), local).await?;
+
+    let mut request = format!("CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n");
+    if !url.username().is_empty() {
+        let user = percent_decode_str(url.username()).decode_utf8_lossy();
+        let pass = percent_decode_str(url.password().unwrap_or_default()).decode_utf8_lossy();
+        let token = base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", user, pass));
+        request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", token));
+    }
+    request.push_str("\r\n");
+    tcp.write_all(request.as_bytes()).await?;
+
+    // 逐字节读到空行为止，避免多读走隧道里的 TLS 数据
+    let mut head = Vec::new();
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() > MAX_HANDSHAKE_LEN {
+            bail!("代理 CONNECT 响应过长");
+        }
+        head.push(tcp.read_u8().await?);
+    }
+    let head = String::from_utf8_lossy(&head);
+    let status_line = head.lines().next().unwrap_or_default();
+    if status_line.split_whitespace().nth(1) != Some("200") {
+        bail!("代理拒绝建立隧道: {}", status_line);
+    }
+    Ok(tcp)
+}
+
+/// 建立 `wss://host:port/path` 连接并完成握手。主路线连不上时按 `route.backup` 再试一次
+pub(crate) async fn connect(host: &str, port: u16, path: &str, user_agent: &str, route: &Route) -> Result<(WsReader, WsWriter)> {
+    let primary = proxy::route(&route.rules, host, route.default.as_deref());
+    let tcp = match (open_tcp(host, port, primary, route.local_address).await, &route.backup) {
+        (Ok(tcp), _) => tcp,
+        (Err(e), Some(backup)) if backup.as_deref() != primary => {
+            println!("弹幕连接经{}失败（{}），改用{}", proxy::route_label(primary), e, proxy::route_label(backup.as_deref()));
+            open_tcp(host, port, backup.as_deref(), route.local_address).await?
+        }
+        (Err(e), _) => return Err(e),
+    };
+    let tls = tls_connector().connect(ServerName::try_from(host).map_err(BiliError::other)?, tcp).await?;
+
+    let mut request = format!("wss://{}:{}{}", host, port, path).into_client_request()?;
+    let headers = request.headers_mut();
+    headers.insert("Origin", HeaderValue::from_static("https://live.bilibili.com"));
+    headers.insert("User-Agent", HeaderValue::from_str(user_agent).map_err(BiliError::other)?);
+    let config = WebSocketConfig { max_frame_size: Some(MAX_FRAME_LEN), ..WebSocketConfig::default() };
+    let (stream, _) = tokio_tungstenite::client_async_with_config(request, tls, Some(config)).await?;
+    let (w, r) = stream.split();
+    Ok((WsReader { inner: r }, WsWriter { inner: w }))
+}
+
+impl WsReader {
+    /// 读取下一条完整消息，分片帧已由 tungstenite 拼接；pong 直接忽略
+    pub(crate) async fn recv(&mut self) -> Result<Message> {
+        loop {
+            let msg = match self.inner.next().await {
+                Some(msg) => msg?,
+                None => return Ok(Message::Close),
+            };
+            return Ok(match msg {
+                WsMessage::Binary(data) => Message::Binary(data),
+                WsMessage::Text(_) => Message::Text,
+                WsMessage::Ping(payload) => Message::Ping(payload),
+                WsMessage::Close(_) => Message::Close,
+                WsMessage::Pong(_) | WsMessage::Frame(_) => continue,
+            });
+        }
+    }
+}
+
+impl WsWriter {
+    pub(crate) async fn send_binary(&mut self, payload: &[u8]) -> Result<()> {
+        Ok(self.inner.send(WsMessage::Binary(payload.to_vec())).await?)
+    }
+
+    /// tungstenite 收到 ping 时已排好 pong，这里只需把它发出去
+    pub(crate) async fn send_pong(&mut self, _payload: &[u8]) -> Result<()> {
+        Ok(self.inner.flush().await?)
+    }
+
+    pub(crate) async fn close(&mut self) {
+        let _ = self.inner.send(WsMessage::Close(None)).await;
+        let _ = self.inner.close().await;
+    }
+}
@@ -1,10 +1,57 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
-use api_client::BiliClient;
+use api_client::{replay_danmaku, BiliClient, CancellationToken, ClientOptions, DanmakuLogWriter, ErrorKind, IpFamily};
+use domain::{AuditInfo, DanmakuEvent, IdKind, LiveEvent, LiveStart, LiveTemplate, LoginState, RoomDanmakuEvent, StreamProtocol};
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::process::ExitCode;
 
 #[derive(Parser)]
-#[command(author, version, about)]
+#[command(
+    name = "bili",
+    author,
+    version = domain::version_line(env!("CARGO_PKG_VERSION")),
+    long_version = domain::long_version(env!("CARGO_PKG_VERSION")),
+    about
+)]
 struct Cli {
+    /// 配置目录，缺省为系统配置目录
+    #[arg(long, global = true, value_name = "DIR")]
+    config: Option<PathBuf>,
+    /// 使用的账号名，凭据保存在配置目录的 accounts/<name>.json；缺省为默认账号
+    #[arg(long, global = true, value_name = "NAME")]
+    account: Option<String>,
+    /// 只使用 IPv4 连接
+    #[arg(long, global = true, conflicts_with = "ipv6_only")]
+    ipv4_only: bool,
+    /// 只使用 IPv6 连接
+    #[arg(long, global = true)]
+    ipv6_only: bool,
+    /// 绑定的本地出口地址（多网卡时指定该网卡的 IP）
+    #[arg(long, global = true, value_name = "IP")]
+    local_address: Option<IpAddr>,
+    /// 覆盖所有请求的 Origin / Referer，如 https://live.bilibili.com；缺省按接口自动选择
+    #[arg(long, global = true, value_name = "URL")]
+    origin: Option<String>,
+    /// 请求失败重试前的退避（毫秒），第 n 次重试等待 n 倍；服务端返回 Retry-After 时以其为准
+    #[arg(long, global = true, value_name = "MS")]
+    retry_backoff_ms: Option<u64>,
+    /// 弹幕连接断开后的最大连续重连次数
+    #[arg(long, global = true, value_name = "N")]
+    max_reconnects: Option<u32>,
+    /// 使用的代理，如 http://127.0.0.1:7890；优先于配置文件、环境变量与系统代理
+    #[arg(long, global = true, value_name = "URL", conflicts_with = "no_proxy")]
+    proxy: Option<String>,
+    /// 不使用任何代理（忽略配置、环境变量与系统代理）
+    #[arg(long, global = true)]
+    no_proxy: bool,
+    /// 出错时以 JSON 输出错误信息与退出码，便于脚本解析
+    #[arg(long, global = true)]
+    json: bool,
+    /// 写操作只打印将要发送的请求（csrf 打码）而不真正发送，用于验证脚本参数
+    #[arg(long, global = true)]
+    dry_run: bool,
     #[command(subcommand)]
     command: Commands,
 }
@@ -14,29 +61,518 @@ enum Commands {
     /// 检查登录状态
     CheckLogin,
     /// 启动直播
-    Start,
+    Start {
+        /// 直播间号，缺省时使用当前账号的直播间
+        #[arg(long, value_parser = parse_room_id)]
+        room_id: Option<i64>,
+        /// 开播分区（子分区 id），与 --template 同时给出时以此为准
+        #[arg(long, required_unless_present = "template", value_parser = parse_area_id)]
+        area_id: Option<i64>,
+        /// 开播前套用的模板名
+        #[arg(long)]
+        template: Option<String>,
+        /// 透传给开播接口的额外表单字段，形如 key=value，可重复
+        #[arg(long = "extra", value_name = "KEY=VALUE", value_parser = parse_key_val)]
+        extra: Vec<(String, String)>,
+        /// 拿到推流密钥后等待推流连接、确认直播真正上线再退出
+        #[arg(long)]
+        wait: bool,
+        /// --wait 的最长等待（秒）
+        #[arg(long, value_name = "SECS", default_value_t = 120, requires = "wait")]
+        wait_timeout: u64,
+    },
     /// 停止直播
-    Stop,
+    Stop {
+        /// 直播间号，缺省时使用当前账号的直播间
+        #[arg(long, value_parser = parse_room_id)]
+        room_id: Option<i64>,
+    },
+    /// 修改直播间标题
+    SetTitle {
+        /// 直播间号，缺省时使用当前账号的直播间
+        #[arg(long, value_parser = parse_room_id)]
+        room_id: Option<i64>,
+        /// 新标题，支持 {date}、{count} 等变量
+        title: String,
+    },
+    /// 修改直播间分区
+    SetArea {
+        /// 直播间号，缺省时使用当前账号的直播间
+        #[arg(long, value_parser = parse_room_id)]
+        room_id: Option<i64>,
+        /// 子分区 id
+        #[arg(value_parser = parse_area_id)]
+        area_id: i64,
+    },
+    /// 修改直播间简介，需要审核
+    SetDescription {
+        /// 直播间号，缺省时使用当前账号的直播间
+        #[arg(long, value_parser = parse_room_id)]
+        room_id: Option<i64>,
+        /// 新简介，传空字符串清空
+        description: String,
+    },
+    /// 管理开播模板
+    Template {
+        #[command(subcommand)]
+        action: TemplateAction,
+    },
+    /// 导出/导入完整配置包（登录信息 + 配置）
+    Profile {
+        #[command(subcommand)]
+        action: ProfileAction,
+    },
+    /// 服务端提示需要时刷新 cookie
+    RefreshCookies {
+        /// 不调用确认接口，旧 refresh_token 保持有效（多端共用账号时避免互踢），覆盖配置中的设置
+        #[arg(long)]
+        keep_old_token: bool,
+    },
+    /// 从其它工具导出的 cookie 文件导入登录态（自动识别 JSON、Netscape cookies.txt、Cookie 字符串等格式）
+    ImportCookies { path: PathBuf },
+    /// 打印当前 cookie（默认脱敏）
+    Cookies {
+        /// 显示完整的 cookie 值
+        #[arg(long)]
+        show_values: bool,
+    },
+    /// 诊断到 B 站各域名的网络连通性
+    Doctor,
+    /// 拉取直播间最近的历史弹幕（无需长连接）
+    DanmakuHistory {
+        /// 直播间号，缺省时使用当前账号的直播间
+        #[arg(long, value_parser = parse_room_id)]
+        room_id: Option<i64>,
+    },
+    /// 打印配置、登录信息、日志与缓存的实际路径
+    Paths,
+    /// 实时监听一个或多个直播间的弹幕，Ctrl+C 退出
+    DanmakuWatch {
+        /// 直播间号，可重复指定以同时监听多个房间；缺省时使用当前账号的直播间
+        #[arg(long = "room-id", value_parser = parse_room_id)]
+        room_ids: Vec<i64>,
+        /// 同时把事件追加写入该 JSONL 文件，之后可用 danmaku-replay 回放
+        #[arg(long)]
+        record: Option<PathBuf>,
+    },
+    /// 按原时间间隔回放 danmaku-watch --record 保存的弹幕日志
+    DanmakuReplay {
+        path: PathBuf,
+        /// 倍速，2 即两倍速
+        #[arg(long, default_value_t = 1.0)]
+        speed: f64,
+    },
+    /// 调试用：发一轮只读请求后打印各接口的调用指标（指标只保存在进程内）
+    Stats,
+    /// 清除本地登录信息与缓存，回到初始状态
+    Reset {
+        /// 保留配置文件，只清除登录信息与缓存
+        #[arg(long)]
+        keep_config: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum TemplateAction {
+    /// 列出所有模板
+    List,
+    /// 新增模板，同名覆盖
+    Add {
+        name: String,
+        #[arg(long)]
+        title: String,
+        #[arg(long, value_parser = parse_area_id)]
+        area_id: i64,
+        /// 直播间公告
+        #[arg(long)]
+        room_news: Option<String>,
+    },
+    /// 删除模板
+    Remove { name: String },
+    /// 从 JSON / TOML 文件批量导入模板，默认覆盖同名模板
+    Import {
+        path: PathBuf,
+        /// 同名模板保留本地的，不覆盖
+        #[arg(long)]
+        skip_existing: bool,
+    },
+    /// 把全部模板导出到文件，扩展名为 .toml 时写 TOML，否则写 JSON
+    Export { path: PathBuf },
+}
+
+#[derive(Subcommand)]
+enum ProfileAction {
+    /// 导出到文件
+    Export { path: PathBuf },
+    /// 从文件导入，覆盖本地登录信息与配置
+    Import { path: PathBuf },
+}
+
+fn parse_key_val(s: &str) -> Result<(String, String)> {
+    let (key, value) = s.split_once('=').ok_or_else(|| anyhow::anyhow!("格式应为 key=value: {}", s))?;
+    Ok((key.to_string(), value.to_string()))
+}
+
+fn parse_room_id(s: &str) -> Result<i64, String> {
+    domain::parse_id(IdKind::Room, s)
+}
+
+fn parse_area_id(s: &str) -> Result<i64, String> {
+    domain::parse_id(IdKind::Area, s)
+}
+
+/// 一条弹幕事件的单行描述
+fn describe_event(event: &DanmakuEvent) -> String {
+    match event {
+        DanmakuEvent::Danmaku { user, text, time, .. } => format!("[{}] {}: {}", domain::beijing_clock(*time), user, text),
+        DanmakuEvent::Enter { user, .. } => format!("{} 进入直播间", user),
+        DanmakuEvent::Guard { user, level, .. } => format!("{} 上舰（等级 {}）", user, level),
+        DanmakuEvent::Gift { user, gift, count, .. } => format!("{} 赠送 {} x{}", user, gift, count),
+        DanmakuEvent::Disconnected { reason } => format!("弹幕连接断开: {}", reason),
+        DanmakuEvent::Reconnected { attempt } => format!("第 {} 次重连成功", attempt),
+    }
+}
+
+/// 未指定直播间号时，取当前登录账号的直播间
+async fn resolve_room_id(client: &BiliClient, room_id: Option<i64>) -> Result<i64> {
+    match room_id {
+        Some(id) => Ok(id),
+        None => Ok(client.get_self_info().await?.live_room.room_id),
+    }
+}
+
+/// 打印选中协议的推流信息，另一个协议也可用时一并列出；偏好的协议不可用时提示
+fn print_stream(start: &LiveStart) {
+    let preferred = BiliClient::load_settings().stream_protocol;
+    if start.protocol != preferred {
+        println!("偏好的 {} 推流不可用，已改用 {}", preferred.label(), start.protocol.label());
+    }
+    for protocol in StreamProtocol::ALL {
+        let Some(line) = start.lines.get(protocol) else { continue };
+        let current = if protocol == start.protocol { "（当前）" } else { "" };
+        println!("[{}]{}\n服务器: {}\n串流密钥: {}\n完整地址: {}", protocol.label(), current, line.server(), line.stream_key(), line.full_url());
+    }
+}
+
+/// 标题进入审核时提示审核状态
+fn print_audit(audit: Option<AuditInfo>) {
+    if let Some(audit) = audit.filter(|a| a.audit_title_status != 0) {
+        println!("标题审核状态: {} - {}", audit.audit_title_status, audit.audit_title_reason);
+    }
+}
+
+/// 退出码：0 成功 / 1 其他错误 / 2 未登录 / 3 网络 / 4 风控 / 5 参数
+const EXIT_OTHER: u8 = 1;
+const EXIT_NOT_LOGGED_IN: u8 = 2;
+const EXIT_NETWORK: u8 = 3;
+const EXIT_BLOCKED: u8 = 4;
+const EXIT_INVALID_ARGUMENT: u8 = 5;
+
+/// CLI 自身判定的错误，附带明确的错误大类
+#[derive(Debug)]
+struct CliError {
+    kind: ErrorKind,
+    message: String,
+}
+
+impl std::fmt::Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CliError {}
+
+fn cli_error(kind: ErrorKind, message: impl Into<String>) -> anyhow::Error {
+    CliError { kind, message: message.into() }.into()
+}
+
+/// CLI 自身的错误用其标明的大类，其余交给 [`api_client::error_kind`] 判断
+fn classify_error(err: &anyhow::Error) -> ErrorKind {
+    match err.downcast_ref::<CliError>() {
+        Some(e) => e.kind,
+        None => api_client::error_kind(err.as_ref()),
+    }
+}
+
+/// 错误大类对应的退出码与 JSON 中的 kind 字段
+fn exit_code(kind: ErrorKind) -> (u8, &'static str) {
+    match kind {
+        ErrorKind::NotLoggedIn => (EXIT_NOT_LOGGED_IN, "not_logged_in"),
+        ErrorKind::Network => (EXIT_NETWORK, "network"),
+        ErrorKind::Blocked => (EXIT_BLOCKED, "blocked"),
+        ErrorKind::InvalidArgument => (EXIT_INVALID_ARGUMENT, "invalid_argument"),
+        ErrorKind::Other => (EXIT_OTHER, "other"),
+    }
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
-    let client = BiliClient::new();
+async fn main() -> ExitCode {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) => {
+            // --help / --version 也走这里，它们不算错误
+            let _ = e.print();
+            return if e.use_stderr() { ExitCode::from(EXIT_INVALID_ARGUMENT) } else { ExitCode::SUCCESS };
+        }
+    };
+    let json = cli.json;
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            let (code, kind) = exit_code(classify_error(&e));
+            if json {
+                println!("{}", serde_json::json!({ "ok": false, "exit_code": code, "kind": kind, "error": format!("{:#}", e) }));
+            } else {
+                eprintln!("Error: {:?}", e);
+            }
+            ExitCode::from(code)
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    BiliClient::set_profile(cli.config, cli.account)?;
+    let ip_family = match (cli.ipv4_only, cli.ipv6_only) {
+        (true, _) => IpFamily::V4Only,
+        (_, true) => IpFamily::V6Only,
+        _ => IpFamily::Auto,
+    };
+    let mut client = BiliClient::with_options(ClientOptions {
+        local_address: cli.local_address,
+        ip_family,
+        origin: cli.origin,
+        retry_backoff: cli.retry_backoff_ms.map(std::time::Duration::from_millis),
+        max_reconnects: cli.max_reconnects,
+        proxy: cli.proxy,
+        no_proxy: cli.no_proxy,
+        dry_run: cli.dry_run,
+    })?;
 
     match cli.command {
         Commands::CheckLogin => {
             let state = client.check_login_state().await?;
             println!("当前登录状态: {:?}", state);
+            if !matches!(state, LoginState::LoggedIn) {
+                return Err(cli_error(ErrorKind::NotLoggedIn, "未登录"));
+            }
         }
-        Commands::Start => {
-            let (url, key) = client.start_live().await?;
-            println!("推流地址: {}\n推流密钥: {}", url, key);
+        Commands::Start { room_id, area_id, template, extra, wait, wait_timeout } => {
+            let room_id = resolve_room_id(&client, room_id).await?;
+            let area_id = match template {
+                Some(name) => {
+                    let settings = BiliClient::load_settings();
+                    let tpl = settings.template(&name).ok_or_else(|| cli_error(ErrorKind::InvalidArgument, format!("模板不存在: {}", name)))?;
+                    domain::validate_ids(None, Some(tpl.area_id)).map_err(|e| cli_error(ErrorKind::InvalidArgument, format!("模板 {} 配置有误: {}", tpl.name, e)))?;
+                    let areas = client.get_area_list().await?;
+                    if domain::find_area(&areas, tpl.area_id).is_none() {
+                        return Err(cli_error(ErrorKind::InvalidArgument, format!("模板 {} 的分区 {} 已失效，请更新模板", tpl.name, tpl.area_id)));
+                    }
+                    let title = domain::render_title_template(&tpl.title, &settings.title_context(domain::now_secs(), false));
+                    client.update_room_info(room_id, Some(&title), Some(tpl.area_id), None).await?;
+                    if let Some(news) = &tpl.room_news {
+                        client.update_room_news(room_id, news).await?;
+                    }
+                    println!("已套用模板: {}", tpl.name);
+                    area_id.unwrap_or(tpl.area_id)
+                }
+                None => area_id.ok_or_else(|| cli_error(ErrorKind::InvalidArgument, "缺少 --area-id"))?,
+            };
+            let problems = client.pre_live_check(room_id, area_id).await?;
+            if !problems.is_empty() {
+                anyhow::bail!("开播前检查未通过: {}", problems.join("；"));
+            }
+            let extra: BTreeMap<String, String> = extra.into_iter().collect();
+            let token = CancellationToken::new();
+            let res = client
+                .start_live_with_face_auth(room_id, area_id, Some(&extra), &token, |auth| {
+                    println!("开播需要人脸认证，请用 B 站 App 打开以下链接（或将其转成二维码后扫码）完成认证：\n{}", auth.qr_url);
+                    println!("等待认证完成，最多 {} 分钟…", api_client::FACE_AUTH_TIMEOUT.as_secs() / 60);
+                })
+                .await;
+            match res {
+                Ok(start) if start.already_live => {
+                    println!("直播间已在直播，未重复开播，以下为当前推流信息");
+                    print_stream(&start);
+                }
+                Ok(start) => {
+                    let event = LiveEvent::new("live_start", room_id, format!("分区 {}", area_id));
+                    if !client.is_dry_run() {
+                        let mut settings = BiliClient::load_settings();
+                        settings.record_area_use(area_id, event.time);
+                        settings.live_count += 1;
+                        if let Err(e) = BiliClient::save_settings(&settings) {
+                            println!("保存开播统计失败: {}", e);
+                        }
+                    }
+                    client.emit_event(&event).await;
+                    print_stream(&start);
+                    if wait {
+                        println!("等待推流连接…");
+                        client.wait_until_live(room_id, std::time::Duration::from_secs(wait_timeout), &token).await?;
+                        println!("直播已上线");
+                    }
+                }
+                Err(e) => {
+                    client.emit_event(&LiveEvent::new("error", room_id, format!("开播失败: {}", e))).await;
+                    return Err(e.into());
+                }
+            }
         }
-        Commands::Stop => {
-            client.stop_live().await?;
-            println!("已发送停播请求");
+        Commands::Stop { room_id } => {
+            let room_id = resolve_room_id(&client, room_id).await?;
+            match client.stop_live(room_id).await {
+                Ok(true) => {
+                    client.emit_event(&LiveEvent::new("live_stop", room_id, "")).await;
+                    println!("已发送停播请求");
+                }
+                Ok(false) => println!("直播间未在直播，无需关播"),
+                Err(e) => {
+                    client.emit_event(&LiveEvent::new("error", room_id, format!("关播失败: {}", e))).await;
+                    return Err(e.into());
+                }
+            }
+        }
+        Commands::SetTitle { room_id, title } => {
+            let room_id = resolve_room_id(&client, room_id).await?;
+            let title = domain::render_title_template(&title, &BiliClient::load_settings().title_context(domain::now_secs(), false));
+            print_audit(client.update_room_info(room_id, Some(&title), None, None).await?);
+            println!("标题已更新: {}", title);
+        }
+        Commands::SetArea { room_id, area_id } => {
+            let room_id = resolve_room_id(&client, room_id).await?;
+            print_audit(client.update_room_info(room_id, None, Some(area_id), None).await?);
+            println!("分区已更新: {}", area_id);
+        }
+        Commands::SetDescription { room_id, description } => {
+            let room_id = resolve_room_id(&client, room_id).await?;
+            client.update_room_description(room_id, &description).await?;
+            println!("简介已提交，审核通过后生效");
+        }
+        Commands::Template { action } => {
+            let mut settings = BiliClient::load_settings();
+            match action {
+                TemplateAction::List => {
+                    if settings.templates.is_empty() {
+                        println!("暂无模板");
+                    }
+                    for t in &settings.templates {
+                        println!("{}\t分区 {}\t{}", t.name, t.area_id, t.title);
+                    }
+                }
+                TemplateAction::Add { name, title, area_id, room_news } => {
+                    settings.upsert_template(LiveTemplate { name, title, area_id, room_news });
+                    BiliClient::save_settings(&settings)?;
+                    println!("模板已保存");
+                }
+                TemplateAction::Remove { name } => {
+                    if !settings.remove_template(&name) {
+                        return Err(cli_error(ErrorKind::InvalidArgument, format!("模板不存在: {}", name)));
+                    }
+                    BiliClient::save_settings(&settings)?;
+                    println!("模板已删除");
+                }
+                TemplateAction::Import { path, skip_existing } => {
+                    let merge = BiliClient::import_templates(&path, !skip_existing)?;
+                    println!("模板已导入：{}", merge.summary());
+                }
+                TemplateAction::Export { path } => {
+                    let count = BiliClient::export_templates(&path)?;
+                    println!("已导出 {} 个模板到 {}", count, path.display());
+                }
+            }
+        }
+        Commands::Profile { action } => match action {
+            ProfileAction::Export { path } => {
+                BiliClient::export_profile(&path)?;
+                println!("配置包已导出到 {}（含登录凭据，请妥善保管）", path.display());
+            }
+            ProfileAction::Import { path } => {
+                client.import_profile(&path).await?;
+                println!("配置包已导入，登录状态有效");
+            }
+        },
+        Commands::RefreshCookies { keep_old_token } => {
+            let confirm_old = !keep_old_token && BiliClient::load_settings().confirm_cookie_refresh;
+            client.refresh_cookies_if_needed(confirm_old).await?;
+            println!("cookie 检查完成");
+        }
+        Commands::ImportCookies { path } => {
+            let format = client.import_from_file(&path).await?;
+            println!("已按 {} 格式导入 cookie，登录状态有效", format);
+        }
+        Commands::Cookies { show_values } => {
+            let cookies = client.current_cookies();
+            if cookies.is_empty() {
+                println!("当前没有 cookie");
+            }
+            for c in cookies {
+                let value = if show_values { c.value.clone() } else { c.masked_value() };
+                println!("{}={}", c.name, value);
+            }
+        }
+        Commands::Doctor => {
+            let diag = client.diagnose_network().await;
+            for h in &diag.hosts {
+                match &h.error {
+                    None => println!("{:<24}可达\t{} ms", h.host, h.latency_ms),
+                    Some(e) => println!("{:<24}不可达\t{}", h.host, e),
+                }
+            }
+            if !diag.all_reachable() {
+                return Err(cli_error(ErrorKind::Network, "部分域名不可达，请检查网络、DNS 或代理设置"));
+            }
+        }
+        Commands::DanmakuHistory { room_id } => {
+            let room_id = resolve_room_id(&client, room_id).await?;
+            let list = client.get_recent_danmaku(room_id).await?;
+            if list.is_empty() {
+                println!("暂无历史弹幕");
+            }
+            for d in list {
+                println!("[{}] {}: {}", domain::beijing_clock(d.time), d.user, d.text);
+            }
+        }
+        Commands::Paths => {
+            for (name, path) in BiliClient::local_paths() {
+                println!("{}: {}", name, path.display());
+            }
+        }
+        Commands::DanmakuWatch { room_ids, record } => {
+            let room_ids = if room_ids.is_empty() { vec![resolve_room_id(&client, None).await?] } else { room_ids };
+            let mut log = record.as_deref().map(DanmakuLogWriter::open).transpose()?;
+            let mut stream = client.connect_multi(&room_ids).await?;
+            while let Some(RoomDanmakuEvent { room_id, event }) = stream.next().await {
+                println!("#{} {}", room_id, describe_event(&event));
+                if let Some(Err(e)) = log.as_mut().map(|log| log.record(room_id, &event)) {
+                    println!("写入弹幕日志失败: {}", e);
+                }
+            }
+        }
+        Commands::DanmakuReplay { path, speed } => {
+            let mut stream = replay_danmaku(&path, speed).await?;
+            while let Some(event) = stream.next().await {
+                println!("{}", describe_event(&event));
+            }
+        }
+        Commands::Stats => {
+            if matches!(client.check_login_state().await?, LoginState::LoggedIn) {
+                if let Err(e) = client.get_self_info().await {
+                    println!("获取用户信息失败: {}", e);
+                }
+            }
+            if let Err(e) = client.get_area_list().await {
+                println!("获取分区列表失败: {}", e);
+            }
+            println!("{:<48}{:>6}{:>10}{:>6}{:>6}", "接口", "次数", "平均(ms)", "412", "失败");
+            for m in api_client::metrics_snapshot() {
+                println!("{:<48}{:>6}{:>10}{:>6}{:>6}", m.endpoint, m.calls, m.avg_ms(), m.blocked, m.failures);
+            }
+        }
+        Commands::Reset { keep_config } => {
+            client.clear_local_data(keep_config)?;
+            println!("本地数据已清除{}", if keep_config { "（保留配置）" } else { "" });
         }
     }
     Ok(())
-} 
\ No newline at end of file
+}
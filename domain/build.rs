@@ -0,0 +1,38 @@
+//! 注入构建信息：`GIT_HASH`（短提交哈希）、`BUILD_TIME` / `BUILD_DATE`（UTC）与 `BUILD_TARGET`（目标 triple），
+//! 由 lib.rs 中的同名常量导出给 GUI 与 CLI。设置了 `SOURCE_DATE_EPOCH` 时以它为构建时间，便于复现构建。
+
+use std::path::Path;
+use std::process::Command;
+
+fn main() {
+    let hash = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=GIT_HASH={}", hash);
+
+    let time = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+        .unwrap_or_else(chrono::Utc::now);
+    println!("cargo:rustc-env=BUILD_TIME={}", time.format("%Y-%m-%d %H:%M UTC"));
+    println!("cargo:rustc-env=BUILD_DATE={}", time.format("%Y-%m-%d"));
+    println!("cargo:rustc-env=BUILD_TARGET={}", std::env::var("TARGET").unwrap_or_default());
+
+    // 提交变化时重新生成；HEAD 指向分支时还要监视分支引用
+    let git_dir = Path::new("../.git");
+    if let Ok(head) = std::fs::read_to_string(git_dir.join("HEAD")) {
+        println!("cargo:rerun-if-changed={}", git_dir.join("HEAD").display());
+        if let Some(reference) = head.trim().strip_prefix("ref: ") {
+            println!("cargo:rerun-if-changed={}", git_dir.join(reference).display());
+        }
+    }
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    println!("cargo:rerun-if-changed=build.rs");
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum LoginState {
@@ -20,11 +21,46 @@ pub struct WebQrInfo {
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct RoomInfo {
     pub room_id: u64,
+    pub short_id: u64,
     pub title: String,
     pub cover_url: String,
     pub area_id: u64,
     pub area_name: String,
     pub description: String,
+    pub live_status: i32,
+    /// 本场开播时间（Unix 秒），未开播为 0
+    pub live_start: i64,
+    /// 人气值
+    #[serde(default)]
+    pub online: u64,
+    #[serde(default)]
+    pub interact: InteractStatus,
+}
+
+/// 直播间的 PK / 连麦状态，只读展示
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum InteractStatus {
+    #[default]
+    Idle,
+    Pk,
+}
+
+impl InteractStatus {
+    /// 由 get_info 的 `pk_status` 与 `battle_id` 判断，任一非 0 即视为 PK 中
+    pub fn from_pk_fields(pk_status: i64, battle_id: i64) -> Self {
+        if pk_status != 0 || battle_id != 0 {
+            Self::Pk
+        } else {
+            Self::Idle
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Idle => "空闲",
+            Self::Pk => "PK 中",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -42,6 +78,28 @@ pub struct Cookie {
     pub expires: i64,
 }
 
+/// 隐私模式下的完全打码，不保留任何字符，长度也不泄露
+pub fn redact(s: &str) -> String {
+    if s.is_empty() {
+        String::new()
+    } else {
+        "******".to_string()
+    }
+}
+
+impl Cookie {
+    /// 脱敏后的值，只保留前后各 4 个字符
+    pub fn masked_value(&self) -> String {
+        let chars: Vec<char> = self.value.chars().collect();
+        if chars.len() <= 8 {
+            return "*".repeat(chars.len());
+        }
+        let head: String = chars[..4].iter().collect();
+        let tail: String = chars[chars.len() - 4..].iter().collect();
+        format!("{}****{}", head, tail)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct AuthData {
     pub token: TokenInfo,
@@ -57,29 +115,1998 @@ pub struct LiveRoomBrief {
     pub room_id: i64,
 }
 
+/// 主播自己的礼物收益概览，金额单位为金瓜子（100 金瓜子 = 1 电池）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct IncomeOverview {
+    pub month_gold: u64,
+    pub month_gifts: u64,
+    /// 本场（开播以来）的收益，未开播时为 0
+    pub session_gold: u64,
+    pub session_gifts: u64,
+    /// 流水超过翻页上限，统计可能偏少
+    pub truncated: bool,
+}
+
+impl IncomeOverview {
+    pub fn gold_to_battery(gold: u64) -> f64 {
+        gold as f64 / 100.0
+    }
+}
+
+/// 还没保存到服务端的直播标题草稿，防止误关窗口丢失
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TitleDraft {
+    pub room_id: i64,
+    pub title: String,
+    /// 写入草稿时的 Unix 秒
+    pub saved_at: i64,
+}
+
+/// 标题模板变量的取值
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TitleContext {
+    /// Unix 秒，日期按北京时间计算
+    pub now: i64,
+    /// 第几场直播
+    pub count: u64,
+}
+
+const WEEKDAYS: [&str; 7] = ["周一", "周二", "周三", "周四", "周五", "周六", "周日"];
+
+/// 直播间简介的最大字数，与网页端设置页一致
+pub const ROOM_DESCRIPTION_MAX: usize = 1000;
+
+/// 保存前检查直播间简介，返回不能保存的原因；清空简介是允许的
+pub fn check_room_description(desc: &str) -> Result<(), String> {
+    let len = desc.chars().count();
+    if len > ROOM_DESCRIPTION_MAX {
+        return Err(format!("简介最多 {} 个字，当前 {} 个", ROOM_DESCRIPTION_MAX, len));
+    }
+    Ok(())
+}
+
+/// 渲染标题模板：`{date}`（2024-01-02）、`{date:%m-%d}`（strftime 格式）、`{count}`、`{weekday}`（周一）。
+/// 不认识的占位符和格式写错的 `{date:…}` 原样保留
+pub fn render_title_template(tpl: &str, ctx: &TitleContext) -> String {
+    use chrono::{format::{Item, StrftimeItems}, Datelike, FixedOffset, TimeZone};
+    let tz = FixedOffset::east_opt(8 * 3600).expect("固定偏移合法");
+    let Some(time) = tz.timestamp_opt(ctx.now, 0).single() else { return tpl.to_string() };
+    let mut out = String::with_capacity(tpl.len());
+    let mut rest = tpl;
+    while let Some(end) = rest.find('}') {
+        let Some(start) = rest[..end].rfind('{') else {
+            out.push_str(&rest[..=end]);
+            rest = &rest[end + 1..];
+            continue;
+        };
+        out.push_str(&rest[..start]);
+        let token = &rest[start + 1..end];
+        let value = match token {
+            "date" => Some(time.format("%Y-%m-%d").to_string()),
+            "count" => Some(ctx.count.to_string()),
+            "weekday" => Some(WEEKDAYS[time.weekday().num_days_from_monday() as usize].to_string()),
+            _ => token
+                .strip_prefix("date:")
+                .filter(|fmt| !fmt.is_empty() && !StrftimeItems::new(fmt).any(|i| matches!(i, Item::Error)))
+                .map(|fmt| time.format(fmt).to_string()),
+        };
+        match value {
+            Some(v) => out.push_str(&v),
+            None => out.push_str(&rest[start..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct UserInfo {
     pub mid: u64,
     pub name: String,
     pub face: String,
     pub live_room: LiveRoomBrief,
+    /// 关注数
+    #[serde(default)]
+    pub following: u64,
+    /// 粉丝数
+    #[serde(default)]
+    pub follower: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct AreaChild {
     pub id: i64,
     pub name: String,
+    /// 分区已锁定，暂不允许开播
+    #[serde(default)]
+    pub locked: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct AreaParent {
     pub id: i64,
     pub name: String,
+    /// 图标 URL，接口未提供时为空
+    #[serde(default)]
+    pub pic: String,
+    /// 官方展示顺序，越小越靠前；0 表示未提供
+    #[serde(default)]
+    pub sort: i64,
     pub children: Vec<AreaChild>,
 }
 
+/// 去掉没有子分区的父分区（无法选择），并按官方顺序稳定排序；未提供顺序时保持接口原顺序
+pub fn normalize_area_list(areas: &mut Vec<AreaParent>) {
+    areas.retain(|p| !p.children.is_empty());
+    areas.sort_by_key(|p| if p.sort > 0 { p.sort } else { i64::MAX });
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct AuditInfo {
     pub audit_title_status: i32,
     pub audit_title_reason: String,
-} 
\ No newline at end of file
+}
+
+/// 开播返回的推流信息：`addr` 形如 `rtmp://.../live-bvc/`，`code` 形如 `?streamname=...&key=...`
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct StreamLine {
+    pub addr: String,
+    pub code: String,
+}
+
+/// 推流协议
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamProtocol {
+    #[default]
+    Rtmp,
+    Srt,
+}
+
+impl StreamProtocol {
+    pub const ALL: [StreamProtocol; 2] = [StreamProtocol::Rtmp, StreamProtocol::Srt];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            StreamProtocol::Rtmp => "RTMP",
+            StreamProtocol::Srt => "SRT",
+        }
+    }
+
+    /// 推流地址的协议头与协议是否相符，RTMP 也接受 rtmps
+    pub fn matches_addr(self, addr: &str) -> bool {
+        match self {
+            StreamProtocol::Rtmp => addr.starts_with("rtmp://") || addr.starts_with("rtmps://"),
+            StreamProtocol::Srt => addr.starts_with("srt://"),
+        }
+    }
+}
+
+/// 开播接口同时返回的 RTMP 与 SRT 推流信息，各自校验，不可用的为 None
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StreamLines {
+    pub rtmp: Option<StreamLine>,
+    pub srt: Option<StreamLine>,
+}
+
+impl StreamLines {
+    pub fn get(&self, protocol: StreamProtocol) -> Option<&StreamLine> {
+        match protocol {
+            StreamProtocol::Rtmp => self.rtmp.as_ref(),
+            StreamProtocol::Srt => self.srt.as_ref(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rtmp.is_none() && self.srt.is_none()
+    }
+
+    /// 按偏好选择协议，偏好的不可用时回退到另一个
+    pub fn pick(&self, preferred: StreamProtocol) -> Option<(StreamProtocol, &StreamLine)> {
+        let others = StreamProtocol::ALL.into_iter().filter(|p| *p != preferred);
+        std::iter::once(preferred).chain(others).find_map(|p| self.get(p).map(|line| (p, line)))
+    }
+}
+
+/// 开播结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiveStart {
+    /// 按偏好选中的推流信息
+    pub line: StreamLine,
+    pub protocol: StreamProtocol,
+    /// 本次可用的全部协议，界面切换协议时从这里取
+    pub lines: StreamLines,
+    /// 直播间本来就在直播：没有重新开播，推流信息取自当前这场直播
+    pub already_live: bool,
+}
+
+impl LiveStart {
+    /// 按偏好从可用的协议中选出推流信息，都不可用时返回 None
+    pub fn pick(lines: StreamLines, preferred: StreamProtocol, already_live: bool) -> Option<Self> {
+        let (protocol, line) = lines.pick(preferred).map(|(p, line)| (p, line.clone()))?;
+        Some(Self { line, protocol, lines, already_live })
+    }
+}
+
+impl StreamLine {
+    /// 推流服务器，对应 OBS 的"服务器"
+    pub fn server(&self) -> &str {
+        &self.addr
+    }
+
+    /// 串流密钥，对应 OBS 的"串流密钥"
+    pub fn stream_key(&self) -> &str {
+        &self.code
+    }
+
+    /// 服务器与密钥拼成的完整推流地址，供只接受单个 URL 的推流工具使用；SRT 的密钥是 streamid
+    pub fn full_url(&self) -> String {
+        if self.addr.starts_with("srt://") {
+            format!("{}?streamid={}", self.addr, self.code)
+        } else if self.addr.ends_with('/') || self.code.starts_with(['?', '/']) {
+            format!("{}{}", self.addr, self.code)
+        } else {
+            format!("{}/{}", self.addr, self.code)
+        }
+    }
+}
+
+/// 开播模板：一键套用标题、分区与公告
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct LiveTemplate {
+    pub name: String,
+    pub title: String,
+    pub area_id: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room_news: Option<String>,
+}
+
+/// 批量导入模板的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TemplateMerge {
+    pub added: usize,
+    /// 同名覆盖
+    pub replaced: usize,
+    /// 同名跳过
+    pub skipped: usize,
+}
+
+impl TemplateMerge {
+    pub fn summary(&self) -> String {
+        format!("新增 {} 个，覆盖 {} 个，跳过 {} 个", self.added, self.replaced, self.skipped)
+    }
+}
+
+/// 单个分区的使用统计，用于"常用分区"
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct AreaUsage {
+    pub area_id: i64,
+    pub count: u32,
+    /// 最后一次使用的 Unix 秒
+    pub last_used: i64,
+}
+
+/// 持久化到 settings.toml 的用户配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Settings {
+    #[serde(default)]
+    pub templates: Vec<LiveTemplate>,
+    /// 直播满多少分钟时提醒，0 表示关闭
+    #[serde(default)]
+    pub live_reminder_minutes: u32,
+    /// 开播/关播/出错等事件的 webhook 地址，为空不推送
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<String>,
+    /// 成功开播 / 保存分区时累计的分区使用统计
+    #[serde(default)]
+    pub area_usage: Vec<AreaUsage>,
+    /// 开播接口成功却没有返回有效推流地址时，自动关播回滚
+    #[serde(default = "default_true")]
+    pub rollback_failed_start: bool,
+    /// 写给 OBS 文本源的直播间信息文件
+    #[serde(default)]
+    pub obs_text: ObsTextConfig,
+    /// 写给 overlay 的直播状态 JSON（文件或命名管道）
+    #[serde(default)]
+    pub status_output: StatusOutputConfig,
+    /// 弹幕关键词自动回复与欢迎语
+    #[serde(default)]
+    pub auto_reply: AutoReplyConfig,
+    /// 导出 ffmpeg 推流命令 / 脚本的参数
+    #[serde(default)]
+    pub ffmpeg: FfmpegConfig,
+    /// 开播期间记录数据快照的间隔（秒），0 表示关闭
+    #[serde(default = "default_stats_interval")]
+    pub stats_interval_secs: u64,
+    /// 界面缩放倍数，叠加在系统 DPI 缩放之上，取值见 [`UI_SCALE_RANGE`]
+    #[serde(default = "default_ui_scale")]
+    pub ui_scale: f32,
+    /// 刷新 cookie 后是否确认并让旧 refresh_token 失效。关闭可避免多端共用账号时互相踢下线，
+    /// 但旧凭据泄露后会一直有效，见 README
+    #[serde(default = "default_true")]
+    pub confirm_cookie_refresh: bool,
+    /// 累计成功开播的场次，标题模板中的 `{count}` 由此而来
+    #[serde(default)]
+    pub live_count: u64,
+    /// 网络代理地址，如 http://127.0.0.1:7890（只支持 HTTP(S) 代理）；"off" 表示直连，缺省按环境变量与系统设置
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// 按域名单独指定代理或直连，未命中规则的请求仍走上面的 `proxy`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub proxy_rules: Vec<ProxyRule>,
+    /// 偏好的推流协议，开播时不可用则回退到另一个
+    #[serde(default)]
+    pub stream_protocol: StreamProtocol,
+    /// 开发者模式：在各操作下方展示最近一次请求的原始内容
+    #[serde(default)]
+    pub developer_mode: bool,
+    /// 请求连接失败时改走备用路线（代理 ↔ 直连）重试一次。默认关闭，避免未经同意绕过代理
+    #[serde(default)]
+    pub proxy_failover: bool,
+    /// 界面后台轮询与重试的间隔
+    #[serde(default)]
+    pub polling: PollingConfig,
+    /// 待机封面与开播预告
+    #[serde(default)]
+    pub standby: StandbyConfig,
+}
+
+/// 按域名选择代理的规则，`host` 同时匹配其子域名，`proxy` 为代理地址或 "off"（直连）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProxyRule {
+    pub host: String,
+    pub proxy: String,
+}
+
+impl ProxyRule {
+    /// 规则里的域名，去掉 `*.` / `.` 前缀并转小写
+    pub fn domain(&self) -> String {
+        self.host.trim().trim_start_matches("*.").trim_start_matches('.').to_ascii_lowercase()
+    }
+
+    pub fn matches(&self, host: &str) -> bool {
+        let domain = self.domain();
+        let host = host.to_ascii_lowercase();
+        !domain.is_empty() && (host == domain || host.ends_with(&format!(".{}", domain)))
+    }
+}
+
+/// 解析设置页的规则文本：每行 `域名 代理地址|off`，空行与 `#` 开头的行忽略
+pub fn parse_proxy_rules(text: &str) -> Result<Vec<ProxyRule>, String> {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| (i, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(i, line)| match line.split_whitespace().collect::<Vec<_>>()[..] {
+            [host, proxy] => Ok(ProxyRule { host: host.to_string(), proxy: proxy.to_string() }),
+            _ => Err(format!("第 {} 行格式应为「域名 代理地址」或「域名 off」", i + 1)),
+        })
+        .collect()
+}
+
+pub fn format_proxy_rules(rules: &[ProxyRule]) -> String {
+    rules.iter().map(|r| format!("{} {}", r.host, r.proxy)).collect::<Vec<_>>().join("\n")
+}
+
+/// 界面缩放允许的范围
+pub const UI_SCALE_RANGE: std::ops::RangeInclusive<f32> = 0.5..=3.0;
+
+/// 上次联网时获取的用户、直播间与分区信息，网络不可用时供界面只读展示
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct OfflineSnapshot {
+    /// 最后一次更新的 Unix 秒
+    pub saved_at: i64,
+    pub user: Option<UserInfo>,
+    pub room: Option<RoomInfo>,
+    #[serde(default)]
+    pub areas: Vec<AreaParent>,
+}
+
+/// 当前 Unix 秒，系统时钟早于 1970 年时为 0
+pub fn now_secs() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Unix 秒转为北京时间 `HH:MM:SS`，弹幕与趋势图的时间标签共用
+pub fn beijing_clock(secs: i64) -> String {
+    let day = (secs + 8 * 3600).rem_euclid(86400);
+    format!("{:02}:{:02}:{:02}", day / 3600, day % 3600 / 60, day % 60)
+}
+
+/// Unix 秒转为北京时间 `MM-DD HH:MM`
+fn beijing_minute_label(secs: i64) -> String {
+    use chrono::TimeZone;
+    let tz = chrono::FixedOffset::east_opt(8 * 3600).expect("固定偏移合法");
+    tz.timestamp_opt(secs, 0).single().map(|t| t.format("%m-%d %H:%M").to_string()).unwrap_or_default()
+}
+
+impl OfflineSnapshot {
+    /// 更新时间（北京时间 `MM-DD HH:MM`）
+    pub fn saved_at_label(&self) -> String {
+        beijing_minute_label(self.saved_at)
+    }
+}
+
+/// 写入磁盘的读接口结果，界面先展示缓存再在后台刷新
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct Cached<T> {
+    /// 拉取时间，Unix 秒
+    pub saved_at: i64,
+    pub data: T,
+}
+
+impl<T> Cached<T> {
+    /// 拉取时间（北京时间 `MM-DD HH:MM`）
+    pub fn saved_at_label(&self) -> String {
+        beijing_minute_label(self.saved_at)
+    }
+}
+
+/// 房管，属于主播本人而不是某个直播间
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct RoomAdmin {
+    pub uid: u64,
+    pub name: String,
+    /// 任命时间，接口原样返回的北京时间字符串
+    pub appointed_at: String,
+}
+
+/// 大航海成员
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct GuardMember {
+    pub uid: u64,
+    pub name: String,
+    /// 1 总督 / 2 提督 / 3 舰长
+    pub level: u8,
+    pub medal_level: u32,
+}
+
+impl GuardMember {
+    pub fn level_name(&self) -> &'static str {
+        match self.level {
+            1 => "总督",
+            2 => "提督",
+            _ => "舰长",
+        }
+    }
+}
+
+/// 当前账号的房管与大航海成员
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct RoomMembers {
+    pub admins: Vec<RoomAdmin>,
+    pub guards: Vec<GuardMember>,
+}
+
+/// 直播首页可参与的活动 / 任务，只读展示
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct LiveActivity {
+    pub title: String,
+    /// 起止时间，Unix 秒，0 表示接口未给出
+    pub start_time: i64,
+    pub end_time: i64,
+    /// 奖励说明
+    pub reward: String,
+    pub url: String,
+}
+
+impl LiveActivity {
+    /// 活动时间（北京时间 `MM-DD HH:MM ~ MM-DD HH:MM`），缺少的一端省略
+    pub fn time_label(&self) -> String {
+        match (self.start_time, self.end_time) {
+            (0, 0) => String::new(),
+            (start, 0) => format!("{} 起", beijing_minute_label(start)),
+            (0, end) => format!("截至 {}", beijing_minute_label(end)),
+            (start, end) => format!("{} ~ {}", beijing_minute_label(start), beijing_minute_label(end)),
+        }
+    }
+}
+
+/// 开播期间定时记录的直播间数据，每条写成 stats 文件中的一行 JSON
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct StatsSnapshot {
+    /// Unix 秒
+    pub time: i64,
+    /// 人气值
+    pub popularity: u64,
+    /// 高能榜在线人数
+    pub online: u64,
+    /// 粉丝团人数
+    pub fans_club: u64,
+}
+
+/// 最近若干个数据快照的环形缓冲，满了丢弃最旧的
+#[derive(Debug, Clone, Default)]
+pub struct StatsRing {
+    capacity: usize,
+    samples: VecDeque<StatsSnapshot>,
+}
+
+impl StatsRing {
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity: capacity.max(1), samples: VecDeque::with_capacity(capacity) }
+    }
+
+    pub fn push(&mut self, snapshot: StatsSnapshot) {
+        if self.samples.len() >= self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(snapshot);
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn samples(&self) -> impl Iterator<Item = &StatsSnapshot> {
+        self.samples.iter()
+    }
+
+    /// 时间上离 `time` 最近的采样点，用于悬停显示
+    pub fn nearest(&self, time: i64) -> Option<&StatsSnapshot> {
+        self.samples.iter().min_by_key(|s| (s.time - time).abs())
+    }
+}
+
+/// 通过 HTTP 拉取的直播间历史弹幕
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryDanmaku {
+    pub uid: u64,
+    pub user: String,
+    pub text: String,
+    /// Unix 秒
+    pub time: i64,
+}
+
+/// 直播间可用的表情，发送时以 `unique` 作为弹幕内容
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Emoticon {
+    /// 如 `official_147`
+    pub unique: String,
+    /// 表情名，如「赞」
+    pub name: String,
+    pub url: String,
+    /// 所属表情包名
+    pub package: String,
+    /// 当前账号能否发送（部分表情需要粉丝勋章或舰长）
+    pub usable: bool,
+}
+
+/// 默认的白色弹幕
+pub const DEFAULT_DANMAKU_COLOR: u32 = 0xFFFFFF;
+/// 直播弹幕只有一种字号，发送接口只接受这个值
+pub const DANMAKU_FONTSIZE: u32 = 25;
+/// 拿不到字数上限时按未佩戴勋章的普通用户处理
+pub const DEFAULT_DANMAKU_LENGTH: usize = 20;
+
+/// 弹幕颜色，`color` 为 0xRRGGBB
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DanmakuColor {
+    pub name: String,
+    pub color: u32,
+    /// 当前账号能否使用（彩色弹幕需要粉丝勋章等级或舰长）
+    pub usable: bool,
+}
+
+/// 当前账号在某个直播间发送弹幕的限制
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DanmakuConfig {
+    pub colors: Vec<DanmakuColor>,
+    /// 单条弹幕最多字数，随用户等级与勋章变化（20 / 30 / 40）
+    pub max_length: usize,
+    /// 最大字号，目前恒为 [`DANMAKU_FONTSIZE`]
+    pub fontsize: u32,
+}
+
+/// 拉取失败时的保守限制：只发白色弹幕，按默认字数上限
+impl Default for DanmakuConfig {
+    fn default() -> Self {
+        Self { colors: Vec::new(), max_length: DEFAULT_DANMAKU_LENGTH, fontsize: DANMAKU_FONTSIZE }
+    }
+}
+
+impl DanmakuConfig {
+    pub fn usable_colors(&self) -> impl Iterator<Item = &DanmakuColor> {
+        self.colors.iter().filter(|c| c.usable)
+    }
+
+    /// 发送前检查，返回不能发送的原因
+    pub fn check(&self, msg: &str, color: u32) -> Result<(), String> {
+        let len = msg.chars().count();
+        if len > self.max_length {
+            return Err(format!("弹幕最多 {} 个字，当前 {} 个", self.max_length, len));
+        }
+        if color != DEFAULT_DANMAKU_COLOR && !self.usable_colors().any(|c| c.color == color) {
+            return Err(format!("当前账号不能使用颜色 #{:06X}", color));
+        }
+        Ok(())
+    }
+}
+
+/// 弹幕连接推送的事件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DanmakuEvent {
+    Danmaku {
+        uid: u64,
+        user: String,
+        text: String,
+        /// Unix 秒
+        time: i64,
+    },
+    /// 进入直播间
+    Enter { uid: u64, user: String },
+    /// 上舰，level 1 总督 / 2 提督 / 3 舰长
+    Guard { uid: u64, user: String, level: u8 },
+    /// 送礼，`count` 为本次赠送的个数
+    Gift { uid: u64, user: String, gift: String, count: u32 },
+    /// 连接断开，随后会按退避自动重连
+    Disconnected { reason: String },
+    /// 第 attempt 次重连成功
+    Reconnected { attempt: u32 },
+}
+
+/// 一场直播内的互动计数，监听弹幕连接时逐条累加
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct DanmakuStats {
+    pub danmaku: u64,
+    /// 礼物个数（按每次赠送的数量累加）
+    pub gifts: u64,
+    pub guards: u64,
+    pub enters: u64,
+    /// 弹幕连接断开次数
+    pub disconnects: u64,
+}
+
+impl DanmakuStats {
+    pub fn record(&mut self, event: &DanmakuEvent) {
+        match event {
+            DanmakuEvent::Danmaku { .. } => self.danmaku += 1,
+            DanmakuEvent::Gift { count, .. } => self.gifts += u64::from(*count),
+            DanmakuEvent::Guard { .. } => self.guards += 1,
+            DanmakuEvent::Enter { .. } => self.enters += 1,
+            DanmakuEvent::Disconnected { .. } => self.disconnects += 1,
+            DanmakuEvent::Reconnected { .. } => {}
+        }
+    }
+
+    /// 当前累计值的副本，供界面展示或导出
+    pub fn snapshot(&self) -> DanmakuStats {
+        *self
+    }
+
+    /// 一行文字摘要
+    pub fn summary(&self) -> String {
+        format!("弹幕 {} 条，礼物 {} 个，上舰 {} 次，进场 {} 次", self.danmaku, self.gifts, self.guards, self.enters)
+    }
+}
+
+/// 多房间监听时带房间号的事件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoomDanmakuEvent {
+    pub room_id: i64,
+    pub event: DanmakuEvent,
+}
+
+/// 弹幕日志（JSONL）的一行，回放时按相邻两行 `at` 的间隔重新推送事件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DanmakuLogEntry {
+    /// 收到事件时的 Unix 毫秒
+    pub at: i64,
+    /// 单房间记录时可省略
+    #[serde(default)]
+    pub room_id: i64,
+    pub event: DanmakuEvent,
+}
+
+/// 自动回复的触发条件
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplyTrigger {
+    /// 弹幕包含关键词
+    #[default]
+    Keyword,
+    /// 有人进场
+    Enter,
+    /// 有人上舰
+    Guard,
+}
+
+impl ReplyTrigger {
+    pub const ALL: [ReplyTrigger; 3] = [ReplyTrigger::Keyword, ReplyTrigger::Enter, ReplyTrigger::Guard];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Keyword => "关键词",
+            Self::Enter => "进场",
+            Self::Guard => "上舰",
+        }
+    }
+}
+
+/// 一条自动回复规则，回复模板支持 `{user}`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct AutoReplyRule {
+    pub trigger: ReplyTrigger,
+    /// 仅 Keyword 使用
+    #[serde(default)]
+    pub keyword: String,
+    pub reply: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoReplyConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 两次自动回复的最小间隔（秒），间隔内触发的回复直接丢弃
+    #[serde(default = "default_reply_interval")]
+    pub min_interval_secs: u64,
+    #[serde(default)]
+    pub rules: Vec<AutoReplyRule>,
+}
+
+fn default_reply_interval() -> u64 {
+    5
+}
+
+impl Default for AutoReplyConfig {
+    fn default() -> Self {
+        Self { enabled: false, min_interval_secs: default_reply_interval(), rules: Vec::new() }
+    }
+}
+
+impl AutoReplyConfig {
+    /// 按顺序取第一条匹配的规则生成回复；`self_uid` 发出的弹幕不触发，避免自问自答
+    pub fn reply_for(&self, event: &DanmakuEvent, self_uid: u64) -> Option<String> {
+        let (trigger, uid, user, text) = match event {
+            DanmakuEvent::Danmaku { uid, user, text, .. } => (ReplyTrigger::Keyword, *uid, user, text.as_str()),
+            DanmakuEvent::Enter { uid, user } => (ReplyTrigger::Enter, *uid, user, ""),
+            DanmakuEvent::Guard { uid, user, .. } => (ReplyTrigger::Guard, *uid, user, ""),
+            DanmakuEvent::Gift { .. } | DanmakuEvent::Disconnected { .. } | DanmakuEvent::Reconnected { .. } => return None,
+        };
+        if uid != 0 && uid == self_uid {
+            return None;
+        }
+        self.rules
+            .iter()
+            .filter(|r| r.trigger == trigger && !r.reply.trim().is_empty())
+            .find(|r| trigger != ReplyTrigger::Keyword || (!r.keyword.is_empty() && text.contains(&r.keyword)))
+            .map(|r| r.reply.replace("{user}", user))
+    }
+}
+
+/// 推流脚本所用的命令行风格
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShellKind {
+    /// sh / bash
+    Posix,
+    /// Windows cmd（.bat）
+    Cmd,
+}
+
+impl ShellKind {
+    /// 当前平台默认的命令行
+    pub fn current() -> Self {
+        if cfg!(windows) {
+            Self::Cmd
+        } else {
+            Self::Posix
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Posix => "sh",
+            Self::Cmd => "bat",
+        }
+    }
+
+    /// 用双引号包住参数；推流地址含 `&`，不加引号会被命令行截断
+    fn quote(&self, arg: &str) -> String {
+        match self {
+            Self::Posix => {
+                let escaped: String = arg
+                    .chars()
+                    .flat_map(|c| if matches!(c, '"' | '\\' | '$' | '`') { vec!['\\', c] } else { vec![c] })
+                    .collect();
+                format!("\"{}\"", escaped)
+            }
+            Self::Cmd => format!("\"{}\"", arg.replace('"', "").replace('%', "%%")),
+        }
+    }
+
+    /// 把一条命令包装成可直接运行的脚本
+    pub fn script(&self, command: &str) -> String {
+        match self {
+            Self::Posix => format!("#!/bin/sh\nexec {}\n", command),
+            Self::Cmd => format!("@echo off\r\n{}\r\npause\r\n", command),
+        }
+    }
+}
+
+/// ffmpeg 推流参数，不用 OBS 时可直接把本地文件或设备推到直播间
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FfmpegConfig {
+    /// 输入源：文件路径、设备或网络地址
+    #[serde(default)]
+    pub input: String,
+    /// 放在 `-i` 之前的输入参数，推本地文件时需要 `-re` 按实际速率读取
+    #[serde(default = "default_ffmpeg_input_args")]
+    pub input_args: String,
+    /// 编码参数
+    #[serde(default = "default_ffmpeg_encode_args")]
+    pub encode_args: String,
+}
+
+fn default_ffmpeg_input_args() -> String {
+    "-re".to_string()
+}
+
+fn default_ffmpeg_encode_args() -> String {
+    "-c:v libx264 -preset veryfast -b:v 3000k -maxrate 3000k -bufsize 6000k -g 60 -c:a aac -b:a 160k -ar 44100".to_string()
+}
+
+impl Default for FfmpegConfig {
+    fn default() -> Self {
+        Self { input: String::new(), input_args: default_ffmpeg_input_args(), encode_args: default_ffmpeg_encode_args() }
+    }
+}
+
+impl FfmpegConfig {
+    /// 生成推流到 `addr` + `key` 的 ffmpeg 命令，`input` 为空时使用配置中的输入源
+    pub fn command(&self, addr: &str, key: &str, input: &str, shell: ShellKind) -> String {
+        let url = StreamLine { addr: addr.to_string(), code: key.to_string() }.full_url();
+        // SRT 推流要求 TS 封装
+        let format = if url.starts_with("srt://") { "-f mpegts" } else { "-f flv" };
+        let input = if input.is_empty() { self.input.as_str() } else { input };
+        [
+            "ffmpeg",
+            self.input_args.trim(),
+            "-i",
+            &shell.quote(input),
+            self.encode_args.trim(),
+            format,
+            &shell.quote(&url),
+        ]
+        .iter()
+        .filter(|part| !part.is_empty())
+        .copied()
+        .collect::<Vec<_>>()
+        .join(" ")
+    }
+}
+
+/// 用默认编码参数生成当前平台的 ffmpeg 推流命令
+pub fn generate_ffmpeg_command(addr: &str, key: &str, input: &str) -> String {
+    FfmpegConfig::default().command(addr, key, input, ShellKind::current())
+}
+
+impl RoomInfo {
+    /// 本场已播秒数，`now` 为 Unix 秒；未开播时为 0
+    pub fn live_secs(&self, now: i64) -> i64 {
+        if self.live_status == 1 && self.live_start > 0 { (now - self.live_start).max(0) } else { 0 }
+    }
+}
+
+/// 直播状态 JSON 的输出方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusOutputMode {
+    /// 定时覆盖写文件（先写临时文件再改名，读取方不会读到半截内容）
+    #[default]
+    File,
+    /// 命名管道：Unix 下为 FIFO，Windows 下为 `\\.\pipe\名称`，每次写一行 JSON
+    Pipe,
+}
+
+/// 把直播状态持续输出给本地 overlay / 脚本的配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusOutputConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub mode: StatusOutputMode,
+    #[serde(default)]
+    pub path: String,
+    /// 刷新间隔（秒）
+    #[serde(default = "default_status_interval")]
+    pub interval_secs: u64,
+}
+
+fn default_status_interval() -> u64 {
+    2
+}
+
+impl Default for StatusOutputConfig {
+    fn default() -> Self {
+        Self { enabled: false, mode: StatusOutputMode::default(), path: String::new(), interval_secs: default_status_interval() }
+    }
+}
+
+/// 输出给 overlay 的直播状态，序列化为一行 JSON
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LiveStatus {
+    pub room_id: u64,
+    /// 0 未开播 / 1 直播中 / 2 轮播
+    pub live_status: i32,
+    pub online: u64,
+    /// 本场已播秒数
+    pub duration: i64,
+    pub title: String,
+    pub area: String,
+    /// 生成时间（Unix 秒）
+    pub time: i64,
+}
+
+impl LiveStatus {
+    pub fn from_room(room: &RoomInfo, now: i64) -> Self {
+        Self {
+            room_id: room.room_id,
+            live_status: room.live_status,
+            online: room.online,
+            duration: room.live_secs(now),
+            title: room.title.clone(),
+            area: room.area_name.clone(),
+            time: now,
+        }
+    }
+}
+
+/// OBS 文本源输出配置。模板占位符：`{title}` `{area}` `{online}` `{duration}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObsTextConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub path: String,
+    #[serde(default = "default_obs_template")]
+    pub template: String,
+    /// 刷新间隔（秒）
+    #[serde(default = "default_obs_interval")]
+    pub interval_secs: u64,
+}
+
+fn default_obs_template() -> String {
+    "{title}\n{area} | 人气：{online} | 已播 {duration}".to_string()
+}
+
+fn default_obs_interval() -> u64 {
+    10
+}
+
+impl Default for ObsTextConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: String::new(),
+            template: default_obs_template(),
+            interval_secs: default_obs_interval(),
+        }
+    }
+}
+
+impl ObsTextConfig {
+    /// 用直播间信息填充模板，`now` 为 Unix 秒；未开播时直播时长为 00:00:00
+    pub fn render(&self, room: &RoomInfo, now: i64) -> String {
+        let secs = room.live_secs(now);
+        let duration = format!("{:02}:{:02}:{:02}", secs / 3600, secs % 3600 / 60, secs % 60);
+        self.template
+            .replace("{title}", &room.title)
+            .replace("{area}", &room.area_name)
+            .replace("{online}", &room.online.to_string())
+            .replace("{duration}", &duration)
+    }
+}
+
+fn default_stats_interval() -> u64 {
+    60
+}
+
+fn default_ui_scale() -> f32 {
+    1.0
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// 后台轮询与重试的间隔（秒）。弱网或省电时可以调大，缺省值与早期版本的固定间隔一致
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PollingConfig {
+    /// 查询扫码登录结果
+    pub qr_poll_secs: u64,
+    /// 获取登录二维码失败后重试
+    pub qr_retry_secs: u64,
+    /// 获取用户与直播间信息失败后重试
+    pub user_info_retry_secs: u64,
+    /// 刷新 PK 状态
+    pub interact_secs: u64,
+    /// 后台复查登录态
+    pub login_check_secs: u64,
+    /// 离线模式下探测网络是否恢复
+    pub offline_probe_secs: u64,
+    /// 弹幕连接异常断开后重连
+    pub reconnect_secs: u64,
+    /// 成员页距上次刷新多久后重新拉取
+    pub members_secs: u64,
+    /// 开播后确认直播上线
+    pub live_check_secs: u64,
+    /// 查询人脸认证结果
+    pub face_auth_secs: u64,
+    /// 多房间监听时单个房间重连次数耗尽后，重新建立连接前的等待
+    pub danmaku_retry_secs: u64,
+    /// 编辑中的标题写入本地草稿
+    pub title_draft_secs: u64,
+}
+
+impl Default for PollingConfig {
+    fn default() -> Self {
+        Self {
+            qr_poll_secs: 2,
+            qr_retry_secs: 5,
+            user_info_retry_secs: 5,
+            interact_secs: 30,
+            login_check_secs: 300,
+            offline_probe_secs: 30,
+            reconnect_secs: 30,
+            members_secs: 60,
+            live_check_secs: 3,
+            face_auth_secs: 3,
+            danmaku_retry_secs: 5,
+            title_draft_secs: 5,
+        }
+    }
+}
+
+/// 轮询间隔的可选范围（秒）
+pub const POLLING_RANGE: std::ops::RangeInclusive<u64> = 1..=3600;
+
+impl PollingConfig {
+    /// 设置页展示的各项间隔：名称与对应字段
+    pub fn fields_mut(&mut self) -> [(&'static str, &mut u64); 12] {
+        [
+            ("扫码结果查询", &mut self.qr_poll_secs),
+            ("二维码获取失败重试", &mut self.qr_retry_secs),
+            ("用户信息获取失败重试", &mut self.user_info_retry_secs),
+            ("PK 状态刷新", &mut self.interact_secs),
+            ("登录态复查", &mut self.login_check_secs),
+            ("离线时探测网络", &mut self.offline_probe_secs),
+            ("弹幕断线重连", &mut self.reconnect_secs),
+            ("成员列表刷新", &mut self.members_secs),
+            ("开播后确认上线", &mut self.live_check_secs),
+            ("人脸认证结果查询", &mut self.face_auth_secs),
+            ("弹幕重连失败后等待", &mut self.danmaku_retry_secs),
+            ("标题草稿保存", &mut self.title_draft_secs),
+        ]
+    }
+
+    /// 秒数转为间隔，手动改坏的配置（如 0）按范围修正，避免空转
+    pub fn interval(secs: u64) -> std::time::Duration {
+        std::time::Duration::from_secs(secs.clamp(*POLLING_RANGE.start(), *POLLING_RANGE.end()))
+    }
+}
+
+/// 直播间封面的两份本地存档。B 站只有一个封面字段，没有单独的未开播封面，切换靠重新上传
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoverSlot {
+    Live,
+    Standby,
+}
+
+impl CoverSlot {
+    pub const ALL: [CoverSlot; 2] = [CoverSlot::Live, CoverSlot::Standby];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CoverSlot::Live => "开播封面",
+            CoverSlot::Standby => "待机封面",
+        }
+    }
+
+    /// 配置目录 `covers/` 下的文件名
+    pub fn file_name(self) -> &'static str {
+        match self {
+            CoverSlot::Live => "live.jpg",
+            CoverSlot::Standby => "standby.jpg",
+        }
+    }
+}
+
+/// 预告文字写到哪里。B 站没有"未开播文案"字段，只能借用公告或简介
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum NoticeTarget {
+    #[default]
+    News,
+    Description,
+}
+
+impl NoticeTarget {
+    pub const ALL: [NoticeTarget; 2] = [NoticeTarget::News, NoticeTarget::Description];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            NoticeTarget::News => "公告",
+            NoticeTarget::Description => "简介",
+        }
+    }
+}
+
+/// 未开播时展示的待机封面与开播预告
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct StandbyConfig {
+    /// 预告文字，如"下次开播：周五 20:00"
+    pub notice: String,
+    pub target: NoticeTarget,
+    /// 关播后自动换上待机封面并写入预告，开播后换回开播封面并移除简介里的预告
+    pub auto_switch: bool,
+}
+
+/// 简介里预告行的前缀，再次写入或开播后移除时按它找到旧的预告
+pub const STANDBY_NOTICE_PREFIX: &str = "【开播预告】";
+
+/// 把预告写成简介的第一行并去掉旧的预告行，其余内容不变；`notice` 为空时只移除
+pub fn with_standby_notice(desc: &str, notice: &str) -> String {
+    let rest = desc.lines().filter(|line| !line.starts_with(STANDBY_NOTICE_PREFIX)).collect::<Vec<_>>().join("\n");
+    let notice = notice.split_whitespace().collect::<Vec<_>>().join(" ");
+    match (notice.is_empty(), rest.is_empty()) {
+        (true, _) => rest,
+        (false, true) => format!("{}{}", STANDBY_NOTICE_PREFIX, notice),
+        (false, false) => format!("{}{}\n{}", STANDBY_NOTICE_PREFIX, notice, rest),
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            templates: Vec::new(),
+            live_reminder_minutes: 0,
+            webhook_url: None,
+            area_usage: Vec::new(),
+            rollback_failed_start: true,
+            obs_text: ObsTextConfig::default(),
+            status_output: StatusOutputConfig::default(),
+            auto_reply: AutoReplyConfig::default(),
+            ffmpeg: FfmpegConfig::default(),
+            stats_interval_secs: default_stats_interval(),
+            ui_scale: default_ui_scale(),
+            confirm_cookie_refresh: true,
+            live_count: 0,
+            proxy: None,
+            proxy_rules: Vec::new(),
+            stream_protocol: StreamProtocol::Rtmp,
+            proxy_failover: false,
+            developer_mode: false,
+            polling: PollingConfig::default(),
+            standby: StandbyConfig::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn template(&self, name: &str) -> Option<&LiveTemplate> {
+        self.templates.iter().find(|t| t.name == name)
+    }
+
+    /// 新增模板，同名则覆盖
+    pub fn upsert_template(&mut self, template: LiveTemplate) {
+        match self.templates.iter_mut().find(|t| t.name == template.name) {
+            Some(existing) => *existing = template,
+            None => self.templates.push(template),
+        }
+    }
+
+    /// 批量合并模板，按 name 去重；同名时 `overwrite` 为 true 则覆盖，否则保留已有的
+    pub fn merge_templates(&mut self, templates: Vec<LiveTemplate>, overwrite: bool) -> TemplateMerge {
+        let mut merge = TemplateMerge::default();
+        for template in templates {
+            match self.templates.iter_mut().find(|t| t.name == template.name) {
+                Some(existing) if overwrite => {
+                    *existing = template;
+                    merge.replaced += 1;
+                }
+                Some(_) => merge.skipped += 1,
+                None => {
+                    self.templates.push(template);
+                    merge.added += 1;
+                }
+            }
+        }
+        merge
+    }
+
+    /// 删除模板，返回是否存在
+    pub fn remove_template(&mut self, name: &str) -> bool {
+        let before = self.templates.len();
+        self.templates.retain(|t| t.name != name);
+        self.templates.len() != before
+    }
+
+    /// 记录一次分区使用，`now` 为 Unix 秒
+    pub fn record_area_use(&mut self, area_id: i64, now: i64) {
+        match self.area_usage.iter_mut().find(|u| u.area_id == area_id) {
+            Some(usage) => {
+                usage.count += 1;
+                usage.last_used = now;
+            }
+            None => self.area_usage.push(AreaUsage { area_id, count: 1, last_used: now }),
+        }
+    }
+
+    /// 渲染标题模板用的上下文。未开播时 `{count}` 指即将开始的这一场
+    pub fn title_context(&self, now: i64, live: bool) -> TitleContext {
+        TitleContext { now, count: if live { self.live_count } else { self.live_count + 1 } }
+    }
+
+    /// 最常用的 `n` 个分区：按使用次数降序，次数相同时最近使用的在前
+    pub fn top_used_areas(&self, n: usize) -> Vec<i64> {
+        let mut usage: Vec<&AreaUsage> = self.area_usage.iter().collect();
+        usage.sort_by(|a, b| b.count.cmp(&a.count).then(b.last_used.cmp(&a.last_used)));
+        usage.into_iter().take(n).map(|u| u.area_id).collect()
+    }
+
+    /// 最近一次使用的分区
+    pub fn last_used_area(&self) -> Option<i64> {
+        self.area_usage.iter().max_by_key(|u| u.last_used).map(|u| u.area_id)
+    }
+}
+
+/// 推送到 webhook 的事件载荷
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiveEvent {
+    /// `live_start` / `live_stop` / `error`
+    pub event: String,
+    pub room_id: i64,
+    /// Unix 秒
+    pub time: i64,
+    pub detail: String,
+}
+
+impl LiveEvent {
+    pub fn new(event: &str, room_id: i64, detail: impl Into<String>) -> Self {
+        let time = now_secs();
+        Self { event: event.to_string(), room_id, time, detail: detail.into() }
+    }
+}
+
+/// 单个域名的连通性探测结果
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct HostDiag {
+    pub host: String,
+    pub reachable: bool,
+    /// 收到响应所用的毫秒数，不可达时为 0
+    pub latency_ms: u64,
+    pub error: Option<String>,
+}
+
+/// 网络诊断结果，每个关键域名一项
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct NetworkDiag {
+    pub hosts: Vec<HostDiag>,
+}
+
+impl NetworkDiag {
+    pub fn all_reachable(&self) -> bool {
+        self.hosts.iter().all(|h| h.reachable)
+    }
+}
+
+/// 开发者模式下记录的一次接口请求与原始响应，csrf 等敏感参数已打码
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct ApiExchange {
+    /// Unix 秒
+    pub time: i64,
+    pub method: String,
+    pub url: String,
+    /// 表单参数，GET 请求为空
+    pub params: Vec<(String, String)>,
+    /// HTTP 状态码，连接失败时为 None
+    pub status: Option<u16>,
+    /// 原始响应正文（JSON 会格式化），连接失败时为错误信息
+    pub response: String,
+}
+
+impl ApiExchange {
+    /// 请求时间（北京时间 `MM-DD HH:MM`）
+    pub fn time_label(&self) -> String {
+        beijing_minute_label(self.time)
+    }
+
+    /// 复制给开发者的完整文本
+    pub fn to_report(&self) -> String {
+        let status = self.status.map(|s| s.to_string()).unwrap_or_else(|| "请求失败".to_string());
+        let mut report = format!("{} {}\nHTTP {}\n", self.method, self.url, status);
+        for (k, v) in &self.params {
+            report.push_str(&format!("{}={}\n", k, v));
+        }
+        report.push('\n');
+        report.push_str(&self.response);
+        report
+    }
+}
+
+/// 单个接口在本进程内的调用指标
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct EndpointMetrics {
+    /// 域名加路径，不含查询参数
+    pub endpoint: String,
+    pub calls: u64,
+    pub failures: u64,
+    /// HTTP 412 或 code -412（风控拦截）的次数
+    pub blocked: u64,
+    pub total_ms: u64,
+}
+
+impl EndpointMetrics {
+    pub fn avg_ms(&self) -> u64 {
+        self.total_ms.checked_div(self.calls).unwrap_or(0)
+    }
+}
+
+/// 配置包格式版本，导入时拒绝比当前更新的版本
+pub const PROFILE_FORMAT_VERSION: u32 = 1;
+
+/// 迁移用的完整配置包：登录信息 + 用户配置
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ProfileBundle {
+    pub format_version: u32,
+    /// 导出时的程序版本，仅供参考
+    #[serde(default)]
+    pub app_version: String,
+    pub auth: AuthData,
+    #[serde(default)]
+    pub settings: Settings,
+}
+
+/// 开播时要求的人脸认证，用 B 站 App 扫描 `qr_url` 完成
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FaceAuthInfo {
+    pub room_id: i64,
+    pub qr_url: String,
+}
+
+/// 可撤销的房管操作
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ModAction {
+    /// 禁言，撤销即解除禁言
+    Silence { room_id: i64, uid: u64 },
+    /// 任命房管，撤销即撤掉房管
+    AppointAdmin { room_id: i64, uid: u64 },
+}
+
+impl ModAction {
+    pub fn label(&self) -> String {
+        match self {
+            Self::Silence { uid, .. } => format!("禁言 UID {}", uid),
+            Self::AppointAdmin { uid, .. } => format!("任命房管 UID {}", uid),
+        }
+    }
+}
+
+/// 最近执行的房管操作，只有在 `window_secs` 内的才能撤销，过期的在访问时丢弃
+#[derive(Debug, Clone, Default)]
+pub struct UndoStack {
+    window_secs: i64,
+    /// (操作, 执行时的 Unix 秒)，最新的在末尾
+    entries: Vec<(ModAction, i64)>,
+}
+
+impl UndoStack {
+    pub fn new(window_secs: i64) -> Self {
+        Self { window_secs, entries: Vec::new() }
+    }
+
+    pub fn push(&mut self, action: ModAction, now: i64) {
+        self.prune(now);
+        self.entries.push((action, now));
+    }
+
+    /// 最近一次仍可撤销的操作与剩余秒数
+    pub fn latest(&mut self, now: i64) -> Option<(&ModAction, i64)> {
+        self.prune(now);
+        let window = self.window_secs;
+        self.entries.last().map(|(action, at)| (action, at + window - now))
+    }
+
+    /// 取出最近一次仍可撤销的操作
+    pub fn pop(&mut self, now: i64) -> Option<ModAction> {
+        self.prune(now);
+        self.entries.pop().map(|(action, _)| action)
+    }
+
+    fn prune(&mut self, now: i64) {
+        let window = self.window_secs;
+        self.entries.retain(|(_, at)| now - at < window);
+    }
+}
+
+/// 构建时的 git 短提交哈希，不在 git 仓库中构建时为 "unknown"。以下构建信息均由 build.rs 注入
+pub const GIT_HASH: &str = env!("GIT_HASH");
+/// 构建时间（UTC，精确到分钟）
+pub const BUILD_TIME: &str = env!("BUILD_TIME");
+pub const BUILD_DATE: &str = env!("BUILD_DATE");
+/// 目标平台 triple，如 x86_64-pc-windows-msvc
+pub const BUILD_TARGET: &str = env!("BUILD_TARGET");
+
+/// 报 bug 时用的版本行：`v0.1.0 (abc1234, 2024-01-02)`
+pub fn version_line(pkg_version: &str) -> String {
+    format!("v{} ({}, {})", pkg_version, GIT_HASH, BUILD_DATE)
+}
+
+/// `--version` 的完整输出，多带构建时间与目标平台
+pub fn long_version(pkg_version: &str) -> String {
+    format!("{}\n构建时间: {}\n目标平台: {}", version_line(pkg_version), BUILD_TIME, BUILD_TARGET)
+}
+
+/// 手填或来自配置 / 命令行的 id 种类，决定合法范围与报错用语
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdKind {
+    Room,
+    Area,
+    Uid,
+}
+
+impl IdKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            IdKind::Room => "直播间号",
+            IdKind::Area => "分区 id",
+            IdKind::Uid => "UID",
+        }
+    }
+
+    /// 允许的最大值，超出的必然是填错了
+    fn max(&self) -> i64 {
+        match self {
+            IdKind::Room => u32::MAX as i64,
+            IdKind::Area => 99_999,
+            IdKind::Uid => i64::MAX,
+        }
+    }
+}
+
+/// 校验单个 id：必须是不超过上限的正整数
+pub fn validate_id(kind: IdKind, id: i64) -> Result<i64, String> {
+    if id <= 0 || id > kind.max() {
+        return Err(format!("{}应为 1 到 {} 之间的整数: {}", kind.label(), kind.max(), id));
+    }
+    Ok(id)
+}
+
+/// 解析并校验输入框 / 命令行里的 id 文本，前后空白忽略
+pub fn parse_id(kind: IdKind, text: &str) -> Result<i64, String> {
+    let text = text.trim();
+    let id = text.parse::<i64>().map_err(|_| format!("{}只能填数字: {}", kind.label(), text))?;
+    validate_id(kind, id)
+}
+
+/// 发请求前统一校验直播间号与分区 id，返回第一个错误
+pub fn validate_ids(room_id: Option<i64>, area_id: Option<i64>) -> Result<(), String> {
+    if let Some(id) = room_id {
+        validate_id(IdKind::Room, id)?;
+    }
+    if let Some(id) = area_id {
+        validate_id(IdKind::Area, id)?;
+    }
+    Ok(())
+}
+
+/// 在分区树中查找子分区，返回 (父分区下标, 子分区下标)
+pub fn find_area(parents: &[AreaParent], area_id: i64) -> Option<(usize, usize)> {
+    parents.iter().enumerate().find_map(|(pi, p)| {
+        p.children.iter().position(|c| c.id == area_id).map(|ci| (pi, ci))
+    })
+}
+
+/// 开播 / 改分区只接受子分区，传入父分区或不存在的 id 时返回给用户看的错误说明
+pub fn leaf_area_error(parents: &[AreaParent], area_id: i64) -> Option<String> {
+    if find_area(parents, area_id).is_some() {
+        return None;
+    }
+    Some(match parents.iter().find(|p| p.id == area_id) {
+        Some(p) => format!("「{}」是父分区，请选择具体子分区", p.name),
+        None => format!("分区 {} 不存在，请选择具体子分区", area_id),
+    })
+}
+
+/// 父/子分区下拉框的选中下标。所有修改都经过这里，保证下标不会越过当前分区列表
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AreaSelection {
+    parent: usize,
+    child: usize,
+}
+
+impl AreaSelection {
+    pub fn parent(&self) -> usize {
+        self.parent
+    }
+
+    pub fn child(&self) -> usize {
+        self.child
+    }
+
+    /// 切换父分区，子分区重置为第一个
+    pub fn set_parent(&mut self, parents: &[AreaParent], parent: usize) {
+        if parent != self.parent {
+            self.parent = parent;
+            self.child = 0;
+        }
+        self.clamp(parents);
+    }
+
+    pub fn set_child(&mut self, parents: &[AreaParent], child: usize) {
+        self.child = child;
+        self.clamp(parents);
+    }
+
+    /// 按子分区 id 定位，找不到时保持原选择并返回 false
+    pub fn select_area(&mut self, parents: &[AreaParent], area_id: i64) -> bool {
+        match find_area(parents, area_id) {
+            Some((parent, child)) => {
+                *self = Self { parent, child };
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 分区列表（重新）加载后按 id 依次尝试定位，下标只是列表内的位置，不能跨列表沿用。
+    /// 都找不到时收回合法范围，返回最终选中的分区 id
+    pub fn relocate(&mut self, parents: &[AreaParent], ids: impl IntoIterator<Item = i64>) -> Option<i64> {
+        if !ids.into_iter().any(|id| self.select_area(parents, id)) {
+            self.clamp(parents);
+        }
+        self.area_id(parents)
+    }
+
+    /// 分区列表变化后把下标收回合法范围
+    pub fn clamp(&mut self, parents: &[AreaParent]) {
+        if self.parent >= parents.len() {
+            self.parent = 0;
+            self.child = 0;
+        }
+        let children = parents.get(self.parent).map(|p| p.children.len()).unwrap_or(0);
+        if self.child >= children {
+            self.child = 0;
+        }
+    }
+
+    /// 当前选中的子分区 id；列表为空或父分区没有子分区时为 None
+    pub fn area_id(&self, parents: &[AreaParent]) -> Option<i64> {
+        parents.get(self.parent)?.children.get(self.child).map(|c| c.id)
+    }
+}
+
+/// 图片上的 16:9 裁剪选区（像素坐标）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CropRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl CropRect {
+    /// 在 `width`×`height` 的图片上取最大的居中 16:9 选区
+    pub fn centered_16x9(width: u32, height: u32) -> Self {
+        let (w, h) = if width as u64 * 9 >= height as u64 * 16 {
+            ((height as u64 * 16 / 9) as u32, height)
+        } else {
+            (width, (width as u64 * 9 / 16) as u32)
+        };
+        Self { x: (width - w) / 2, y: (height - h) / 2, width: w, height: h }
+    }
+
+    /// 平移选区，结果限制在图片范围内
+    pub fn moved(self, dx: i64, dy: i64, image_width: u32, image_height: u32) -> Self {
+        let max_x = image_width.saturating_sub(self.width) as i64;
+        let max_y = image_height.saturating_sub(self.height) as i64;
+        Self {
+            x: (self.x as i64 + dx).clamp(0, max_x) as u32,
+            y: (self.y as i64 + dy).clamp(0, max_y) as u32,
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn areas() -> Vec<AreaParent> {
+        let child = |id, name: &str| AreaChild { id, name: name.to_string(), locked: false };
+        vec![
+            AreaParent { id: 1, name: "网游".into(), children: vec![child(10, "a"), child(11, "b"), child(12, "c")], ..AreaParent::default() },
+            AreaParent { id: 2, name: "手游".into(), children: vec![child(20, "d")], ..AreaParent::default() },
+            AreaParent { id: 3, name: "空".into(), children: vec![], ..AreaParent::default() },
+        ]
+    }
+
+    #[test]
+    fn ids_must_be_positive_and_in_range() {
+        assert_eq!(parse_id(IdKind::Room, " 21452505 "), Ok(21452505));
+        assert!(parse_id(IdKind::Room, "abc").is_err());
+        assert!(parse_id(IdKind::Room, "").is_err());
+        assert!(parse_id(IdKind::Area, "-3").is_err());
+        assert!(parse_id(IdKind::Area, "100000").is_err());
+        assert_eq!(parse_id(IdKind::Uid, "3546000000000000"), Ok(3546000000000000));
+        assert!(validate_ids(Some(1), Some(235)).is_ok());
+        assert!(validate_ids(Some(0), None).unwrap_err().contains("直播间号"));
+        assert!(validate_ids(None, Some(0)).unwrap_err().contains("分区"));
+    }
+
+    #[test]
+    fn render_title_template_fills_known_placeholders() {
+        // 2024-01-01 16:30 UTC，北京时间 2024-01-02 00:30 周二
+        let ctx = TitleContext { now: 1704126600, count: 12 };
+        assert_eq!(render_title_template("{date} 第{count}天 {weekday}", &ctx), "2024-01-02 第12天 周二");
+        assert_eq!(render_title_template("{date:%m-%d}杂谈", &ctx), "01-02杂谈");
+        assert_eq!(render_title_template("{unknown} {date:%Q} {date:} {", &ctx), "{unknown} {date:%Q} {date:} {");
+        assert_eq!(render_title_template("普通标题", &ctx), "普通标题");
+        assert_eq!(render_title_template("【{第{count}场}】", &ctx), "【{第12场}】");
+        let settings = Settings { live_count: 3, ..Settings::default() };
+        assert_eq!(settings.title_context(0, false).count, 4);
+        assert_eq!(settings.title_context(0, true).count, 3);
+    }
+
+    #[test]
+    fn top_used_areas_orders_by_count_then_recency() {
+        let mut settings = Settings::default();
+        settings.record_area_use(1, 100);
+        settings.record_area_use(2, 200);
+        settings.record_area_use(3, 50);
+        settings.record_area_use(3, 60);
+        assert_eq!(settings.top_used_areas(3), vec![3, 2, 1]);
+        assert_eq!(settings.top_used_areas(1), vec![3]);
+        assert_eq!(settings.area_usage.iter().find(|u| u.area_id == 3).map(|u| (u.count, u.last_used)), Some((2, 60)));
+    }
+
+    #[test]
+    fn stream_line_full_url_joins_server_and_key() {
+        let line = StreamLine {
+            addr: "rtmp://live-push.bilivideo.com/live-bvc/".into(),
+            code: "?streamname=live_1&key=abc".into(),
+        };
+        assert_eq!(line.server(), "rtmp://live-push.bilivideo.com/live-bvc/");
+        assert_eq!(line.stream_key(), "?streamname=live_1&key=abc");
+        assert_eq!(line.full_url(), "rtmp://live-push.bilivideo.com/live-bvc/?streamname=live_1&key=abc");
+        let bare = StreamLine { addr: "rtmp://host/app".into(), code: "live_1".into() };
+        assert_eq!(bare.full_url(), "rtmp://host/app/live_1");
+        let srt = StreamLine { addr: "srt://live-push.bilivideo.com:1937".into(), code: "#!::r=live-bvc/live_1,m=publish".into() };
+        assert_eq!(srt.full_url(), "srt://live-push.bilivideo.com:1937?streamid=#!::r=live-bvc/live_1,m=publish");
+    }
+
+    #[test]
+    fn stream_lines_pick_preferred_protocol_or_fall_back() {
+        let rtmp = StreamLine { addr: "rtmp://a/".into(), code: "k".into() };
+        let srt = StreamLine { addr: "srt://a:1937".into(), code: "s".into() };
+        let both = StreamLines { rtmp: Some(rtmp.clone()), srt: Some(srt.clone()) };
+        assert_eq!(both.pick(StreamProtocol::Srt), Some((StreamProtocol::Srt, &srt)));
+        assert_eq!(both.pick(StreamProtocol::Rtmp), Some((StreamProtocol::Rtmp, &rtmp)));
+        let rtmp_only = StreamLines { rtmp: Some(rtmp.clone()), srt: None };
+        let start = LiveStart::pick(rtmp_only, StreamProtocol::Srt, false).unwrap();
+        assert_eq!((start.protocol, start.line), (StreamProtocol::Rtmp, rtmp));
+        assert!(LiveStart::pick(StreamLines::default(), StreamProtocol::Rtmp, false).is_none());
+        assert!(StreamProtocol::Rtmp.matches_addr("rtmps://a/") && !StreamProtocol::Srt.matches_addr("rtmp://a/"));
+    }
+
+    #[test]
+    fn switching_parent_resets_child() {
+        let areas = areas();
+        let mut sel = AreaSelection::default();
+        sel.set_child(&areas, 2);
+        assert_eq!(sel.area_id(&areas), Some(12));
+        sel.set_parent(&areas, 1);
+        assert_eq!((sel.parent(), sel.child()), (1, 0));
+        assert_eq!(sel.area_id(&areas), Some(20));
+    }
+
+    #[test]
+    fn out_of_range_indices_are_clamped() {
+        let areas = areas();
+        let mut sel = AreaSelection::default();
+        sel.set_child(&areas, 5);
+        assert_eq!(sel.child(), 0);
+        sel.set_parent(&areas, 9);
+        assert_eq!((sel.parent(), sel.child()), (0, 0));
+        sel.set_parent(&areas, 2);
+        assert_eq!(sel.area_id(&areas), None);
+        assert_eq!(sel.area_id(&[]), None);
+    }
+
+    #[test]
+    fn list_shrinking_is_clamped() {
+        let mut areas = areas();
+        let mut sel = AreaSelection::default();
+        assert!(sel.select_area(&areas, 12));
+        areas[0].children.truncate(1);
+        sel.clamp(&areas);
+        assert_eq!(sel.area_id(&areas), Some(10));
+        assert!(!sel.select_area(&areas, 99));
+        assert_eq!(sel.area_id(&areas), Some(10));
+    }
+
+    #[test]
+    fn reordered_list_relocates_by_id() {
+        let mut areas = areas();
+        let mut sel = AreaSelection::default();
+        assert_eq!(sel.relocate(&areas, [11]), Some(11));
+        areas.reverse();
+        areas[2].children.reverse();
+        assert_eq!(sel.area_id(&areas), None);
+        assert_eq!(sel.relocate(&areas, [99, 11]), Some(11));
+        assert_eq!((sel.parent(), sel.child()), (2, 1));
+        assert_eq!(sel.relocate(&areas, []), Some(11));
+        assert_eq!(sel.relocate(&areas[..1], [11]), None);
+    }
+
+    #[test]
+    fn last_used_area_is_most_recent() {
+        let mut settings = Settings::default();
+        assert_eq!(settings.last_used_area(), None);
+        settings.record_area_use(1, 100);
+        settings.record_area_use(1, 110);
+        settings.record_area_use(2, 200);
+        settings.record_area_use(1, 150);
+        assert_eq!(settings.last_used_area(), Some(2));
+    }
+
+    #[test]
+    fn proxy_rules_parse_and_match_subdomains() {
+        let rules = parse_proxy_rules("# 图片直连\n*.hdslb.com off\n\n  bilibili.com   http://127.0.0.1:7890 \n").unwrap();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(format_proxy_rules(&rules), "*.hdslb.com off\nbilibili.com http://127.0.0.1:7890");
+        assert!(rules[0].matches("i0.HDSLB.com"));
+        assert!(rules[0].matches("hdslb.com"));
+        assert!(!rules[0].matches("nothdslb.com"));
+        assert!(rules[1].matches("api.live.bilibili.com"));
+        assert_eq!(parse_proxy_rules("bilibili.com").unwrap_err(), "第 1 行格式应为「域名 代理地址」或「域名 off」");
+        assert_eq!(parse_proxy_rules("").unwrap(), vec![]);
+    }
+
+    #[test]
+    fn cached_label_uses_beijing_time() {
+        // 2024-03-01 00:30 +08:00
+        let cached = Cached { saved_at: 1709224200, data: RoomMembers::default() };
+        assert_eq!(cached.saved_at_label(), "03-01 00:30");
+    }
+
+    #[test]
+    fn beijing_clock_wraps_at_midnight() {
+        assert_eq!(beijing_clock(0), "08:00:00");
+        assert_eq!(beijing_clock(16 * 3600 + 61), "00:01:01");
+        assert_eq!(beijing_clock(-1), "07:59:59");
+    }
+
+    #[test]
+    fn polling_interval_is_clamped() {
+        let mut polling = PollingConfig::default();
+        assert_eq!(PollingConfig::interval(polling.qr_poll_secs), std::time::Duration::from_secs(2));
+        assert_eq!(PollingConfig::interval(0), std::time::Duration::from_secs(1));
+        assert_eq!(PollingConfig::interval(u64::MAX), std::time::Duration::from_secs(3600));
+        for (_, secs) in polling.fields_mut() {
+            *secs = 120;
+        }
+        assert_eq!((polling.members_secs, polling.title_draft_secs), (120, 120));
+    }
+
+    #[test]
+    fn standby_notice_replaces_previous_line() {
+        let desc = with_standby_notice("欢迎来玩\n每周更新", "周五\n20:00");
+        assert_eq!(desc, "【开播预告】周五 20:00\n欢迎来玩\n每周更新");
+        assert_eq!(with_standby_notice(&desc, "周六 21:00"), "【开播预告】周六 21:00\n欢迎来玩\n每周更新");
+        assert_eq!(with_standby_notice(&desc, " "), "欢迎来玩\n每周更新");
+        assert_eq!(with_standby_notice("", "周五"), "【开播预告】周五");
+    }
+
+    #[test]
+    fn merge_templates_dedupes_by_name() {
+        let tpl = |name: &str, title: &str| LiveTemplate { name: name.into(), title: title.into(), area_id: 1, room_news: None };
+        let mut settings = Settings { templates: vec![tpl("a", "旧")], ..Default::default() };
+        let merge = settings.merge_templates(vec![tpl("a", "新"), tpl("b", "b")], false);
+        assert_eq!(merge, TemplateMerge { added: 1, replaced: 0, skipped: 1 });
+        assert_eq!(settings.template("a").map(|t| t.title.as_str()), Some("旧"));
+        let merge = settings.merge_templates(vec![tpl("a", "新")], true);
+        assert_eq!(merge.summary(), "新增 0 个，覆盖 1 个，跳过 0 个");
+        assert_eq!(settings.template("a").map(|t| t.title.as_str()), Some("新"));
+        assert_eq!(settings.templates.len(), 2);
+    }
+
+    #[test]
+    fn activity_time_label_omits_missing_end() {
+        let mut activity = LiveActivity { start_time: 1709224200, end_time: 1709310600, ..Default::default() };
+        assert_eq!(activity.time_label(), "03-01 00:30 ~ 03-02 00:30");
+        activity.end_time = 0;
+        assert_eq!(activity.time_label(), "03-01 00:30 起");
+        activity.start_time = 0;
+        assert_eq!(activity.time_label(), "");
+    }
+
+    #[test]
+    fn room_description_length_limit() {
+        assert!(check_room_description("").is_ok());
+        assert!(check_room_description(&"简".repeat(ROOM_DESCRIPTION_MAX)).is_ok());
+        assert_eq!(check_room_description(&"简".repeat(ROOM_DESCRIPTION_MAX + 1)).unwrap_err(), "简介最多 1000 个字，当前 1001 个");
+    }
+
+    #[test]
+    fn danmaku_config_limits_length_and_color() {
+        let config = DanmakuConfig {
+            colors: vec![
+                DanmakuColor { name: "白色".into(), color: DEFAULT_DANMAKU_COLOR, usable: true },
+                DanmakuColor { name: "红色".into(), color: 0xFF6868, usable: true },
+                DanmakuColor { name: "金色".into(), color: 0xE2B233, usable: false },
+            ],
+            max_length: 3,
+            fontsize: 25,
+        };
+        assert_eq!(config.check("晚上好", 0xFF6868), Ok(()));
+        assert_eq!(config.check("晚上好呀", DEFAULT_DANMAKU_COLOR).unwrap_err(), "弹幕最多 3 个字，当前 4 个");
+        assert_eq!(config.check("好", 0xE2B233).unwrap_err(), "当前账号不能使用颜色 #E2B233");
+        assert_eq!(config.usable_colors().count(), 2);
+    }
+
+    #[test]
+    fn centered_crop_keeps_16x9() {
+        assert_eq!(CropRect::centered_16x9(1000, 1000), CropRect { x: 0, y: 219, width: 1000, height: 562 });
+        assert_eq!(CropRect::centered_16x9(4000, 900), CropRect { x: 1200, y: 0, width: 1600, height: 900 });
+        assert_eq!(CropRect::centered_16x9(1920, 1080), CropRect { x: 0, y: 0, width: 1920, height: 1080 });
+    }
+
+    #[test]
+    fn moved_crop_stays_inside_image() {
+        let rect = CropRect::centered_16x9(1000, 1000);
+        assert_eq!(rect.moved(50, -500, 1000, 1000), CropRect { x: 0, y: 0, ..rect });
+        assert_eq!(rect.moved(0, 5000, 1000, 1000).y, 438);
+    }
+
+    #[test]
+    fn redact_hides_content_and_length() {
+        assert_eq!(redact(""), "");
+        assert_eq!(redact("1"), "******");
+        assert_eq!(redact("?streamname=live_1&key=abcdef0123456789"), "******");
+    }
+
+    #[test]
+    fn obs_text_fills_placeholders() {
+        let config = ObsTextConfig { template: "{title} [{area}] 人气：{online} {duration}".into(), ..ObsTextConfig::default() };
+        let mut room = RoomInfo { title: "杂谈".into(), area_name: "聊天".into(), online: 1234, live_status: 1, live_start: 1_000, ..RoomInfo::default() };
+        assert_eq!(config.render(&room, 1_000 + 3_723), "杂谈 [聊天] 人气：1234 01:02:03");
+        room.live_status = 0;
+        assert_eq!(config.render(&room, 1_000 + 3_723), "杂谈 [聊天] 人气：1234 00:00:00");
+    }
+
+    #[test]
+    fn live_status_reports_duration_only_while_live() {
+        let mut room = RoomInfo { room_id: 7, title: "杂谈".into(), area_name: "聊天".into(), online: 10, live_status: 1, live_start: 1_000, ..RoomInfo::default() };
+        let status = LiveStatus::from_room(&room, 1_060);
+        assert_eq!((status.room_id, status.duration, status.online, status.time), (7, 60, 10, 1_060));
+        room.live_status = 0;
+        assert_eq!(LiveStatus::from_room(&room, 1_060).duration, 0);
+    }
+
+    #[test]
+    fn interact_status_from_pk_fields() {
+        assert_eq!(InteractStatus::from_pk_fields(0, 0), InteractStatus::Idle);
+        assert_eq!(InteractStatus::from_pk_fields(1, 0), InteractStatus::Pk);
+        assert_eq!(InteractStatus::from_pk_fields(0, 42), InteractStatus::Pk);
+    }
+
+    #[test]
+    fn auto_reply_matches_rules_in_order() {
+        let rule = |trigger, keyword: &str, reply: &str| AutoReplyRule { trigger, keyword: keyword.into(), reply: reply.into() };
+        let config = AutoReplyConfig {
+            enabled: true,
+            min_interval_secs: 5,
+            rules: vec![
+                rule(ReplyTrigger::Keyword, "歌单", "{user}，歌单在简介"),
+                rule(ReplyTrigger::Keyword, "", "空关键词不匹配"),
+                rule(ReplyTrigger::Enter, "", "欢迎 {user}"),
+            ],
+        };
+        let danmaku = |uid, text: &str| DanmakuEvent::Danmaku { uid, user: "阿B".into(), text: text.into(), time: 0 };
+        assert_eq!(config.reply_for(&danmaku(1, "求歌单"), 99).as_deref(), Some("阿B，歌单在简介"));
+        assert_eq!(config.reply_for(&danmaku(1, "晚上好"), 99), None);
+        assert_eq!(config.reply_for(&danmaku(99, "求歌单"), 99), None);
+        assert_eq!(config.reply_for(&DanmakuEvent::Enter { uid: 2, user: "小明".into() }, 99).as_deref(), Some("欢迎 小明"));
+        assert_eq!(config.reply_for(&DanmakuEvent::Guard { uid: 2, user: "小明".into(), level: 3 }, 99), None);
+    }
+
+    #[test]
+    fn danmaku_stats_counts_each_kind() {
+        let mut stats = DanmakuStats::default();
+        stats.record(&DanmakuEvent::Danmaku { uid: 1, user: "a".into(), text: "hi".into(), time: 0 });
+        stats.record(&DanmakuEvent::Gift { uid: 1, user: "a".into(), gift: "辣条".into(), count: 10 });
+        stats.record(&DanmakuEvent::Gift { uid: 2, user: "b".into(), gift: "小心心".into(), count: 1 });
+        stats.record(&DanmakuEvent::Guard { uid: 2, user: "b".into(), level: 3 });
+        stats.record(&DanmakuEvent::Enter { uid: 3, user: "c".into() });
+        stats.record(&DanmakuEvent::Reconnected { attempt: 1 });
+        assert_eq!(stats.snapshot(), DanmakuStats { danmaku: 1, gifts: 11, guards: 1, enters: 1, disconnects: 0 });
+        assert_eq!(stats.summary(), "弹幕 1 条，礼物 11 个，上舰 1 次，进场 1 次");
+    }
+
+    #[test]
+    fn stats_ring_drops_oldest_and_finds_nearest() {
+        let sample = |time, popularity| StatsSnapshot { time, popularity, ..StatsSnapshot::default() };
+        let mut ring = StatsRing::new(3);
+        for t in 1..=4 {
+            ring.push(sample(t * 10, t as u64));
+        }
+        assert_eq!(ring.samples().map(|s| s.time).collect::<Vec<_>>(), vec![20, 30, 40]);
+        assert_eq!(ring.nearest(26).map(|s| s.time), Some(30));
+        assert_eq!(ring.nearest(0).map(|s| s.time), Some(20));
+        ring.clear();
+        assert!(ring.nearest(0).is_none());
+    }
+
+    #[test]
+    fn normalize_area_list_drops_empty_parents_and_sorts() {
+        let child = AreaChild { id: 1, name: "子".into(), locked: false };
+        let parent = |id, sort, children: Vec<AreaChild>| AreaParent { id, name: id.to_string(), sort, children, ..AreaParent::default() };
+        let mut areas = vec![
+            parent(1, 0, vec![child.clone()]),
+            parent(2, 3, vec![child.clone()]),
+            parent(3, 1, Vec::new()),
+            parent(4, 0, vec![child.clone()]),
+            parent(5, 1, vec![child]),
+        ];
+        normalize_area_list(&mut areas);
+        assert_eq!(areas.iter().map(|p| p.id).collect::<Vec<_>>(), vec![5, 2, 1, 4]);
+    }
+
+    #[test]
+    fn ffmpeg_command_quotes_input_and_url() {
+        let config = FfmpegConfig { encode_args: "-c copy".into(), ..FfmpegConfig::default() };
+        let addr = "rtmp://live-push.bilivideo.com/live-bvc/";
+        let key = "?streamname=live_1&key=abc";
+        assert_eq!(
+            config.command(addr, key, "/tmp/my $video.mp4", ShellKind::Posix),
+            r#"ffmpeg -re -i "/tmp/my \$video.mp4" -c copy -f flv "rtmp://live-push.bilivideo.com/live-bvc/?streamname=live_1&key=abc""#
+        );
+        assert_eq!(
+            config.command(addr, key, r"C:\videos\100%.mp4", ShellKind::Cmd),
+            r#"ffmpeg -re -i "C:\videos\100%%.mp4" -c copy -f flv "rtmp://live-push.bilivideo.com/live-bvc/?streamname=live_1&key=abc""#
+        );
+        assert!(ShellKind::Posix.script("ffmpeg").starts_with("#!/bin/sh\nexec ffmpeg"));
+    }
+
+    #[test]
+    fn undo_stack_expires_after_window() {
+        let mut stack = UndoStack::new(10);
+        stack.push(ModAction::Silence { room_id: 1, uid: 7 }, 100);
+        stack.push(ModAction::AppointAdmin { room_id: 1, uid: 8 }, 105);
+        assert_eq!(stack.latest(106), Some((&ModAction::AppointAdmin { room_id: 1, uid: 8 }, 9)));
+        assert_eq!(stack.pop(106), Some(ModAction::AppointAdmin { room_id: 1, uid: 8 }));
+        // 第一条在 110 时过期
+        assert_eq!(stack.pop(110), None);
+        stack.push(ModAction::Silence { room_id: 1, uid: 9 }, 200);
+        assert!(stack.latest(215).is_none());
+    }
+
+    #[test]
+    fn version_line_has_hash_and_date() {
+        let line = version_line("1.2.3");
+        assert_eq!(line, format!("v1.2.3 ({}, {})", GIT_HASH, BUILD_DATE));
+        let long = long_version("1.2.3");
+        assert!(long.starts_with(&line));
+        assert_eq!(long.lines().count(), 3);
+        assert_eq!(BUILD_DATE.len(), "2024-01-02".len());
+    }
+
+    #[test]
+    fn leaf_area_error_accepts_only_child_areas() {
+        let list = areas();
+        assert_eq!(leaf_area_error(&list, 11), None);
+        assert_eq!(leaf_area_error(&list, 1).as_deref(), Some("「网游」是父分区，请选择具体子分区"));
+        assert_eq!(leaf_area_error(&list, 99).as_deref(), Some("分区 99 不存在，请选择具体子分区"));
+    }
+}
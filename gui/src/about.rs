@@ -0,0 +1,85 @@
+//! 关于页：版本与构建信息，以及内嵌的第三方依赖许可证清单。
+//! 清单 `assets/licenses.tsv` 每行为"许可证<Tab>crate 版本"，更新依赖后按 README 重新生成。
+
+use domain::{BUILD_TARGET, BUILD_TIME, GIT_HASH};
+use eframe::egui;
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+const LICENSES: &str = include_str!("../assets/licenses.tsv");
+const REPO_URL: &str = "https://github.com/BlueIceChannel/Bili-Live-Tool";
+
+/// 按许可证分组的依赖，组内按名称排序
+fn licenses() -> &'static BTreeMap<&'static str, Vec<&'static str>> {
+    static GROUPS: OnceLock<BTreeMap<&'static str, Vec<&'static str>>> = OnceLock::new();
+    GROUPS.get_or_init(|| {
+        let mut groups: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for (license, krate) in LICENSES.lines().filter_map(|line| line.split_once('\t')) {
+            groups.entry(license).or_default().push(krate);
+        }
+        for crates in groups.values_mut() {
+            crates.sort_unstable();
+        }
+        groups
+    })
+}
+
+pub fn about_page(ui: &mut egui::Ui) {
+    let version = env!("CARGO_PKG_VERSION");
+    ui.group(|ui| {
+        ui.heading("B站直播工具");
+        ui.add_space(5.0);
+        egui::Grid::new("build_info").num_columns(2).spacing([10.0, 4.0]).show(ui, |ui| {
+            ui.label("版本");
+            ui.label(format!("v{}", version));
+            ui.end_row();
+            ui.label("提交");
+            ui.label(egui::RichText::new(GIT_HASH).monospace());
+            ui.end_row();
+            ui.label("构建时间");
+            ui.label(BUILD_TIME);
+            ui.end_row();
+            ui.label("目标平台");
+            ui.label(egui::RichText::new(BUILD_TARGET).monospace());
+            ui.end_row();
+            ui.label("许可证");
+            ui.label("MIT");
+            ui.end_row();
+        });
+        ui.horizontal(|ui| {
+            ui.hyperlink_to("源代码", REPO_URL);
+            if ui.button("复制版本信息").clicked() {
+                ui.output_mut(|o| o.copied_text = domain::long_version(version));
+            }
+        });
+    });
+    ui.add_space(10.0);
+
+    ui.group(|ui| {
+        ui.heading("快捷键");
+        ui.add_space(5.0);
+        egui::Grid::new("shortcuts").num_columns(2).spacing([10.0, 4.0]).show(ui, |ui| {
+            for (keys, action) in crate::shortcuts::LIST {
+                ui.label(egui::RichText::new(keys).monospace());
+                ui.label(action);
+                ui.end_row();
+            }
+        });
+        ui.label("输入框聚焦时 F5 与 Ctrl+L 不生效");
+    });
+    ui.add_space(10.0);
+
+    ui.group(|ui| {
+        let groups = licenses();
+        ui.heading("第三方依赖");
+        ui.label(format!("本程序使用了以下 {} 个开源 crate，按许可证分组，许可证全文见各 crate 的源码包", groups.values().map(Vec::len).sum::<usize>()));
+        ui.add_space(5.0);
+        for (license, crates) in groups {
+            egui::CollapsingHeader::new(format!("{}（{}）", license, crates.len())).id_source(license).show(ui, |ui| {
+                ui.add(egui::Label::new(egui::RichText::new(crates.join("\n")).monospace()).wrap(true));
+            });
+        }
+        ui.add_space(5.0);
+        ui.label("界面字体为微软雅黑，版权归 Microsoft 所有");
+    });
+}
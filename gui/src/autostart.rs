@@ -0,0 +1,128 @@
+//! 开机自启：Windows 写当前用户的注册表 Run 键，macOS 写 LaunchAgent，Linux 写 XDG autostart 的 .desktop。
+//! 自启时以 `--minimized` 启动。
+
+use anyhow::{anyhow, Result};
+
+const APP_ID: &str = "BiliLiveTool";
+const AUTOSTART_ARGS: &[&str] = &["--minimized"];
+
+/// 开启或关闭开机自启
+pub fn set_autostart(enabled: bool) -> Result<()> {
+    let res = if enabled {
+        std::env::current_exe().map_err(Into::into).and_then(|exe| platform::enable(&exe))
+    } else {
+        platform::disable()
+    };
+    res.map_err(|e| anyhow!("{}（请确认当前用户有权限写入启动项）", e))
+}
+
+/// 当前是否已开启开机自启
+pub fn is_autostart_enabled() -> bool {
+    platform::is_enabled()
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::{APP_ID, AUTOSTART_ARGS};
+    use anyhow::{bail, Result};
+    use std::os::windows::process::CommandExt;
+    use std::path::Path;
+    use std::process::{Command, Output};
+
+    const RUN_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+    /// 不为 reg.exe 弹出控制台窗口
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    fn reg(args: &[&str]) -> std::io::Result<Output> {
+        Command::new("reg").args(args).creation_flags(CREATE_NO_WINDOW).output()
+    }
+
+    pub fn enable(exe: &Path) -> Result<()> {
+        let value = format!("\"{}\" {}", exe.display(), AUTOSTART_ARGS.join(" "));
+        let out = reg(&["add", RUN_KEY, "/v", APP_ID, "/t", "REG_SZ", "/d", &value, "/f"])?;
+        if !out.status.success() {
+            bail!("写入注册表失败: {}", String::from_utf8_lossy(&out.stderr).trim());
+        }
+        Ok(())
+    }
+
+    pub fn disable() -> Result<()> {
+        if !is_enabled() {
+            return Ok(());
+        }
+        let out = reg(&["delete", RUN_KEY, "/v", APP_ID, "/f"])?;
+        if !out.status.success() {
+            bail!("删除注册表项失败: {}", String::from_utf8_lossy(&out.stderr).trim());
+        }
+        Ok(())
+    }
+
+    pub fn is_enabled() -> bool {
+        reg(&["query", RUN_KEY, "/v", APP_ID]).map(|o| o.status.success()).unwrap_or(false)
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    use super::{APP_ID, AUTOSTART_ARGS};
+    use anyhow::{anyhow, Context, Result};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    pub fn enable(exe: &Path) -> Result<()> {
+        let path = entry_path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("无法创建目录 {}", dir.display()))?;
+        }
+        fs::write(&path, entry_content(exe)).with_context(|| format!("无法写入 {}", path.display()))
+    }
+
+    pub fn disable() -> Result<()> {
+        let path = entry_path()?;
+        if path.exists() {
+            fs::remove_file(&path).with_context(|| format!("无法删除 {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    pub fn is_enabled() -> bool {
+        entry_path().map(|p| p.exists()).unwrap_or(false)
+    }
+
+    #[cfg(target_os = "macos")]
+    fn entry_path() -> Result<PathBuf> {
+        let base = directories::BaseDirs::new().ok_or_else(|| anyhow!("无法定位用户目录"))?;
+        Ok(base.home_dir().join("Library/LaunchAgents").join(format!("com.blueicechannel.{}.plist", APP_ID)))
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    fn entry_path() -> Result<PathBuf> {
+        let base = directories::BaseDirs::new().ok_or_else(|| anyhow!("无法定位用户目录"))?;
+        Ok(base.config_dir().join("autostart").join(format!("{}.desktop", APP_ID)))
+    }
+
+    #[cfg(target_os = "macos")]
+    fn entry_content(exe: &Path) -> String {
+        let escape = |s: &str| s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+        let args: String = std::iter::once(exe.display().to_string())
+            .chain(AUTOSTART_ARGS.iter().map(|a| a.to_string()))
+            .map(|a| format!("        <string>{}</string>\n", escape(&a)))
+            .collect();
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+             <plist version=\"1.0\">\n<dict>\n    <key>Label</key>\n    <string>com.blueicechannel.{}</string>\n    \
+             <key>ProgramArguments</key>\n    <array>\n{}    </array>\n    <key>RunAtLoad</key>\n    <true/>\n</dict>\n</plist>\n",
+            APP_ID, args
+        )
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    fn entry_content(exe: &Path) -> String {
+        format!(
+            "[Desktop Entry]\nType=Application\nName=Bili Live Tool\nExec=\"{}\" {}\nX-GNOME-Autostart-enabled=true\n",
+            exe.display(),
+            AUTOSTART_ARGS.join(" ")
+        )
+    }
+}
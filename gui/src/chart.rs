@@ -0,0 +1,48 @@
+//! 数据页的趋势折线图：用 painter 直接绘制，悬停时显示最近采样点的时间与数值。
+
+use domain::{StatsRing, StatsSnapshot};
+use eframe::egui;
+
+const CHART_HEIGHT: f32 = 160.0;
+/// 纵轴留白，避免折线贴着上边框
+const Y_HEADROOM: f32 = 1.1;
+
+/// 画一条折线，`value` 取出要展示的字段；没有数据时只画坐标框
+pub fn trend_chart(ui: &mut egui::Ui, ring: &StatsRing, name: &str, color: egui::Color32, value: impl Fn(&StatsSnapshot) -> u64) {
+    let width = ui.available_width();
+    let (response, painter) = ui.allocate_painter(egui::vec2(width, CHART_HEIGHT), egui::Sense::hover());
+    let rect = response.rect;
+    let visuals = ui.visuals();
+    painter.rect_filled(rect, 2.0, visuals.extreme_bg_color);
+    painter.rect_stroke(rect, 2.0, visuals.widgets.noninteractive.bg_stroke);
+    let text_color = visuals.weak_text_color();
+    let font = egui::FontId::proportional(12.0);
+
+    let (Some(first), Some(last)) = (ring.samples().next(), ring.samples().last()) else { return };
+    let (t0, t1) = (first.time, last.time.max(first.time + 1));
+    let max = ring.samples().map(&value).max().unwrap_or(0).max(1) as f32 * Y_HEADROOM;
+    let to_pos = |s: &StatsSnapshot| {
+        let x = rect.left() + (s.time - t0) as f32 / (t1 - t0) as f32 * rect.width();
+        let y = rect.bottom() - value(s) as f32 / max * rect.height();
+        egui::pos2(x, y)
+    };
+
+    let points: Vec<egui::Pos2> = ring.samples().map(to_pos).collect();
+    if points.len() == 1 {
+        painter.circle_filled(points[0], 3.0, color);
+    } else {
+        painter.add(egui::Shape::line(points, egui::Stroke::new(1.5, color)));
+    }
+    painter.text(rect.left_top() + egui::vec2(4.0, 4.0), egui::Align2::LEFT_TOP, format!("{} 峰值 {}", name, (max / Y_HEADROOM) as u64), font.clone(), text_color);
+    painter.text(rect.left_bottom() + egui::vec2(4.0, -4.0), egui::Align2::LEFT_BOTTOM, domain::beijing_clock(t0), font.clone(), text_color);
+    painter.text(rect.right_bottom() + egui::vec2(-4.0, -4.0), egui::Align2::RIGHT_BOTTOM, domain::beijing_clock(t1), font, text_color);
+
+    let Some(hover) = response.hover_pos() else { return };
+    let time = t0 + ((hover.x - rect.left()) / rect.width() * (t1 - t0) as f32).round() as i64;
+    if let Some(sample) = ring.nearest(time) {
+        let pos = to_pos(sample);
+        painter.line_segment([egui::pos2(pos.x, rect.top()), egui::pos2(pos.x, rect.bottom())], egui::Stroke::new(1.0, text_color));
+        painter.circle_filled(pos, 4.0, color);
+        response.on_hover_text_at_pointer(format!("{}\n{}: {}", domain::beijing_clock(sample.time), name, value(sample)));
+    }
+}
@@ -0,0 +1,118 @@
+//! 封面本地裁剪：把任意比例的图片裁成 16:9 并缩放到推荐尺寸，避免上传后被 B 站再次裁剪。
+
+use anyhow::{bail, Result};
+use domain::CropRect;
+use eframe::egui;
+use image::imageops::FilterType;
+use image::DynamicImage;
+use std::path::{Path, PathBuf};
+
+/// 导出尺寸
+pub const COVER_SIZE: (u32, u32) = (1280, 720);
+/// 预览纹理的最大边长，过大的原图只在显示时缩小
+const TEXTURE_MAX_SIDE: u32 = 2048;
+/// 预览区域的最大宽度
+const PREVIEW_MAX_WIDTH: f32 = 480.0;
+
+/// 按选区裁剪并缩放到 [`COVER_SIZE`]
+pub fn crop_cover(img: &DynamicImage, rect: CropRect) -> DynamicImage {
+    img.crop_imm(rect.x, rect.y, rect.width, rect.height)
+        .resize_exact(COVER_SIZE.0, COVER_SIZE.1, FilterType::Lanczos3)
+}
+
+/// 拖入的文件按扩展名判断是否为支持的图片
+pub fn is_supported_image(path: &Path) -> bool {
+    matches!(image::ImageFormat::from_path(path), Ok(image::ImageFormat::Jpeg | image::ImageFormat::Png))
+}
+
+/// 导出到原图所在目录，文件名加 `_16x9` 后缀
+pub fn output_path(src: &Path) -> PathBuf {
+    let stem = src.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_else(|| "cover".into());
+    src.with_file_name(format!("{}_16x9.jpg", stem))
+}
+
+#[derive(Default)]
+pub struct CoverEditor {
+    pub path: String,
+    source: Option<DynamicImage>,
+    texture: Option<egui::TextureHandle>,
+    crop: Option<CropRect>,
+    /// 拖动时不足一像素的位移
+    drag_residual: egui::Vec2,
+}
+
+impl CoverEditor {
+    /// 读取 `path` 指向的图片，选区默认居中
+    pub fn load(&mut self, ctx: &egui::Context) -> Result<()> {
+        let img = image::open(self.path.trim())?;
+        let preview = img.thumbnail(TEXTURE_MAX_SIDE, TEXTURE_MAX_SIDE).into_rgba8();
+        let size = [preview.width() as usize, preview.height() as usize];
+        let color = egui::ColorImage::from_rgba_unmultiplied(size, preview.as_raw());
+        self.texture = Some(ctx.load_texture("cover_crop", color, Default::default()));
+        self.crop = Some(CropRect::centered_16x9(img.width(), img.height()));
+        self.drag_residual = egui::Vec2::ZERO;
+        self.source = Some(img);
+        Ok(())
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.source.is_some()
+    }
+
+    /// 按当前选区导出 JPEG，返回导出路径
+    pub fn export(&self) -> Result<PathBuf> {
+        let out = output_path(Path::new(self.path.trim()));
+        self.cropped()?.save(&out)?;
+        Ok(out)
+    }
+
+    /// 按当前选区编码为 JPEG，直接用于上传
+    pub fn encode_jpeg(&self) -> Result<Vec<u8>> {
+        let mut buf = std::io::Cursor::new(Vec::new());
+        self.cropped()?.write_to(&mut buf, image::ImageOutputFormat::Jpeg(90))?;
+        Ok(buf.into_inner())
+    }
+
+    fn cropped(&self) -> Result<image::RgbImage> {
+        let (Some(img), Some(rect)) = (&self.source, self.crop) else {
+            bail!("请先加载图片");
+        };
+        Ok(crop_cover(img, rect).into_rgb8())
+    }
+
+    /// 显示原图与可拖动的 16:9 选区，选区外变暗
+    pub fn preview(&mut self, ui: &mut egui::Ui) {
+        let (Some(tex), Some(img), Some(mut crop)) = (&self.texture, &self.source, self.crop) else { return };
+        let (img_w, img_h) = (img.width(), img.height());
+        let display_w = ui.available_width().min(PREVIEW_MAX_WIDTH);
+        let scale = display_w / img_w as f32;
+        let (response, painter) = ui.allocate_painter(egui::vec2(display_w, img_h as f32 * scale), egui::Sense::drag());
+        let rect = response.rect;
+        let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+        painter.image(tex.id(), rect, uv, egui::Color32::WHITE);
+
+        if response.dragged() {
+            let delta = response.drag_delta() / scale + self.drag_residual;
+            let whole = egui::vec2(delta.x.trunc(), delta.y.trunc());
+            self.drag_residual = delta - whole;
+            crop = crop.moved(whole.x as i64, whole.y as i64, img_w, img_h);
+            self.crop = Some(crop);
+        }
+
+        let sel = egui::Rect::from_min_size(
+            rect.min + egui::vec2(crop.x as f32, crop.y as f32) * scale,
+            egui::vec2(crop.width as f32, crop.height as f32) * scale,
+        );
+        let shade = egui::Color32::from_black_alpha(140);
+        for r in [
+            egui::Rect::from_min_max(rect.min, egui::pos2(rect.max.x, sel.min.y)),
+            egui::Rect::from_min_max(egui::pos2(rect.min.x, sel.max.y), rect.max),
+            egui::Rect::from_min_max(egui::pos2(rect.min.x, sel.min.y), egui::pos2(sel.min.x, sel.max.y)),
+            egui::Rect::from_min_max(egui::pos2(sel.max.x, sel.min.y), egui::pos2(rect.max.x, sel.max.y)),
+        ] {
+            painter.rect_filled(r, 0.0, shade);
+        }
+        painter.rect_stroke(sel, 0.0, egui::Stroke::new(2.0, egui::Color32::WHITE));
+        response.on_hover_cursor(egui::CursorIcon::Grab);
+    }
+}
@@ -0,0 +1,68 @@
+//! 网络图片（头像、封面）的纹理缓存：后台下载，解码后按 URL 缓存，避免重复下载与阻塞界面。
+
+use crate::BgTask;
+use api_client::BiliClient;
+use eframe::egui;
+use image::io::Reader as ImageReader;
+use std::collections::{HashMap, HashSet};
+use tokio::runtime::Runtime;
+
+#[derive(Default)]
+pub struct ImageCache {
+    textures: HashMap<String, egui::TextureHandle>,
+    pending: HashMap<String, BgTask<Vec<u8>>>,
+    /// 下载或解码失败的 URL，不再自动重试
+    failed: HashSet<String>,
+}
+
+impl ImageCache {
+    /// 取 `url` 对应的纹理；尚未下载时在后台开始下载并返回 None，完成后会触发重绘
+    pub fn get(&mut self, url: &str, rt: &Runtime, client: &BiliClient, ctx: &egui::Context) -> Option<egui::TextureHandle> {
+        if url.is_empty() || self.failed.contains(url) {
+            return None;
+        }
+        if let Some(tex) = self.textures.get(url) {
+            return Some(tex.clone());
+        }
+        match self.pending.get(url).map(|task| task.poll()) {
+            Some(Some(res)) => {
+                self.pending.remove(url);
+                match res.ok().and_then(|bytes| Self::bytes_to_texture(url, &bytes, ctx)) {
+                    Some(tex) => {
+                        self.textures.insert(url.to_string(), tex.clone());
+                        return Some(tex);
+                    }
+                    None => {
+                        println!("图片加载失败: {}", url);
+                        self.failed.insert(url.to_string());
+                    }
+                }
+            }
+            Some(None) => {}
+            None => {
+                let client = client.clone();
+                let target = url.to_string();
+                let task = BgTask::spawn(rt, ctx, move |token| async move {
+                    api_client::cancellable(&token, client.download_image(&target)).await
+                });
+                self.pending.insert(url.to_string(), task);
+            }
+        }
+        None
+    }
+
+    /// 丢弃所有缓存与进行中的下载
+    pub fn clear(&mut self) {
+        self.textures.clear();
+        self.pending.clear();
+        self.failed.clear();
+    }
+
+    fn bytes_to_texture(name: &str, bytes: &[u8], ctx: &egui::Context) -> Option<egui::TextureHandle> {
+        let img = ImageReader::new(std::io::Cursor::new(bytes)).with_guessed_format().ok()?.decode().ok()?;
+        let size = [img.width() as usize, img.height() as usize];
+        let pixels = img.into_rgba8().into_raw();
+        let img = egui::ColorImage::from_rgba_unmultiplied(size, &pixels);
+        Some(ctx.load_texture(name, img, Default::default()))
+    }
+}
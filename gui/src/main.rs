@@ -1,15 +1,189 @@
 #![windows_subsystem = "windows"] // 在Windows上隐藏控制台窗口
-use api_client::BiliClient;
+use api_client::{BiliClient, BiliError, CancellationToken, ErrorKind};
 use anyhow::Result;
-use domain::{LoginState, LiveRoomBrief, UserInfo, AreaParent, WebQrInfo};
+use domain::{ApiExchange, CoverSlot, NoticeTarget, StandbyConfig, Cached, RoomMembers, LiveActivity, PollingConfig, LiveStart, StreamLines, StreamProtocol, DanmakuConfig, DanmakuStats, Emoticon, StatusOutputConfig, StatusOutputMode, FaceAuthInfo, IdKind, IncomeOverview, ModAction, TitleDraft, UndoStack, StreamLine, AreaSelection, LiveEvent, LoginState, NetworkDiag, LiveRoomBrief, RoomInfo, UserInfo, AreaParent, WebQrInfo, Settings, LiveTemplate, ObsTextConfig, InteractStatus, AutoReplyConfig, AutoReplyRule, ReplyTrigger, StatsRing, ShellKind, redact, UI_SCALE_RANGE};
+use clap::Parser;
 use eframe::{egui, Frame};
+use serde::{Deserialize, Serialize};
 use qrcode::QrCode;
 use tokio::runtime::Runtime;
-use image::io::Reader as ImageReader;
 use qrcode::Color;
-use reqwest;
 use std::time::{Instant, Duration};
-use std::sync::Arc;
+use notify::{Notifications, StatusLine};
+use images::ImageCache;
+use cover::CoverEditor;
+
+mod about;
+mod autostart;
+mod chart;
+mod cover;
+mod images;
+mod notify;
+mod shortcuts;
+use std::future::Future;
+use std::collections::HashMap;
+use std::sync::{mpsc, Arc, Mutex};
+
+const CREATE_ROOM_GUIDE_URL: &str = "https://link.bilibili.com/p/center/index#/my-room/start-live";
+/// 低于该可用宽度时，横排的内容改为竖排
+const NARROW_WIDTH: f32 = 480.0;
+/// 二维码每个模块的显示边长（逻辑点），纹理按当前 DPI 换算成像素
+const QR_MODULE_POINTS: f32 = 6.0;
+/// 封面图显示的最大宽度
+const COVER_MAX_WIDTH: f32 = 320.0;
+/// "常用分区"最多显示的个数
+const COMMON_AREA_COUNT: usize = 5;
+/// 连续失败多少次后在直播页提示网络诊断
+const DIAG_HINT_FAILURES: u32 = 2;
+/// 数据页趋势图保留的采样点数
+const STATS_RING_CAP: usize = 360;
+/// 房管操作执行后可撤销的时长（秒）
+const UNDO_WINDOW_SECS: i64 = 10;
+/// 关键操作之间的冷却时间：请求阻塞期间积压的点击会在下一帧一起触发，冷却期内按钮置灰
+const ACTION_COOLDOWN: Duration = Duration::from_millis(800);
+/// 开播后等待推流连接、直播上线的最长时间
+const LIVE_WAIT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// GUI 启动参数，解析失败时按无参数启动
+#[derive(Parser, Default)]
+#[command(name = "bili-live-tool", version = domain::version_line(env!("CARGO_PKG_VERSION")), long_version = domain::long_version(env!("CARGO_PKG_VERSION")))]
+struct GuiArgs {
+    /// 启动后立即最小化到任务栏（没有托盘图标）
+    #[arg(long)]
+    minimized: bool,
+    /// 登录有效时自动按模板开播（配合开机自启挂播）
+    #[arg(long)]
+    autostart_live: bool,
+    /// 自动开播使用的模板名，缺省时用第一个模板
+    #[arg(long, requires = "autostart_live")]
+    template: Option<String>,
+}
+
+/// 在运行时上执行的后台任务，结果通过通道回传 UI 线程；drop 时取消
+struct BgTask<T> {
+    token: CancellationToken,
+    rx: mpsc::Receiver<Result<T>>,
+}
+
+impl<T: Send + 'static> BgTask<T> {
+    fn spawn<F, E>(rt: &Runtime, ctx: &egui::Context, task: impl FnOnce(CancellationToken) -> F) -> Self
+    where
+        F: Future<Output = std::result::Result<T, E>> + Send + 'static,
+        E: Into<anyhow::Error>,
+    {
+        let token = CancellationToken::new();
+        let (tx, rx) = mpsc::channel();
+        let fut = task(token.clone());
+        let ctx = ctx.clone();
+        rt.spawn(async move {
+            let _ = tx.send(fut.await.map_err(Into::into));
+            ctx.request_repaint();
+        });
+        Self { token, rx }
+    }
+
+    fn poll(&self) -> Option<Result<T>> {
+        self.rx.try_recv().ok()
+    }
+}
+
+impl<T> Drop for BgTask<T> {
+    fn drop(&mut self) {
+        self.token.cancel();
+    }
+}
+
+/// 后台进行中的开播：需要人脸认证时在直播页展示二维码，认证通过后自动开播
+struct PendingStart {
+    room_id: i64,
+    area_id: i64,
+    /// 成功时的提示与 webhook 事件详情
+    success: String,
+    detail: String,
+    face_auth: Arc<Mutex<Option<FaceAuthInfo>>>,
+    qr: Option<egui::TextureHandle>,
+    task: BgTask<LiveStart>,
+}
+
+impl PendingStart {
+    fn spawn(rt: &Runtime, ctx: &egui::Context, client: &BiliClient, room_id: i64, area_id: i64, success: String, detail: String) -> Self {
+        let face_auth: Arc<Mutex<Option<FaceAuthInfo>>> = Arc::default();
+        let (client, slot, repaint) = (client.clone(), face_auth.clone(), ctx.clone());
+        let task = BgTask::spawn(rt, ctx, move |token| async move {
+            client
+                .start_live_with_face_auth(room_id, area_id, None, &token, move |info| {
+                    *slot.lock().unwrap_or_else(|e| e.into_inner()) = Some(info.clone());
+                    repaint.request_repaint();
+                })
+                .await
+        });
+        Self { room_id, area_id, success, detail, face_auth, qr: None, task }
+    }
+}
+
+/// 保存直播间设置后重新拉取服务端的最新值，标题可能被审核改写、分区可能被规范化
+struct RoomSync {
+    /// 提交的标题与分区，返回时本地仍是这两个值才覆盖，避免冲掉用户之后的编辑
+    title: String,
+    area_id: Option<i64>,
+    task: BgTask<RoomInfo>,
+}
+
+impl RoomSync {
+    fn spawn(rt: &Runtime, ctx: &egui::Context, client: &BiliClient, room_id: i64, title: String, area_id: Option<i64>) -> Option<Self> {
+        // dry-run 没有真正修改，拉回来的是旧值
+        if client.is_dry_run() {
+            return None;
+        }
+        let client = client.clone();
+        let task = BgTask::spawn(rt, ctx, move |token| async move { api_client::cancellable(&token, client.get_room_info(room_id)).await });
+        Some(Self { title, area_id, task })
+    }
+}
+
+/// eframe storage 中保存界面状态的键
+const UI_STATE_KEY: &str = "ui_state";
+
+/// 通过 eframe storage 持久化的纯界面状态，窗口大小与位置由 eframe 自己保存。
+/// 登录信息与业务配置仍写在配置目录里，不放进这里；隐私模式每次启动默认关闭，也不保存
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+struct UiState {
+    tab: Tab,
+    /// 上次选中的分区，分区列表加载后按 id 重新定位
+    area_id: Option<i64>,
+    /// 未发送的弹幕草稿与颜色
+    danmaku_input: String,
+    danmaku_color: u32,
+}
+
+impl Default for UiState {
+    fn default() -> Self {
+        Self {
+            tab: Tab::Live,
+            area_id: None,
+            danmaku_input: String::new(),
+            danmaku_color: domain::DEFAULT_DANMAKU_COLOR,
+        }
+    }
+}
+
+/// 软登出前正在编辑的直播间信息
+struct ReloginDraft {
+    uid: u64,
+    room_id: i64,
+    title: String,
+    area_id: Option<i64>,
+}
+
+#[derive(PartialEq, Clone, Copy, Serialize, Deserialize)]
+enum Tab {
+    Live,
+    Data,
+    Members,
+    Settings,
+    About,
+}
 
 struct BiliApp {
     client: BiliClient,
@@ -17,107 +191,2907 @@ struct BiliApp {
     login_state: LoginState,
     user_info: Option<UserInfo>,
     room_info: Option<LiveRoomBrief>,
+    room_detail: Option<RoomInfo>,
     qr_texture: Option<egui::TextureHandle>,
     qr_info: Option<WebQrInfo>,
-    avatar_texture: Option<egui::TextureHandle>,
-    cover_texture: Option<egui::TextureHandle>,
+    /// 获取二维码失败的原因；存在时到 `qr_retry_at` 自动重试，也可手动重试
+    qr_error: Option<String>,
+    qr_retry_at: Option<Instant>,
+    /// 后台获取二维码，离开扫码页时随之取消
+    qr_fetch: Option<BgTask<WebQrInfo>>,
+    images: ImageCache,
+    cover_editor: CoverEditor,
+    /// 后台上传封面，完成后返回新封面地址
+    cover_upload: Option<BgTask<String>>,
+    /// 本帧绘制的封面放置区，只有落在其中的拖放才会上传
+    cover_drop_rect: Option<egui::Rect>,
+    /// 开播 / 关播后自动切换封面与预告：(切到的状态, 新封面地址)
+    standby_task: Option<BgTask<(&'static str, Option<String>)>>,
+    /// 两份封面存档是否已保存（按 `CoverSlot` 顺序），首次展示时读一次，保存后刷新
+    cover_slots_saved: Option<[bool; 2]>,
     area_list: Vec<AreaParent>,
-    selected_parent: usize,
-    selected_child: usize,
+    area_sel: AreaSelection,
     selected_area_id: Option<i64>,
-    push_addr: String,
-    push_key: String,
-    last_qr_poll: Option<Instant>,
+    stream: Option<StreamLine>,
+    /// 本场可用的 RTMP / SRT 推流信息，`stream` 是其中正在展示的一个
+    stream_lines: StreamLines,
+    /// 复制推流信息时合并为完整地址，否则分别复制服务器与密钥（OBS）
+    copy_merged: bool,
+    qr_poll: Option<BgTask<LoginState>>,
     last_user_info_fetch: Option<Instant>,
+    login_task: Option<BgTask<LoginState>>,
+    last_login_check: Option<Instant>,
     area_list_fetch_error: Option<String>,
     version: String,
+    tab: Tab,
+    confirm_reset: bool,
+    /// 本帧按下的快捷键，每帧开始时重新读取
+    shortcuts: shortcuts::Pressed,
+    /// Ctrl+L 触发后等待确认开播 / 关播
+    confirm_toggle_live: bool,
+    reset_keep_config: bool,
+    show_cookie_values: bool,
+    /// 隐私模式：录屏/截图时打码 UID、推流密钥与 cookie，并禁用复制。不持久化，每次启动默认关闭
+    privacy_mode: bool,
+    settings: Settings,
+    selected_template: Option<String>,
+    new_template_name: String,
+    webhook_input: String,
+    proxy_input: String,
+    /// 扫码页导入 cookie 文件的路径
+    cookie_file_input: String,
+    /// 代理规则的编辑文本，每行 `域名 代理地址|off`
+    proxy_rules_input: String,
+    /// OBS 文本源配置的编辑缓冲，保存后才生效
+    obs_input: ObsTextConfig,
+    obs_task: Option<BgTask<()>>,
+    /// 直播状态输出配置的编辑缓冲，保存后才生效
+    status_input: StatusOutputConfig,
+    status_task: Option<BgTask<()>>,
+    /// 自动回复配置的编辑缓冲，保存后才生效
+    auto_reply_input: AutoReplyConfig,
+    auto_reply_task: Option<BgTask<()>>,
+    start_task: Option<PendingStart>,
+    /// 开播成功后等待推流连接的任务，结束即确认直播已上线或超时
+    live_wait_task: Option<BgTask<()>>,
+    room_sync: Option<RoomSync>,
+    /// 网络不可用时展示的缓存数据的更新时间说明；存在时为只读模式，写操作禁用
+    offline: Option<String>,
+    offline_probe: Option<BgTask<LoginState>>,
+    last_offline_probe: Option<Instant>,
+    /// 房管操作的目标 UID 输入
+    mod_uid_input: String,
+    danmaku_input: String,
+    /// 表情列表按直播间拉取，打开表情选择器时才加载
+    emoticons: Option<(i64, Vec<Emoticon>)>,
+    emoticon_task: Option<(i64, BgTask<Vec<Emoticon>>)>,
+    /// 弹幕颜色与字数上限按直播间拉取，失败时退回只发白色弹幕
+    danmaku_config: Option<(i64, DanmakuConfig)>,
+    danmaku_config_task: Option<(i64, BgTask<DanmakuConfig>)>,
+    danmaku_color: u32,
+    /// 按分区 id 直接选择分区的输入框
+    area_id_input: String,
+    /// 服务端当前的标题，编辑框内容与它不同即为未保存的草稿
+    server_title: Option<String>,
+    /// 上次写入本地草稿的标题，内容没变时不重复写盘
+    saved_title_draft: Option<String>,
+    last_title_draft_save: Option<Instant>,
+    /// 启动时发现的未保存草稿，等待用户选择恢复或丢弃
+    title_draft_prompt: Option<TitleDraft>,
+    /// 正在编辑的直播间简介，None 表示未进入编辑
+    description_edit: Option<String>,
+    income_task: Option<BgTask<IncomeOverview>>,
+    /// 房管与大航海成员，先展示磁盘缓存，再在后台刷新
+    members: Option<Cached<RoomMembers>>,
+    members_task: Option<BgTask<Cached<RoomMembers>>>,
+    members_error: Option<String>,
+    /// 已读过磁盘缓存，没有缓存时也不再每帧重读
+    members_cache_loaded: bool,
+    last_members_refresh: Option<Instant>,
+    income: Option<IncomeOverview>,
+    income_error: Option<String>,
+    /// 收益金额默认打码，勾选后才显示
+    show_income: bool,
+    /// 当前可参与的直播活动，拉取失败时为空列表
+    activities: Option<Vec<LiveActivity>>,
+    activities_task: Option<BgTask<Vec<LiveActivity>>>,
+    /// 软登出时保存的草稿，同一账号重新登录后恢复
+    relogin_draft: Option<ReloginDraft>,
+    /// 当前生效的界面缩放，与 `ctx.zoom_factor()` 不同说明用户用快捷键改了缩放
+    applied_zoom: f32,
+    undo_stack: UndoStack,
+    /// 弹幕连接异常退出后，到此时刻之前不重连
+    auto_reply_retry_at: Option<Instant>,
+    stats_task: Option<BgTask<()>>,
+    /// 采集任务退出后，到此时刻之前不重新启动，避免直播状态未刷新时反复重启
+    stats_retry_at: Option<Instant>,
+    /// 本场直播的数据快照，由采集任务写入，数据页读取
+    stats_ring: Arc<Mutex<StatsRing>>,
+    /// `stats_ring` 对应场次的开播时间，换场时清空
+    stats_session: i64,
+    /// 本场互动计数的监听任务，断开后等待重连间隔接着累计
+    interaction_task: Option<BgTask<()>>,
+    interaction_retry_at: Option<Instant>,
+    interaction: Arc<Mutex<DanmakuStats>>,
+    /// `interaction` 对应的直播间与开播时间，停播时据此导出摘要
+    interaction_session: Option<(i64, i64)>,
+    /// 分区开播资质探测结果缓存：`Some(原因)` 表示不允许开播
+    area_check: HashMap<i64, Option<String>>,
+    area_check_task: Option<(i64, BgTask<Option<String>>)>,
+    notify: Notifications,
+    /// 开播/关播/套用模板的结果
+    live_result: StatusLine,
+    /// 保存直播间设置的结果
+    save_result: StatusLine,
+    /// 上一次关键操作完成的时间，用于按钮防抖
+    last_action: Option<Instant>,
+    /// 连续失败的请求数，成功后清零
+    consecutive_failures: u32,
+    diag_task: Option<BgTask<NetworkDiag>>,
+    network_diag: Option<NetworkDiag>,
+    /// 本场开播时间（Unix 秒），用于开播计时与时长提醒
+    live_started_at: Option<i64>,
+    duration_reminded: bool,
+    interact: Option<InteractStatus>,
+    interact_task: Option<BgTask<InteractStatus>>,
+    last_interact_fetch: Option<Instant>,
+    /// 开机自启开关的回显，启动时从系统读取
+    autostart_enabled: bool,
+    /// 首帧时最小化窗口（--minimized）
+    start_minimized: bool,
+    /// 待自动开播的模板名（--autostart-live），直播间与分区加载完成后触发一次
+    autostart_template: Option<String>,
 }
 
-impl BiliApp {
-    /// 生成带静区且放大后的二维码纹理
-    fn load_qr_texture(url: &str, ctx: &egui::Context) -> egui::TextureHandle {
-        let code = QrCode::new(url.as_bytes()).expect("QR encode failed");
-        let module_count = code.width() as usize;
-        let margin_modules = 4; // 留白
-        let scale = 6; // 单模块像素数，控制大小与清晰度
-        let img_side = (module_count + margin_modules * 2) * scale;
-        let mut pixels = vec![255u8; img_side * img_side * 4]; // white background
+impl BiliApp {
+    /// 生成带静区且放大后的二维码纹理，单模块像素数按 DPI 换算，高分屏下依然清晰
+    fn load_qr_texture(url: &str, ctx: &egui::Context) -> egui::TextureHandle {
+        let code = QrCode::new(url.as_bytes()).expect("QR encode failed");
+        let module_count = code.width();
+        let margin_modules = 4; // 留白
+        let scale = (QR_MODULE_POINTS * ctx.pixels_per_point()).ceil().max(1.0) as usize; // 单模块像素数
+        let img_side = (module_count + margin_modules * 2) * scale;
+        let mut pixels = vec![255u8; img_side * img_side * 4]; // white background
+
+        for y in 0..module_count {
+            for x in 0..module_count {
+                if code[(x, y)] == Color::Dark {
+                    let start_x = (x + margin_modules) * scale;
+                    let start_y = (y + margin_modules) * scale;
+                    for dy in 0..scale {
+                        for dx in 0..scale {
+                            let idx = ((start_y + dy) * img_side + (start_x + dx)) * 4;
+                            pixels[idx..idx + 4].copy_from_slice(&[0, 0, 0, 255]);
+                        }
+                    }
+                }
+            }
+        }
+
+        let img = egui::ColorImage::from_rgba_unmultiplied([img_side, img_side], &pixels);
+        ctx.load_texture("qr", img, egui::TextureOptions::NEAREST)
+    }
+
+    /// 按 `settings.ui_scale` 缩放界面并保存配置
+    fn apply_zoom(&mut self, ctx: &egui::Context) {
+        ctx.set_zoom_factor(self.settings.ui_scale);
+        self.applied_zoom = self.settings.ui_scale;
+        if let Err(e) = BiliClient::save_settings(&self.settings) {
+            self.notify.error(format!("保存配置失败: {}", e));
+        }
+    }
+
+    /// 二维码纹理对应的显示边长（逻辑点），不超过 `max`
+    fn qr_side(tex: &egui::TextureHandle, ctx: &egui::Context, max: f32) -> f32 {
+        (tex.size_vec2().x / ctx.pixels_per_point()).min(max)
+    }
+
+    fn ui_state(&self) -> UiState {
+        UiState {
+            tab: self.tab,
+            area_id: self.selected_area_id,
+            danmaku_input: self.danmaku_input.clone(),
+            danmaku_color: self.danmaku_color,
+        }
+    }
+
+    /// 启动时恢复上次的界面状态；颜色是否可用要等弹幕设置加载后再校验
+    fn restore_ui_state(mut self, state: UiState) -> Self {
+        self.tab = state.tab;
+        self.danmaku_input = state.danmaku_input;
+        self.danmaku_color = state.danmaku_color;
+        self.selected_area_id = state.area_id.or(self.selected_area_id);
+        if !self.area_list.is_empty() {
+            self.relocate_area();
+        }
+        self
+    }
+
+    fn with_args(mut self, args: GuiArgs) -> Self {
+        self.start_minimized = args.minimized;
+        // 登录无效时不自动开播，扫码登录后由用户手动操作
+        if args.autostart_live && matches!(self.login_state, LoginState::LoggedIn) {
+            let name = args.template.or_else(|| self.settings.templates.first().map(|t| t.name.clone()));
+            match name {
+                Some(name) => self.autostart_template = Some(name),
+                None => self.live_result.warning("没有可用的开播模板，已跳过自动开播"),
+            }
+        }
+        self
+    }
+
+    /// 软登出：记下当前的标题与分区草稿，只清除登录信息后回到扫码界面
+    fn soft_logout(&mut self) {
+        self.relogin_draft = self.user_info.as_ref().zip(self.room_info.as_ref()).map(|(user, room)| ReloginDraft {
+            uid: user.mid,
+            room_id: room.room_id,
+            title: room.title.clone(),
+            area_id: self.selected_area_id,
+        });
+        if let Err(e) = self.client.logout() {
+            self.notify.error(format!("清除登录信息失败: {}", e));
+        }
+        self.reset_session_state();
+    }
+
+    /// 同一账号重新登录后恢复软登出前的标题与分区
+    fn restore_relogin_draft(&mut self) {
+        let Some(draft) = self.relogin_draft.take() else { return };
+        let (Some(user), Some(room)) = (&self.user_info, &mut self.room_info) else { return };
+        if user.mid != draft.uid || room.room_id != draft.room_id {
+            return;
+        }
+        room.title = draft.title;
+        if let Some(area_id) = draft.area_id.filter(|id| self.area_sel.select_area(&self.area_list, *id)) {
+            self.selected_area_id = Some(area_id);
+        }
+        self.notify.success("已恢复退出登录前的标题与分区");
+    }
+
+    /// 直播间信息加载后检查本地草稿：与服务端标题不同才提示恢复，否则直接清除
+    fn check_title_draft(&mut self) {
+        let Some(room) = &self.room_info else { return };
+        match BiliClient::load_title_draft() {
+            Some(draft) if draft.room_id == room.room_id && draft.title != room.title => {
+                self.saved_title_draft = Some(draft.title.clone());
+                self.title_draft_prompt = Some(draft);
+            }
+            Some(_) => self.discard_title_draft(),
+            None => {}
+        }
+    }
+
+    /// 编辑框与服务端标题不同时，按配置的间隔把内容写入本地草稿；改回原标题则清除草稿
+    fn update_title_draft(&mut self, ctx: &egui::Context) {
+        let (Some(room), Some(server_title)) = (&self.room_info, &self.server_title) else { return };
+        if self.title_draft_prompt.is_some() {
+            return;
+        }
+        if &room.title == server_title {
+            if self.saved_title_draft.is_some() {
+                self.discard_title_draft();
+            }
+            return;
+        }
+        if self.saved_title_draft.as_ref() == Some(&room.title) {
+            return;
+        }
+        let interval = PollingConfig::interval(self.settings.polling.title_draft_secs);
+        if let Some(wait) = self.last_title_draft_save.and_then(|t| interval.checked_sub(t.elapsed())) {
+            ctx.request_repaint_after(wait);
+            return;
+        }
+        let draft = TitleDraft { room_id: room.room_id, title: room.title.clone(), saved_at: domain::now_secs() };
+        self.last_title_draft_save = Some(Instant::now());
+        match BiliClient::save_title_draft(&draft) {
+            Ok(()) => self.saved_title_draft = Some(draft.title),
+            Err(e) => println!("保存标题草稿失败: {}", e),
+        }
+    }
+
+    /// 标题已保存到服务端（或用户放弃草稿）后删除本地草稿
+    fn discard_title_draft(&mut self) {
+        self.saved_title_draft = None;
+        self.title_draft_prompt = None;
+        if let Err(e) = BiliClient::clear_title_draft() {
+            println!("删除标题草稿失败: {}", e);
+        }
+    }
+
+    /// 丢弃与当前账号相关的界面状态，回到扫码登录
+    fn reset_session_state(&mut self) {
+        self.login_state = LoginState::NeedQrCode;
+        self.user_info = None;
+        self.room_info = None;
+        self.room_detail = None;
+        self.qr_texture = None;
+        self.qr_info = None;
+        self.qr_error = None;
+        self.qr_retry_at = None;
+        self.qr_fetch = None;
+        self.confirm_toggle_live = false;
+        self.images.clear();
+        self.area_list.clear();
+        self.area_sel = AreaSelection::default();
+        self.selected_area_id = None;
+        self.stream = None;
+        self.stream_lines = StreamLines::default();
+        self.qr_poll = None;
+        self.last_user_info_fetch = None;
+        self.login_task = None;
+        self.last_login_check = None;
+        self.area_list_fetch_error = None;
+        self.area_check.clear();
+        self.area_check_task = None;
+        self.obs_task = None;
+        self.status_task = None;
+        self.auto_reply_task = None;
+        self.start_task = None;
+        self.live_wait_task = None;
+        self.standby_task = None;
+        self.cover_slots_saved = None;
+        self.room_sync = None;
+        self.offline = None;
+        self.offline_probe = None;
+        self.last_offline_probe = None;
+        self.undo_stack = UndoStack::new(UNDO_WINDOW_SECS);
+        self.server_title = None;
+        self.description_edit = None;
+        self.income_task = None;
+        self.members = None;
+        self.members_task = None;
+        self.members_error = None;
+        self.members_cache_loaded = false;
+        self.last_members_refresh = None;
+        self.emoticons = None;
+        self.emoticon_task = None;
+        self.danmaku_config = None;
+        self.danmaku_config_task = None;
+        self.danmaku_color = domain::DEFAULT_DANMAKU_COLOR;
+        self.income = None;
+        self.income_error = None;
+        self.show_income = false;
+        self.activities = None;
+        self.activities_task = None;
+        self.saved_title_draft = None;
+        self.title_draft_prompt = None;
+        self.auto_reply_retry_at = None;
+        self.stats_task = None;
+        self.stats_retry_at = None;
+        self.stats_ring.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        self.stats_session = 0;
+        self.interaction_task = None;
+        self.interaction_retry_at = None;
+        self.interaction_session = None;
+        *self.interaction.lock().unwrap_or_else(|e| e.into_inner()) = DanmakuStats::default();
+        self.interact = None;
+        self.interact_task = None;
+        self.last_interact_fetch = None;
+        self.autostart_template = None;
+        self.live_started_at = None;
+        self.duration_reminded = false;
+        self.live_result.clear();
+        self.save_result.clear();
+        self.last_action = None;
+        self.consecutive_failures = 0;
+    }
+
+    /// 开播计时；配置了目标时长时显示进度，到点提醒一次
+    fn live_timer(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        let live = self.room_info.as_ref().map(|r| r.live_status == 1).unwrap_or(false);
+        let Some(start) = self.live_started_at.filter(|_| live) else { return };
+        let elapsed_min = (domain::now_secs() - start).max(0) / 60;
+        ui.label(format!("已直播 {} 小时 {} 分钟", elapsed_min / 60, elapsed_min % 60));
+        let target = self.settings.live_reminder_minutes as i64;
+        if target > 0 {
+            let progress = (elapsed_min as f32 / target as f32).min(1.0);
+            let text = if elapsed_min >= target {
+                "已达成目标时长".to_string()
+            } else {
+                format!("距离目标还差 {} 分钟", target - elapsed_min)
+            };
+            ui.add(egui::ProgressBar::new(progress).text(text));
+            if elapsed_min >= target && !self.duration_reminded {
+                self.duration_reminded = true;
+                self.notify.success(format!("已直播满 {} 分钟", target));
+                ctx.send_viewport_cmd(egui::ViewportCommand::RequestUserAttention(egui::UserAttentionType::Informational));
+            }
+        }
+        ui.add_space(10.0);
+        ctx.request_repaint_after(Duration::from_secs(1));
+    }
+
+    /// 选中分区变化时在后台探测一次开播资质，结果按 area_id 缓存
+    fn update_area_check(&mut self, ctx: &egui::Context) {
+        if let Some((id, res)) = self.area_check_task.as_ref().and_then(|(id, t)| t.poll().map(|r| (*id, r))) {
+            self.area_check_task = None;
+            match res {
+                Ok(reason) => {
+                    self.area_check.insert(id, reason);
+                }
+                Err(e) => println!("分区资质探测失败: {}", e),
+            }
+        }
+        let Some(area_id) = self.selected_area_id else { return };
+        let running = self.area_check_task.as_ref().map(|(id, _)| *id == area_id).unwrap_or(false);
+        if running || self.area_check.contains_key(&area_id) {
+            return;
+        }
+        let client = self.client.clone();
+        self.area_check_task = Some((area_id, BgTask::spawn(&self.rt, ctx, move |token| async move {
+            api_client::cancellable(&token, client.check_area(area_id)).await
+        })));
+    }
+
+    /// 启用 OBS 文本源且已知直播间时保持后台写入任务运行；保存配置后任务会被丢弃并按新配置重启
+    fn update_obs_text(&mut self, ctx: &egui::Context) {
+        if let Some(res) = self.obs_task.as_ref().and_then(|t| t.poll()) {
+            self.obs_task = None;
+            if let Err(e) = res {
+                println!("OBS 文本源任务退出: {}", e);
+            }
+        }
+        let config = &self.settings.obs_text;
+        let room_id = self.room_info.as_ref().map(|r| r.room_id).unwrap_or(0);
+        if !config.enabled || config.path.is_empty() || room_id == 0 {
+            self.obs_task = None;
+            return;
+        }
+        if self.obs_task.is_none() {
+            let client = self.client.clone();
+            let config = config.clone();
+            self.obs_task = Some(BgTask::spawn(&self.rt, ctx, move |token| async move {
+                client.run_obs_text(room_id, &config, &token).await
+            }));
+        }
+    }
+
+    /// 启用直播状态输出且已知直播间时保持后台输出任务运行；保存配置后任务会被丢弃并按新配置重启
+    fn update_status_output(&mut self, ctx: &egui::Context) {
+        if let Some(res) = self.status_task.as_ref().and_then(|t| t.poll()) {
+            self.status_task = None;
+            if let Err(e) = res {
+                self.notify.error(format!("直播状态输出已停止: {}", e));
+                self.settings.status_output.enabled = false;
+                self.status_input.enabled = false;
+            }
+        }
+        let config = &self.settings.status_output;
+        let room_id = self.room_info.as_ref().map(|r| r.room_id).unwrap_or(0);
+        if !config.enabled || config.path.is_empty() || room_id == 0 {
+            self.status_task = None;
+            return;
+        }
+        if self.status_task.is_none() {
+            let client = self.client.clone();
+            let config = config.clone();
+            self.status_task = Some(BgTask::spawn(&self.rt, ctx, move |token| async move {
+                client.run_status_output(room_id, &config, &token).await
+            }));
+        }
+    }
+
+    /// 已登录时按配置的间隔在后台复查登录态（优先读客户端缓存），发现失效则回到扫码登录
+    fn update_login_state(&mut self, ctx: &egui::Context) {
+        if let Some(res) = self.login_task.as_ref().and_then(|t| t.poll()) {
+            self.login_task = None;
+            match res {
+                Ok(LoginState::NeedQrCode) if matches!(self.login_state, LoginState::LoggedIn) => {
+                    self.soft_logout();
+                    self.notify.warning("登录已失效，请重新扫码登录，配置与模板均已保留");
+                }
+                Ok(_) => {}
+                Err(e) => println!("复查登录态失败: {}", e),
+            }
+        }
+        if !matches!(self.login_state, LoginState::LoggedIn) || self.login_task.is_some() {
+            return;
+        }
+        let interval = PollingConfig::interval(self.settings.polling.login_check_secs);
+        let due = self.last_login_check.map(|t| t.elapsed() >= interval).unwrap_or(true);
+        if due {
+            self.last_login_check = Some(Instant::now());
+            let client = self.client.clone();
+            self.login_task = Some(BgTask::spawn(&self.rt, ctx, move |token| async move {
+                api_client::cancellable(&token, client.cached_login_state(interval)).await
+            }));
+        }
+        ctx.request_repaint_after(interval);
+    }
+
+    /// 展示开播返回的推流信息；偏好的协议不可用时提示已回退
+    fn set_stream(&mut self, start: LiveStart) {
+        let preferred = self.settings.stream_protocol;
+        if start.protocol != preferred {
+            self.notify.warning(format!("{} 推流地址不可用，已改用 {}", preferred.label(), start.protocol.label()));
+        }
+        self.stream = Some(start.line);
+        self.stream_lines = start.lines;
+    }
+
+    /// 记住选择的推流协议，下次开播默认使用；本场有该协议的推流地址时立即切换展示
+    fn select_stream_protocol(&mut self, protocol: StreamProtocol) {
+        self.settings.stream_protocol = protocol;
+        if let Some(line) = self.stream_lines.get(protocol) {
+            self.stream = Some(line.clone());
+        }
+        if let Err(e) = BiliClient::save_settings(&self.settings) {
+            self.notify.error(format!("保存配置失败: {}", e));
+        }
+    }
+
+    /// 取回后台开播的结果；要求人脸认证时生成二维码纹理
+    fn update_start_live(&mut self, ctx: &egui::Context) {
+        let Some(pending) = &mut self.start_task else { return };
+        if pending.qr.is_none() {
+            if let Some(info) = pending.face_auth.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+                pending.qr = Some(Self::load_qr_texture(&info.qr_url, ctx));
+            }
+        }
+        let Some(res) = pending.task.poll() else { return };
+        let Some(pending) = self.start_task.take() else { return };
+        self.last_action = Some(Instant::now());
+        match res {
+            Ok(start) if start.already_live => {
+                if let Some(room) = self.room_info.as_mut().filter(|r| r.room_id == pending.room_id) {
+                    room.live_status = 1;
+                }
+                self.set_stream(start);
+                self.live_result.success("直播间已在直播，未重复开播，已取回当前推流信息");
+            }
+            Ok(start) => {
+                if let Some(room) = self.room_info.as_mut().filter(|r| r.room_id == pending.room_id) {
+                    room.live_status = 1;
+                }
+                self.set_stream(start);
+                self.live_started_at = Some(domain::now_secs());
+                self.duration_reminded = false;
+                self.live_result.success(pending.success);
+                self.settings.live_count += 1;
+                record_area_use(&mut self.settings, pending.area_id);
+                self.consecutive_failures = 0;
+                spawn_event(&self.rt, &self.client, LiveEvent::new("live_start", pending.room_id, pending.detail));
+                self.standby_task = spawn_standby_switch(&self.rt, ctx, &self.client, &self.settings.standby, pending.room_id, false);
+                let (client, room_id) = (self.client.clone(), pending.room_id);
+                self.live_wait_task = Some(BgTask::spawn(&self.rt, ctx, move |token| async move {
+                    client.wait_until_live(room_id, LIVE_WAIT_TIMEOUT, &token).await
+                }));
+            }
+            Err(e) => {
+                self.live_result.error(format!("开播失败: {}", e));
+                self.consecutive_failures += 1;
+                spawn_event(&self.rt, &self.client, LiveEvent::new("error", pending.room_id, format!("开播失败: {}", e)));
+            }
+        }
+    }
+
+    /// 取回等待直播上线的结果
+    fn update_live_wait(&mut self) {
+        let Some(res) = self.live_wait_task.as_ref().and_then(|t| t.poll()) else { return };
+        self.live_wait_task = None;
+        match res {
+            Ok(()) => self.live_result.success("直播已上线"),
+            Err(e) => self.live_result.warning(e.to_string()),
+        }
+    }
+
+    /// 保存后的同步结果：以服务端为准更新标题、分区与直播间详情，与提交值不同时提示
+    fn update_room_sync(&mut self) {
+        let Some(res) = self.room_sync.as_ref().and_then(|s| s.task.poll()) else { return };
+        let Some(sync) = self.room_sync.take() else { return };
+        let detail = match res {
+            Ok(detail) => detail,
+            Err(e) => return println!("保存后同步直播间信息失败: {}", e),
+        };
+        if let Some(room) = self.room_info.as_mut().filter(|r| r.room_id == detail.room_id as i64) {
+            if room.title == sync.title {
+                room.title = detail.title.clone();
+            }
+            self.server_title = Some(detail.title.clone());
+            if detail.title != sync.title {
+                self.save_result.warning(format!("服务端标题为「{}」，与提交的不同（可能被审核改写）", detail.title));
+            }
+        }
+        let server_area = detail.area_id as i64;
+        if sync.area_id.is_some() && self.selected_area_id == sync.area_id && self.area_sel.select_area(&self.area_list, server_area) {
+            self.selected_area_id = Some(server_area);
+            if sync.area_id != Some(server_area) {
+                self.save_result.warning(format!("服务端分区为「{}」，与提交的不同", detail.area_name));
+            }
+        }
+        self.room_detail = Some(detail);
+    }
+
+    /// 图片拖到直播页的封面放置区：载入封面编辑器预览，并按默认居中的 16:9 选区立即上传；不是 JPG / PNG 时提示
+    fn update_cover_drop(&mut self, ctx: &egui::Context) {
+        let Some(rect) = self.cover_drop_rect.take() else { return };
+        let (dropped, pos): (Vec<std::path::PathBuf>, _) =
+            ctx.input(|i| (i.raw.dropped_files.iter().filter_map(|f| f.path.clone()).collect(), i.pointer.hover_pos()));
+        let Some(path) = dropped.into_iter().next() else { return };
+        if !pos.is_some_and(|p| rect.contains(p)) {
+            return;
+        }
+        let room_id = match &self.room_info {
+            Some(room) if self.tab == Tab::Live && self.offline.is_none() => room.room_id,
+            _ => return,
+        };
+        if self.cover_upload.is_some() {
+            return self.notify.warning("封面正在上传，请稍后再试");
+        }
+        if !cover::is_supported_image(&path) {
+            let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+            return self.notify.warning(format!("「{}」不是 JPG / PNG 图片，不能用作封面", name));
+        }
+        self.cover_editor.path = path.display().to_string();
+        if let Err(e) = self.cover_editor.load(ctx) {
+            return self.notify.error(format!("加载图片失败: {}", e));
+        }
+        match spawn_cover_upload(&self.rt, ctx, &self.client, &self.cover_editor, room_id) {
+            Ok(task) => self.cover_upload = Some(task),
+            Err(e) => self.notify.error(format!("上传封面失败: {}", e)),
+        }
+    }
+
+    /// 封面上传完成后换上新封面
+    fn update_cover_upload(&mut self) {
+        let Some(res) = self.cover_upload.as_ref().and_then(|t| t.poll()) else { return };
+        self.cover_upload = None;
+        self.last_action = Some(Instant::now());
+        match res {
+            Ok(url) => {
+                self.set_cover(url);
+                self.notify.success("封面已更新，审核通过后对观众生效");
+            }
+            Err(e) => self.notify.error(format!("上传封面失败: {}", e)),
+        }
+    }
+
+    fn set_cover(&mut self, url: String) {
+        if let Some(room) = &mut self.room_info {
+            room.cover = url.clone();
+        }
+        if let Some(detail) = &mut self.room_detail {
+            detail.cover_url = url;
+        }
+    }
+
+    fn update_standby_switch(&mut self) {
+        let Some(res) = self.standby_task.as_ref().and_then(|t| t.poll()) else { return };
+        self.standby_task = None;
+        match res {
+            Ok((state, cover)) => {
+                if let Some(url) = cover {
+                    self.set_cover(url);
+                }
+                self.notify.success(format!("已切换到{}封面与预告", state));
+            }
+            Err(e) => self.notify.error(e.to_string()),
+        }
+    }
+
+    /// 处理本帧的快捷键：F5 立即刷新，Ctrl+L 切到直播页等待确认；Ctrl+Enter 由直播页的保存按钮读取
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        self.shortcuts = shortcuts::read(ctx);
+        if self.shortcuts.refresh {
+            self.refresh_all();
+            ctx.request_repaint();
+        }
+        let has_room = self.room_info.as_ref().is_some_and(|r| r.room_id != 0);
+        if self.shortcuts.toggle_live && matches!(self.login_state, LoginState::LoggedIn) && has_room {
+            self.tab = Tab::Live;
+            self.confirm_toggle_live = true;
+        }
+    }
+
+    /// 重新获取用户、直播间与分区信息；扫码页则换一张新二维码，离线时立即探测网络
+    fn refresh_all(&mut self) {
+        match self.login_state {
+            LoginState::NeedQrCode => {
+                self.qr_texture = None;
+                self.qr_info = None;
+                self.qr_poll = None;
+                self.qr_error = None;
+                self.qr_retry_at = None;
+                self.qr_fetch = None;
+            }
+            LoginState::LoggedIn if self.offline.is_some() => self.last_offline_probe = None,
+            LoginState::LoggedIn => {
+                self.user_info = None;
+                self.room_info = None;
+                self.room_detail = None;
+                self.last_user_info_fetch = None;
+            }
+        }
+    }
+
+    /// 分区列表加载后按 id 重新定位选择：优先保留当前选择，其次是直播间当前分区和最近使用的分区
+    fn relocate_area(&mut self) {
+        let room_area = self.room_detail.as_ref().map(|r| r.area_id as i64);
+        let ids = [self.selected_area_id, room_area.filter(|id| *id > 0), self.settings.last_used_area()];
+        self.selected_area_id = self.area_sel.relocate(&self.area_list, ids.into_iter().flatten());
+    }
+
+    /// 网络不可用时载入上次缓存的用户、直播间与分区信息，进入只读模式。没有缓存时返回 false
+    fn enter_offline(&mut self) -> bool {
+        let Some(snapshot) = BiliClient::load_offline_snapshot() else { return false };
+        let label = snapshot.saved_at_label();
+        let Some(user) = snapshot.user else { return false };
+        self.server_title = Some(user.live_room.title.clone());
+        self.room_info = Some(user.live_room.clone());
+        self.room_detail = snapshot.room;
+        if !snapshot.areas.is_empty() {
+            self.area_list = snapshot.areas;
+            self.area_list_fetch_error = None;
+        }
+        self.user_info = Some(user);
+        self.relocate_area();
+        self.members = BiliClient::load_room_members_cache();
+        self.members_cache_loaded = true;
+        self.offline = Some(label);
+        self.last_offline_probe = Some(Instant::now());
+        true
+    }
+
+    /// 离线模式下定期探测网络，恢复后丢弃缓存数据重新获取；登录已失效则回到扫码
+    fn update_offline(&mut self, ctx: &egui::Context) {
+        if self.offline.is_none() {
+            self.offline_probe = None;
+            return;
+        }
+        if let Some(res) = self.offline_probe.as_ref().and_then(|t| t.poll()) {
+            self.offline_probe = None;
+            match res {
+                Ok(LoginState::LoggedIn) => {
+                    self.offline = None;
+                    self.user_info = None;
+                    self.room_info = None;
+                    self.room_detail = None;
+                    self.last_user_info_fetch = None;
+                    self.consecutive_failures = 0;
+                    self.notify.success("网络已恢复，已刷新在线数据");
+                }
+                Ok(LoginState::NeedQrCode) => {
+                    self.soft_logout();
+                    self.notify.warning("网络已恢复，但登录已失效，请重新扫码登录");
+                }
+                Err(e) => println!("网络仍不可用: {}", e),
+            }
+            return;
+        }
+        let interval = PollingConfig::interval(self.settings.polling.offline_probe_secs);
+        let due = self.last_offline_probe.map(|t| t.elapsed() >= interval).unwrap_or(true);
+        if due && self.offline_probe.is_none() {
+            self.last_offline_probe = Some(Instant::now());
+            let client = self.client.clone();
+            self.offline_probe = Some(BgTask::spawn(&self.rt, ctx, move |token| async move {
+                api_client::cancellable(&token, client.check_login_state()).await
+            }));
+        }
+        ctx.request_repaint_after(interval);
+    }
+
+    /// 启用自动回复且已知直播间时保持弹幕监听任务运行；连接异常退出后等待重连间隔再重连
+    fn update_auto_reply(&mut self, ctx: &egui::Context) {
+        if let Some(res) = self.auto_reply_task.as_ref().and_then(|t| t.poll()) {
+            self.auto_reply_task = None;
+            if let Err(e) = res {
+                println!("自动回复任务退出: {}", e);
+                self.auto_reply_retry_at = Some(Instant::now() + PollingConfig::interval(self.settings.polling.reconnect_secs));
+            }
+        }
+        let config = &self.settings.auto_reply;
+        let room_id = self.room_info.as_ref().map(|r| r.room_id).unwrap_or(0);
+        if !config.enabled || config.rules.is_empty() || room_id == 0 {
+            self.auto_reply_task = None;
+            return;
+        }
+        if let Some(at) = self.auto_reply_retry_at {
+            if Instant::now() < at {
+                ctx.request_repaint_after(at - Instant::now());
+                return;
+            }
+            self.auto_reply_retry_at = None;
+        }
+        if self.auto_reply_task.is_none() {
+            let client = self.client.clone();
+            let config = config.clone();
+            self.auto_reply_task = Some(BgTask::spawn(&self.rt, ctx, move |token| async move {
+                client.run_auto_reply(room_id, &config, &token).await
+            }));
+        }
+    }
+
+    /// 开播期间按 `stats_interval_secs` 在后台记录数据快照；任务退出（停播或出错）后等待一个间隔再视情况重启
+    fn update_stats_collector(&mut self, ctx: &egui::Context) {
+        let interval = Duration::from_secs(self.settings.stats_interval_secs);
+        if let Some(res) = self.stats_task.as_ref().and_then(|t| t.poll()) {
+            self.stats_task = None;
+            self.stats_retry_at = Some(Instant::now() + interval);
+            if let Err(e) = res {
+                println!("数据采集任务退出: {}", e);
+            }
+        }
+        let live = self.room_info.as_ref().filter(|r| r.live_status == 1).map(|r| r.room_id);
+        let Some(room_id) = live.filter(|_| !interval.is_zero()) else {
+            self.stats_task = None;
+            return;
+        };
+        if self.stats_retry_at.is_some_and(|at| Instant::now() < at) {
+            return;
+        }
+        let live_start = self.live_started_at.unwrap_or(0);
+        if self.stats_session != live_start {
+            self.stats_session = live_start;
+            self.stats_ring.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        }
+        if self.stats_task.is_none() {
+            let client = self.client.clone();
+            let ring = self.stats_ring.clone();
+            let repaint = ctx.clone();
+            self.stats_task = Some(BgTask::spawn(&self.rt, ctx, move |token| async move {
+                client
+                    .start_stats_collector(room_id, interval, &token, move |snapshot| {
+                        ring.lock().unwrap_or_else(|e| e.into_inner()).push(snapshot);
+                        repaint.request_repaint();
+                    })
+                    .await
+            }));
+        }
+    }
+
+    /// 开播期间监听弹幕累计本场互动；停播后自动导出摘要，换场时清零
+    fn update_interaction_stats(&mut self, ctx: &egui::Context) {
+        if let Some(res) = self.interaction_task.as_ref().and_then(|t| t.poll()) {
+            self.interaction_task = None;
+            self.interaction_retry_at = Some(Instant::now() + PollingConfig::interval(self.settings.polling.reconnect_secs));
+            if let Err(e) = res {
+                println!("互动统计任务退出: {}", e);
+            }
+        }
+        let live = self.room_info.as_ref().filter(|r| r.live_status == 1).map(|r| r.room_id);
+        let session = live.map(|room_id| (room_id, self.live_started_at.unwrap_or(0)));
+        if session != self.interaction_session {
+            if self.interaction_session.is_some() {
+                self.export_interaction();
+            }
+            self.interaction_task = None;
+            self.interaction_retry_at = None;
+            self.interaction_session = session;
+            *self.interaction.lock().unwrap_or_else(|e| e.into_inner()) = DanmakuStats::default();
+        }
+        let Some(room_id) = live else { return };
+        if self.interaction_task.is_some() || self.interaction_retry_at.is_some_and(|at| Instant::now() < at) {
+            return;
+        }
+        let client = self.client.clone();
+        let shared = self.interaction.clone();
+        let start = *shared.lock().unwrap_or_else(|e| e.into_inner());
+        let repaint = ctx.clone();
+        self.interaction_task = Some(BgTask::spawn(&self.rt, ctx, move |token| async move {
+            client
+                .collect_danmaku_stats(room_id, start, &token, move |stats| {
+                    *shared.lock().unwrap_or_else(|e| e.into_inner()) = stats;
+                    repaint.request_repaint();
+                })
+                .await
+        }));
+    }
+
+    /// 导出当前场次的互动摘要，没有任何互动时跳过
+    fn export_interaction(&mut self) {
+        let Some((room_id, live_start)) = self.interaction_session else { return };
+        let stats = self.interaction.lock().unwrap_or_else(|e| e.into_inner()).snapshot();
+        if stats == DanmakuStats::default() {
+            return;
+        }
+        match BiliClient::save_danmaku_summary(room_id, live_start, &stats) {
+            Ok(path) => self.notify.success(format!("本场互动: {}，已导出到 {}", stats.summary(), path.display())),
+            Err(e) => self.notify.error(format!("导出互动摘要失败: {}", e)),
+        }
+    }
+
+    /// 已知直播间时按配置的间隔在后台刷新 PK 状态
+    fn update_interact_status(&mut self, ctx: &egui::Context) {
+        if let Some(res) = self.interact_task.as_ref().and_then(|t| t.poll()) {
+            self.interact_task = None;
+            match res {
+                Ok(status) => self.interact = Some(status),
+                Err(e) => println!("获取 PK 状态失败: {}", e),
+            }
+        }
+        let room_id = self.room_info.as_ref().map(|r| r.room_id).unwrap_or(0);
+        let interval = PollingConfig::interval(self.settings.polling.interact_secs);
+        let due = self.last_interact_fetch.map(|t| t.elapsed() >= interval).unwrap_or(true);
+        if room_id == 0 || self.interact_task.is_some() || !due {
+            return;
+        }
+        self.last_interact_fetch = Some(Instant::now());
+        let client = self.client.clone();
+        self.interact_task = Some(BgTask::spawn(&self.rt, ctx, move |token| async move {
+            api_client::cancellable(&token, client.get_interact_status(room_id)).await
+        }));
+        ctx.request_repaint_after(interval);
+    }
+
+    /// 网络诊断按钮与结果表
+    fn network_diag_ui(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        if let Some(res) = self.diag_task.as_ref().and_then(|t| t.poll()) {
+            self.diag_task = None;
+            if let Ok(diag) = res {
+                self.network_diag = Some(diag);
+            }
+        }
+        ui.horizontal(|ui| {
+            if ui.add_enabled(self.diag_task.is_none(), egui::Button::new("网络诊断")).clicked() {
+                let client = self.client.clone();
+                self.diag_task = Some(BgTask::spawn(&self.rt, ctx, move |_| async move { anyhow::Ok(client.diagnose_network().await) }));
+            }
+            if self.diag_task.is_some() {
+                ui.spinner();
+            }
+        });
+        if let Some(diag) = &self.network_diag {
+            egui::Grid::new("network_diag_grid").striped(true).show(ui, |ui| {
+                for h in &diag.hosts {
+                    ui.label(&h.host);
+                    match &h.error {
+                        None => ui.colored_label(egui::Color32::GREEN, format!("可达 {} ms", h.latency_ms)),
+                        Some(e) => ui.colored_label(egui::Color32::RED, e),
+                    };
+                    ui.end_row();
+                }
+            });
+        }
+    }
+
+    /// 常驻顶部的账号状态栏：头像、用户名与直播状态徽标
+    fn top_bar(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        ui.horizontal(|ui| {
+            match (&self.login_state, &self.user_info) {
+                (LoginState::LoggedIn, Some(user)) => {
+                    if let Some(av) = self.images.get(&user.face, &self.rt, &self.client, ctx) {
+                        ui.image((av.id(), egui::vec2(24.0, 24.0)));
+                    }
+                    ui.label(&user.name);
+                    let live = self.room_info.as_ref().map(|r| r.live_status == 1).unwrap_or(false);
+                    let (text, color) = if live {
+                        ("直播中", egui::Color32::from_rgb(0, 180, 90))
+                    } else {
+                        ("未开播", egui::Color32::GRAY)
+                    };
+                    ui.label(egui::RichText::new(text).color(egui::Color32::WHITE).background_color(color).small());
+                }
+                (LoginState::LoggedIn, None) => {
+                    ui.label("正在获取用户信息...");
+                }
+                (LoginState::NeedQrCode, _) => {
+                    ui.label("未登录");
+                    if ui.button("去登录").clicked() {
+                        self.tab = Tab::Live;
+                    }
+                }
+            }
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                ui.checkbox(&mut self.privacy_mode, "隐私模式")
+                    .on_hover_text("打码 UID、推流密钥与 cookie，并禁用复制，适合录屏或截图");
+            });
+        });
+    }
+
+    /// 本场直播的人气 / 在线人数趋势，数据来自采集任务的环形缓冲
+    fn data_page(&mut self, ui: &mut egui::Ui) {
+        let live = self.room_info.as_ref().map(|r| r.live_status == 1).unwrap_or(false);
+        let stats_ring = self.stats_ring.clone();
+        let ring = stats_ring.lock().unwrap_or_else(|e| e.into_inner());
+        if !live {
+            ui.label("未开播，开播后开始采样");
+        } else if self.settings.stats_interval_secs == 0 {
+            ui.label("数据快照已关闭，可在设置页的“开播”中设置采样间隔");
+        } else if ring.is_empty() {
+            ui.label("等待第一个采样点…");
+        } else {
+            ui.label(format!("最近 {} 个采样点，每 {} 秒一次", ring.samples().count(), self.settings.stats_interval_secs));
+        }
+        ui.add_space(5.0);
+        let empty = StatsRing::default();
+        let ring = if live { &*ring } else { &empty };
+        ui.label("人气");
+        chart::trend_chart(ui, ring, "人气", egui::Color32::from_rgb(251, 114, 153), |s| s.popularity);
+        ui.add_space(10.0);
+        ui.label("在线人数");
+        chart::trend_chart(ui, ring, "在线", egui::Color32::from_rgb(0, 174, 236), |s| s.online);
+        ui.add_space(10.0);
+        self.interaction_ui(ui);
+        // 隐私模式下完全不展示收益
+        if !self.privacy_mode {
+            ui.add_space(10.0);
+            self.income_ui(ui);
+        }
+        ui.add_space(10.0);
+        self.activities_ui(ui);
+    }
+
+    /// 本场互动概况：弹幕、礼物、上舰、进场的累计次数
+    fn interaction_ui(&mut self, ui: &mut egui::Ui) {
+        let stats = self.interaction.lock().unwrap_or_else(|e| e.into_inner()).snapshot();
+        ui.group(|ui| {
+            ui.horizontal(|ui| {
+                ui.heading("本场互动");
+                if self.interaction_task.is_some() {
+                    ui.spinner();
+                }
+                if ui.add_enabled(self.interaction_session.is_some(), egui::Button::new("导出摘要")).clicked() {
+                    self.export_interaction();
+                }
+            });
+            if self.interaction_session.is_none() {
+                ui.label("开播后开始统计");
+                return;
+            }
+            egui::Grid::new("interaction_stats").num_columns(2).show(ui, |ui| {
+                for (name, value) in [("弹幕", stats.danmaku), ("礼物", stats.gifts), ("上舰", stats.guards), ("进场", stats.enters)] {
+                    ui.label(name);
+                    ui.label(value.to_string());
+                    ui.end_row();
+                }
+            });
+            if stats.disconnects > 0 {
+                ui.weak(format!("弹幕连接断开过 {} 次，期间的互动未计入", stats.disconnects));
+            }
+        });
+    }
+
+    /// 收益概览：默认打码，点击"显示"后才展示金额
+    fn income_ui(&mut self, ui: &mut egui::Ui) {
+        if let Some(res) = self.income_task.as_ref().and_then(|t| t.poll()) {
+            self.income_task = None;
+            match res {
+                Ok(overview) => {
+                    self.income = Some(overview);
+                    self.income_error = None;
+                }
+                Err(e) => self.income_error = Some(format!("{:#}", e)),
+            }
+        }
+        ui.group(|ui| {
+            ui.horizontal(|ui| {
+                ui.heading("收益概览");
+                if self.income_task.is_some() {
+                    ui.spinner();
+                } else if ui.button("刷新").clicked() {
+                    let client = self.client.clone();
+                    let session_start = self.live_started_at;
+                    self.income_task = Some(BgTask::spawn(&self.rt, ui.ctx(), move |token| async move {
+                        api_client::cancellable(&token, client.get_live_income_overview(session_start)).await
+                    }));
+                }
+                ui.checkbox(&mut self.show_income, "显示金额");
+            });
+            if let Some(err) = &self.income_error {
+                ui.colored_label(egui::Color32::RED, err);
+            }
+            let Some(overview) = &self.income else {
+                if self.income_error.is_none() {
+                    ui.label("点击刷新获取本月与本场的礼物收益");
+                }
+                return;
+            };
+            let amount = |gold: u64| {
+                if self.show_income {
+                    format!("{:.1} 电池", IncomeOverview::gold_to_battery(gold))
+                } else {
+                    "*** 电池".to_string()
+                }
+            };
+            egui::Grid::new("income_grid").num_columns(3).spacing([20.0, 4.0]).show(ui, |ui| {
+                ui.label("本月");
+                ui.label(amount(overview.month_gold));
+                ui.label(format!("{} 个礼物", overview.month_gifts));
+                ui.end_row();
+                ui.label("本场");
+                ui.label(amount(overview.session_gold));
+                ui.label(format!("{} 个礼物", overview.session_gifts));
+                ui.end_row();
+            });
+            if overview.truncated {
+                ui.label("礼物流水过多，只统计了最近的一部分");
+            }
+        });
+    }
+
+    /// 直播活动列表：首次展示时自动拉取，失败只记录日志，不打扰用户
+    fn activities_ui(&mut self, ui: &mut egui::Ui) {
+        if let Some(res) = self.activities_task.as_ref().and_then(|t| t.poll()) {
+            self.activities_task = None;
+            self.activities = Some(res.unwrap_or_else(|e| {
+                println!("获取直播活动失败: {:#}", e);
+                Vec::new()
+            }));
+        }
+        let mut refresh = self.activities.is_none() && self.activities_task.is_none() && self.offline.is_none();
+        ui.group(|ui| {
+            ui.horizontal(|ui| {
+                ui.heading("直播活动");
+                if self.activities_task.is_some() {
+                    ui.spinner();
+                } else if ui.add_enabled(self.offline.is_none(), egui::Button::new("刷新")).clicked() {
+                    refresh = true;
+                }
+            });
+            match &self.activities {
+                Some(list) if list.is_empty() => {
+                    ui.label("暂无可参与的活动");
+                }
+                Some(list) => {
+                    for activity in list {
+                        ui.horizontal_wrapped(|ui| {
+                            ui.hyperlink_to(&activity.title, &activity.url).on_hover_text(&activity.url);
+                            let time = activity.time_label();
+                            if !time.is_empty() {
+                                ui.weak(time);
+                            }
+                        });
+                        if !activity.reward.is_empty() {
+                            ui.label(format!("奖励：{}", activity.reward));
+                        }
+                    }
+                }
+                None if self.offline.is_some() => {
+                    ui.label("离线模式下不获取活动");
+                }
+                None => {}
+            }
+        });
+        if refresh {
+            let client = self.client.clone();
+            self.activities_task = Some(BgTask::spawn(&self.rt, ui.ctx(), move |token| async move {
+                api_client::cancellable(&token, client.get_live_activities()).await
+            }));
+        }
+    }
+
+    /// 后台拉取房管与大航海成员，结果同时写入磁盘缓存。离线、没有直播间或已在拉取时什么也不做
+    fn refresh_members(&mut self, ctx: &egui::Context) {
+        let Some(room_id) = self.room_info.as_ref().map(|r| r.room_id).filter(|id| *id != 0) else { return };
+        if self.offline.is_some() || self.members_task.is_some() {
+            return;
+        }
+        self.last_members_refresh = Some(Instant::now());
+        let client = self.client.clone();
+        self.members_task = Some(BgTask::spawn(&self.rt, ctx, move |token| async move {
+            api_client::cancellable(&token, client.refresh_room_members(room_id)).await
+        }));
+    }
+
+    /// 房管与大航海成员：立即展示缓存，距上次刷新较久时在后台更新
+    fn members_page(&mut self, ui: &mut egui::Ui) {
+        if let Some(res) = self.members_task.as_ref().and_then(|t| t.poll()) {
+            self.members_task = None;
+            match res {
+                Ok(members) => {
+                    self.members = Some(members);
+                    self.members_error = None;
+                }
+                Err(e) => self.members_error = Some(format!("{:#}", e)),
+            }
+        }
+        if !self.members_cache_loaded {
+            self.members_cache_loaded = true;
+            if self.members.is_none() {
+                self.members = BiliClient::load_room_members_cache();
+            }
+        }
+        if self.last_members_refresh.map(|t| t.elapsed() >= PollingConfig::interval(self.settings.polling.members_secs)).unwrap_or(true) {
+            self.refresh_members(ui.ctx());
+        }
+        if !matches!(self.login_state, LoginState::LoggedIn) {
+            ui.label("登录后查看房管与大航海成员");
+            return;
+        }
+        ui.horizontal(|ui| {
+            ui.heading("房管与大航海");
+            if self.members_task.is_some() {
+                ui.spinner();
+            } else if ui.add_enabled(self.offline.is_none(), egui::Button::new("刷新")).clicked() {
+                self.refresh_members(ui.ctx());
+            }
+            if let Some(members) = &self.members {
+                ui.weak(format!("缓存于 {}", members.saved_at_label()));
+            }
+        });
+        if let Some(err) = &self.members_error {
+            ui.colored_label(egui::Color32::RED, err);
+        }
+        let Some(members) = &self.members else {
+            if self.members_error.is_none() {
+                ui.label(if self.members_task.is_some() { "正在获取…" } else { "暂无缓存" });
+            }
+            return;
+        };
+        let uid = |uid: u64| if self.privacy_mode { redact(&uid.to_string()) } else { uid.to_string() };
+        ui.add_space(5.0);
+        ui.group(|ui| {
+            ui.heading(format!("房管（{}）", members.data.admins.len()));
+            egui::Grid::new("room_admins").num_columns(3).striped(true).spacing([20.0, 4.0]).show(ui, |ui| {
+                for admin in &members.data.admins {
+                    ui.label(&admin.name);
+                    ui.label(uid(admin.uid));
+                    ui.weak(&admin.appointed_at);
+                    ui.end_row();
+                }
+            });
+        });
+        ui.add_space(10.0);
+        ui.group(|ui| {
+            ui.heading(format!("大航海（{}）", members.data.guards.len()));
+            egui::Grid::new("room_guards").num_columns(4).striped(true).spacing([20.0, 4.0]).show(ui, |ui| {
+                for guard in &members.data.guards {
+                    ui.label(guard.level_name());
+                    ui.label(&guard.name);
+                    ui.label(uid(guard.uid));
+                    ui.weak(format!("勋章 {} 级", guard.medal_level));
+                    ui.end_row();
+                }
+            });
+        });
+    }
+
+    fn settings_page(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        ui.group(|ui| {
+            ui.heading("本地数据");
+            ui.add_space(5.0);
+            egui::Grid::new("local_paths").num_columns(3).spacing([10.0, 4.0]).show(ui, |ui| {
+                for (name, path) in BiliClient::local_paths() {
+                    ui.label(name);
+                    ui.add(egui::Label::new(egui::RichText::new(path.display().to_string()).monospace()).wrap(true));
+                    if ui.button("打开所在文件夹").clicked() {
+                        // 文件或目录可能还没创建，打开最近一级存在的目录
+                        let dir = path.ancestors().skip(if path.is_dir() { 0 } else { 1 }).find(|p| p.is_dir());
+                        match dir.map(open_folder) {
+                            Some(Ok(())) => {}
+                            Some(Err(e)) => self.notify.error(format!("无法打开文件夹: {}", e)),
+                            None => self.notify.warning("该目录尚未创建"),
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+            ui.add_space(5.0);
+            if matches!(self.login_state, LoginState::LoggedIn) && ui.button("退出登录（保留配置与缓存）").clicked() {
+                self.soft_logout();
+                self.tab = Tab::Live;
+                self.notify.success("已退出登录");
+            }
+            ui.checkbox(&mut self.reset_keep_config, "保留配置，仅清除登录信息与缓存");
+            if !self.confirm_reset {
+                if ui.add_sized([200.0, 30.0], egui::Button::new("清除登录和缓存")).clicked() {
+                    self.confirm_reset = true;
+                }
+            } else {
+                ui.colored_label(egui::Color32::YELLOW, "确定要清除本地数据吗？清除后需要重新扫码登录。");
+                ui.horizontal(|ui| {
+                    if ui.button("确认清除").clicked() {
+                        self.confirm_reset = false;
+                        match self.client.clear_local_data(self.reset_keep_config) {
+                            Ok(()) => {
+                                self.relogin_draft = None;
+                                self.reset_session_state();
+                                self.tab = Tab::Live;
+                                self.notify.success("本地数据已清除");
+                            }
+                            Err(e) => self.notify.error(format!("清除失败: {}", e)),
+                        }
+                    }
+                    if ui.button("取消").clicked() {
+                        self.confirm_reset = false;
+                    }
+                });
+            }
+        });
+        ui.add_space(10.0);
+
+        ui.group(|ui| {
+            ui.heading("外观");
+            ui.add_space(5.0);
+            ui.horizontal(|ui| {
+                ui.label("界面缩放");
+                let resp = ui.add(egui::Slider::new(&mut self.settings.ui_scale, UI_SCALE_RANGE).step_by(0.1).suffix("×"));
+                if ui.button("重置").clicked() {
+                    self.settings.ui_scale = 1.0;
+                }
+                // 拖动过程中不缩放，否则滑块会在指针下移动
+                if !resp.dragged() && self.settings.ui_scale != self.applied_zoom {
+                    self.apply_zoom(ui.ctx());
+                }
+            });
+            ui.label(format!("系统 DPI 缩放 {:.2}×，也可用 Ctrl + 加号 / 减号 / 0 调整", ui.ctx().native_pixels_per_point().unwrap_or(1.0)));
+        });
+        ui.add_space(10.0);
+
+        ui.group(|ui| {
+            ui.heading("开播");
+            ui.add_space(5.0);
+            ui.horizontal(|ui| {
+                ui.label("直播满");
+                let resp = ui.add(egui::DragValue::new(&mut self.settings.live_reminder_minutes).clamp_range(0..=1440).suffix(" 分钟"));
+                ui.label("时提醒（0 为关闭）");
+                if drag_committed(&resp) {
+                    if let Err(e) = BiliClient::save_settings(&self.settings) {
+                        self.notify.error(format!("保存配置失败: {}", e));
+                    }
+                }
+            });
+            if ui.checkbox(&mut self.autostart_enabled, "开机自启（最小化启动）").changed() {
+                match autostart::set_autostart(self.autostart_enabled) {
+                    Ok(()) => self.notify.success(if self.autostart_enabled { "已开启开机自启" } else { "已关闭开机自启" }),
+                    Err(e) => {
+                        self.autostart_enabled = autostart::is_autostart_enabled();
+                        self.notify.error(format!("设置开机自启失败: {}", e));
+                    }
+                }
+            }
+            ui.horizontal(|ui| {
+                ui.label("开播期间每");
+                let resp = ui.add(egui::DragValue::new(&mut self.settings.stats_interval_secs).clamp_range(0..=3600).suffix(" 秒"));
+                ui.label("记录一次数据快照（0 为关闭）");
+                if drag_committed(&resp) {
+                    self.stats_task = None;
+                    self.stats_retry_at = None;
+                    if let Err(e) = BiliClient::save_settings(&self.settings) {
+                        self.notify.error(format!("保存配置失败: {}", e));
+                    }
+                }
+            });
+            egui::CollapsingHeader::new("轮询间隔").show(ui, |ui| {
+                ui.label("后台刷新与重试的间隔，弱网或省电时可以调大");
+                let mut changed = false;
+                egui::Grid::new("polling_grid").num_columns(2).show(ui, |ui| {
+                    for (name, secs) in self.settings.polling.fields_mut() {
+                        ui.label(name);
+                        changed |= drag_committed(&ui.add(egui::DragValue::new(secs).clamp_range(domain::POLLING_RANGE).suffix(" 秒")));
+                        ui.end_row();
+                    }
+                });
+                if ui.button("恢复默认").clicked() {
+                    self.settings.polling = PollingConfig::default();
+                    changed = true;
+                }
+                if changed {
+                    self.client.set_polling(self.settings.polling);
+                    if let Err(e) = BiliClient::save_settings(&self.settings) {
+                        self.notify.error(format!("保存配置失败: {}", e));
+                    }
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("推流协议:");
+                let mut protocol = self.settings.stream_protocol;
+                for p in StreamProtocol::ALL {
+                    ui.radio_value(&mut protocol, p, p.label());
+                }
+                ui.weak("开播时不可用则自动改用另一个");
+                if protocol != self.settings.stream_protocol {
+                    self.select_stream_protocol(protocol);
+                }
+            });
+            if ui.checkbox(&mut self.settings.rollback_failed_start, "开播未返回推流地址时自动关播回滚").changed() {
+                if let Err(e) = BiliClient::save_settings(&self.settings) {
+                    self.notify.error(format!("保存配置失败: {}", e));
+                }
+            }
+            let resp = ui
+                .checkbox(&mut self.settings.confirm_cookie_refresh, "刷新 cookie 后让旧凭据失效")
+                .on_hover_text("关闭后多台设备共用同一账号时不会互相踢下线，但旧的 refresh_token 会一直有效，泄露后风险更大");
+            if resp.changed() {
+                if let Err(e) = BiliClient::save_settings(&self.settings) {
+                    self.notify.error(format!("保存配置失败: {}", e));
+                }
+            }
+        });
+        ui.add_space(10.0);
+
+        ui.group(|ui| {
+            ui.heading("网络代理");
+            ui.add_space(5.0);
+            ui.label("留空时依次使用环境变量 HTTPS_PROXY / ALL_PROXY 与系统代理；填 off 表示直连");
+            ui.add(egui::TextEdit::singleline(&mut self.proxy_input).hint_text("http://127.0.0.1:7890").desired_width(ui.available_width().min(300.0)));
+            ui.add_space(5.0);
+            ui.label("按域名分流（可选）：每行「域名 代理地址」或「域名 off」，同时匹配子域名，未命中的请求走上面的代理");
+            ui.add(
+                egui::TextEdit::multiline(&mut self.proxy_rules_input)
+                    .hint_text("hdslb.com off\nbilibili.com http://127.0.0.1:7890")
+                    .desired_rows(2)
+                    .desired_width(ui.available_width().min(400.0)),
+            );
+            if ui
+                .checkbox(&mut self.settings.proxy_failover, "连接失败时改走备用路线重试一次（代理 ↔ 直连）")
+                .on_hover_text("开启后代理不可用时请求会绕过代理直连")
+                .changed()
+            {
+                let res = BiliClient::save_settings(&self.settings).and_then(|()| self.client.rebuild());
+                if let Err(e) = res {
+                    self.notify.error(format!("保存配置失败: {}", e));
+                }
+            }
+            if ui.button("保存并应用").clicked() {
+                let proxy = self.proxy_input.trim().to_string();
+                let res = if proxy.is_empty() || proxy.eq_ignore_ascii_case(api_client::PROXY_OFF) {
+                    Ok(())
+                } else {
+                    api_client::parse_proxy(&proxy).map(drop).map_err(anyhow::Error::from)
+                };
+                let res = res
+                    .and_then(|()| domain::parse_proxy_rules(&self.proxy_rules_input).map_err(anyhow::Error::msg))
+                    .and_then(|rules| {
+                        api_client::check_proxy_rules(&rules)?;
+                        self.settings.proxy = (!proxy.is_empty()).then_some(proxy);
+                        self.settings.proxy_rules = rules;
+                        BiliClient::save_settings(&self.settings)?;
+                        Ok(self.client.rebuild()?)
+                    });
+                match res {
+                    Ok(()) => self.notify.success("代理设置已应用"),
+                    Err(e) => self.notify.error(format!("应用代理设置失败: {}", e)),
+                }
+            }
+        });
+        ui.add_space(10.0);
+
+        ui.group(|ui| {
+            ui.heading("事件推送");
+            ui.add_space(5.0);
+            ui.label("开播、关播与出错时 POST JSON（event、room_id、time、detail）到该地址");
+            ui.horizontal_wrapped(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut self.webhook_input).password(self.privacy_mode).hint_text("https://...").desired_width(ui.available_width().min(400.0)));
+                if ui.button("保存").clicked() {
+                    let url = self.webhook_input.trim();
+                    self.settings.webhook_url = (!url.is_empty()).then(|| url.to_string());
+                    match BiliClient::save_settings(&self.settings) {
+                        Ok(()) => self.notify.success("webhook 已保存"),
+                        Err(e) => self.notify.error(format!("保存配置失败: {}", e)),
+                    }
+                }
+            });
+        });
+        ui.add_space(10.0);
+
+        ui.group(|ui| {
+            ui.heading("OBS 文本源");
+            ui.add_space(5.0);
+            ui.label("定时把直播间信息写入文本文件，供 OBS 文本源读取。占位符：{title} {area} {online} {duration}");
+            ui.checkbox(&mut self.obs_input.enabled, "启用");
+            ui.horizontal_wrapped(|ui| {
+                ui.label("输出文件:");
+                ui.add(egui::TextEdit::singleline(&mut self.obs_input.path).hint_text("C:\\obs\\live.txt").desired_width(ui.available_width().min(400.0)));
+            });
+            ui.label("模板:");
+            ui.add(egui::TextEdit::multiline(&mut self.obs_input.template).desired_rows(2).desired_width(ui.available_width().min(400.0)));
+            ui.horizontal(|ui| {
+                ui.label("刷新间隔:");
+                ui.add(egui::DragValue::new(&mut self.obs_input.interval_secs).clamp_range(1..=3600).suffix(" 秒"));
+            });
+            if ui.button("保存").clicked() {
+                self.obs_input.path = self.obs_input.path.trim().to_string();
+                self.settings.obs_text = self.obs_input.clone();
+                self.obs_task = None;
+                match BiliClient::save_settings(&self.settings) {
+                    Ok(()) => self.notify.success("OBS 文本源设置已保存"),
+                    Err(e) => self.notify.error(format!("保存配置失败: {}", e)),
+                }
+            }
+        });
+        ui.add_space(10.0);
+
+        ui.group(|ui| {
+            ui.heading("直播状态输出");
+            ui.add_space(5.0);
+            ui.label("定时把直播状态（live_status、online、duration、title 等）写成一行 JSON，供 overlay 或脚本读取");
+            ui.checkbox(&mut self.status_input.enabled, "启用");
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut self.status_input.mode, StatusOutputMode::File, "覆盖写文件");
+                ui.radio_value(&mut self.status_input.mode, StatusOutputMode::Pipe, "命名管道");
+            });
+            let hint = match self.status_input.mode {
+                StatusOutputMode::File => "C:\\obs\\status.json",
+                StatusOutputMode::Pipe if cfg!(windows) => "bili_status（即 \\\\.\\pipe\\bili_status）",
+                StatusOutputMode::Pipe => "/tmp/bili_status（不存在时自动创建 FIFO）",
+            };
+            ui.horizontal_wrapped(|ui| {
+                ui.label("路径:");
+                ui.add(egui::TextEdit::singleline(&mut self.status_input.path).hint_text(hint).desired_width(ui.available_width().min(400.0)));
+            });
+            ui.horizontal(|ui| {
+                ui.label("刷新间隔:");
+                ui.add(egui::DragValue::new(&mut self.status_input.interval_secs).clamp_range(1..=3600).suffix(" 秒"));
+            });
+            if ui.button("保存").clicked() {
+                self.status_input.path = self.status_input.path.trim().to_string();
+                self.settings.status_output = self.status_input.clone();
+                self.status_task = None;
+                match BiliClient::save_settings(&self.settings) {
+                    Ok(()) => self.notify.success("直播状态输出设置已保存"),
+                    Err(e) => self.notify.error(format!("保存配置失败: {}", e)),
+                }
+            }
+        });
+        ui.add_space(10.0);
+
+        ui.group(|ui| {
+            ui.heading("自动回复");
+            ui.add_space(5.0);
+            ui.label("监听直播间弹幕，按规则自动发送回复。回复内容中的 {user} 会替换为对方昵称，自己的弹幕不会触发");
+            ui.checkbox(&mut self.auto_reply_input.enabled, "启用");
+            ui.horizontal(|ui| {
+                ui.label("最小回复间隔:");
+                ui.add(egui::DragValue::new(&mut self.auto_reply_input.min_interval_secs).clamp_range(1..=600).suffix(" 秒"));
+            });
+            let mut remove = None;
+            for (i, rule) in self.auto_reply_input.rules.iter_mut().enumerate() {
+                ui.horizontal_wrapped(|ui| {
+                    egui::ComboBox::from_id_source(("auto_reply_trigger", i))
+                        .selected_text(rule.trigger.label())
+                        .show_ui(ui, |ui| {
+                            for t in ReplyTrigger::ALL {
+                                ui.selectable_value(&mut rule.trigger, t, t.label());
+                            }
+                        });
+                    if rule.trigger == ReplyTrigger::Keyword {
+                        ui.add(egui::TextEdit::singleline(&mut rule.keyword).hint_text("关键词").desired_width(100.0));
+                    }
+                    ui.add(egui::TextEdit::singleline(&mut rule.reply).hint_text("欢迎 {user}").desired_width(ui.available_width().min(240.0)));
+                    if ui.button("删除").clicked() {
+                        remove = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = remove {
+                self.auto_reply_input.rules.remove(i);
+            }
+            ui.horizontal(|ui| {
+                if ui.button("添加规则").clicked() {
+                    self.auto_reply_input.rules.push(AutoReplyRule::default());
+                }
+                if ui.button("保存").clicked() {
+                    self.auto_reply_input.rules.retain(|r| !r.reply.trim().is_empty());
+                    self.settings.auto_reply = self.auto_reply_input.clone();
+                    self.auto_reply_task = None;
+                    self.auto_reply_retry_at = None;
+                    match BiliClient::save_settings(&self.settings) {
+                        Ok(()) => self.notify.success("自动回复设置已保存"),
+                        Err(e) => self.notify.error(format!("保存配置失败: {}", e)),
+                    }
+                }
+            });
+        });
+        ui.add_space(10.0);
+
+        ui.group(|ui| {
+            ui.heading("网络");
+            ui.add_space(5.0);
+            self.network_diag_ui(ui, ctx);
+        });
+        ui.add_space(10.0);
+
+        ui.group(|ui| {
+            ui.heading("开发者模式");
+            ui.add_space(5.0);
+            if ui.checkbox(&mut self.settings.developer_mode, "在各操作下方显示最近一次请求的原始内容").changed() {
+                api_client::set_capture_exchanges(self.settings.developer_mode);
+                if let Err(e) = BiliClient::save_settings(&self.settings) {
+                    self.notify.error(format!("保存配置失败: {}", e));
+                }
+            }
+            ui.label("记录请求地址、参数（csrf 已打码）与原始响应，只保存在内存中，反馈问题时可复制附上");
+            if self.settings.developer_mode {
+                if self.privacy_mode {
+                    ui.label("隐私模式下不显示请求内容");
+                    return;
+                }
+                egui::CollapsingHeader::new("最近的请求").show(ui, |ui| {
+                    let exchanges = api_client::recent_exchanges();
+                    if exchanges.is_empty() {
+                        ui.label("开启后尚未发出请求");
+                    }
+                    for (i, exchange) in exchanges.iter().enumerate() {
+                        egui::CollapsingHeader::new(format!("{} {}", exchange.time_label(), exchange.url))
+                            .id_source(("exchange", i))
+                            .show(ui, |ui| exchange_detail(ui, exchange));
+                    }
+                });
+            }
+        });
+        ui.add_space(10.0);
+
+        egui::CollapsingHeader::new("接口指标").show(ui, |ui| {
+            let metrics = api_client::metrics_snapshot();
+            if metrics.is_empty() {
+                ui.label("本次启动尚未发出请求");
+            }
+            egui::Grid::new("metrics_grid").striped(true).show(ui, |ui| {
+                for title in ["接口", "次数", "平均耗时", "412", "失败"] {
+                    ui.strong(title);
+                }
+                ui.end_row();
+                for m in &metrics {
+                    ui.label(&m.endpoint);
+                    ui.label(m.calls.to_string());
+                    ui.label(format!("{} ms", m.avg_ms()));
+                    ui.label(m.blocked.to_string());
+                    ui.label(m.failures.to_string());
+                    ui.end_row();
+                }
+            });
+        });
+
+        egui::CollapsingHeader::new("查看当前 Cookie").show(ui, |ui| {
+            ui.add_enabled(!self.privacy_mode, egui::Checkbox::new(&mut self.show_cookie_values, "显示完整值"));
+            let cookies = self.client.current_cookies();
+            if cookies.is_empty() {
+                ui.label("当前没有 cookie");
+            }
+            egui::Grid::new("cookie_grid").striped(true).show(ui, |ui| {
+                for c in &cookies {
+                    ui.label(&c.name);
+                    ui.label(if self.privacy_mode {
+                        redact(&c.value)
+                    } else if self.show_cookie_values {
+                        c.value.clone()
+                    } else {
+                        c.masked_value()
+                    });
+                    ui.end_row();
+                }
+            });
+        });
+    }
+
+    fn live_page(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        match self.login_state {
+            LoginState::LoggedIn => {
+                if self.user_info.is_none() {
+                    let should_fetch = self.last_user_info_fetch.map(|t| t.elapsed() >= PollingConfig::interval(self.settings.polling.user_info_retry_secs)).unwrap_or(true);
+
+                    if should_fetch {
+                        self.last_user_info_fetch = Some(Instant::now());
+                        ui.label("正在获取用户信息...");
+                        ctx.request_repaint();
+                        
+                        match self.rt.block_on(self.client.get_self_info()) {
+                            Ok(info) => {
+                                self.consecutive_failures = 0;
+                                println!("获取到用户详细信息: {:?}", info);
+                                if info.live_room.room_id != 0 {
+                                    // 补充短号等 space 接口没有的信息，失败不影响主流程
+                                    match self.rt.block_on(self.client.get_room_info(info.live_room.room_id)) {
+                                        Ok(detail) => {
+                                            // 重启后基于服务端开播时间继续计时
+                                            if detail.live_status == 1 && detail.live_start > 0 {
+                                                self.live_started_at = Some(detail.live_start);
+                                            }
+                                            self.room_detail = Some(detail);
+                                        }
+                                        Err(e) => println!("获取直播间详情失败: {}", e),
+                                    }
+                                }
+                                self.server_title = Some(info.live_room.title.clone());
+                                self.room_info = Some(info.live_room.clone());
+                                self.user_info = Some(info);
+                                if let Ok(list) = self.rt.block_on(self.client.get_area_list()) {
+                                    println!("获取到分区列表，数量: {}", list.len());
+                                    self.area_list = list;
+                                    self.area_list_fetch_error = None;
+                                    self.relocate_area();
+                                } else {
+                                    let err_msg = "获取分区列表失败，请稍后重试".to_string();
+                                    println!("{}", err_msg);
+                                    self.area_list_fetch_error = Some(err_msg);
+                                }
+                                self.restore_relogin_draft();
+                                self.check_title_draft();
+                                self.refresh_members(ctx);
+                                // 强制重绘
+                                ctx.request_repaint();
+                            },
+                            Err(e) => {
+                                self.consecutive_failures += 1;
+                                println!("获取用户信息失败: {}", e);
+                                // 网络不可用时改用缓存数据；否则不要立即重置登录状态，让它在5秒后重试
+                                if api_client::error_kind(&e) == ErrorKind::Network && self.enter_offline() {
+                                    ctx.request_repaint();
+                                }
+                            }
+                        }
+                    } else {
+                        ui.label("获取用户信息失败，正在重试...");
+                    }
+                    if self.consecutive_failures >= DIAG_HINT_FAILURES {
+                        ui.label("多次请求失败，可能是网络问题：");
+                        self.network_diag_ui(ui, ctx);
+                    }
+                }
+
+                if let Some(user) = &self.user_info {
+                    responsive_row(ui, |ui| {
+                        if let Some(av) = self.images.get(&user.face, &self.rt, &self.client, ctx) {
+                            let avatar_size = 80.0;
+                            ui.image((av.id(), egui::vec2(avatar_size, avatar_size)));
+                            ui.add_space(10.0);
+                        }
+                        ui.vertical(|ui| {
+                            ui.heading(&user.name);
+                            let uid = user.mid.to_string();
+                            ui.label(format!("UID: {}", if self.privacy_mode { redact(&uid) } else { uid }));
+                            ui.label(format!("关注 {} · 粉丝 {}", user.following, user.follower));
+                        });
+                    });
+                    ui.add_space(10.0);
+                    
+                    if user.live_room.room_status == 0 || user.live_room.room_id == 0 {
+                        // 从未开通直播间：不能拿 room_id 0 去请求，引导开通
+                        let mut refetch = false;
+                        ui.group(|ui| {
+                            ui.label("你还没有直播间");
+                            ui.horizontal(|ui| {
+                                if ui.add_enabled(self.offline.is_none(), egui::Button::new("点此开通")).clicked() {
+                                    match self.rt.block_on(self.client.create_live_room()) {
+                                        Ok(room_id) => {
+                                            self.notify.success(format!("直播间已开通: {}", room_id));
+                                            refetch = true;
+                                        }
+                                        Err(e) => self.notify.error(format!("{}，可按开通指引在网页完成", e)),
+                                    }
+                                }
+                                if ui.button("重新检测").clicked() {
+                                    refetch = true;
+                                }
+                                ui.hyperlink_to("开通指引", CREATE_ROOM_GUIDE_URL);
+                            });
+                        });
+                        if refetch {
+                            self.user_info = None;
+                            self.room_info = None;
+                            self.room_detail = None;
+                            self.last_user_info_fetch = None;
+                            ctx.request_repaint();
+                        }
+                        return;
+                    }
+                    
+                    self.live_timer(ui, ctx);
+
+                    if self.consecutive_failures >= DIAG_HINT_FAILURES {
+                        ui.group(|ui| {
+                            ui.label("最近的操作多次失败，可能是网络问题：");
+                            self.network_diag_ui(ui, ctx);
+                        });
+                        ui.add_space(10.0);
+                    }
+
+                    if let Some(saved) = &self.offline {
+                        ui.colored_label(egui::Color32::from_rgb(230, 160, 0), format!("离线数据（更新于 {}），只读，网络恢复后自动刷新", saved));
+                        ui.add_space(5.0);
+                    }
+
+                    let action_ready = self.offline.is_none() && self.last_action.map(|t| t.elapsed() >= ACTION_COOLDOWN).unwrap_or(true);
+                    if !action_ready {
+                        ctx.request_repaint_after(ACTION_COOLDOWN);
+                    }
+
+                    // 切换推流协议要改设置，放到借用直播间信息的代码块之后
+                    let mut switch_protocol = None;
+                    if let Some(room) = &mut self.room_info {
+                        ui.group(|ui| {
+                            ui.heading("直播间信息");
+                            ui.add_space(5.0);
+                            
+                            ui.horizontal(|ui| {
+                                ui.label("标题: ");
+                                ui.add(egui::TextEdit::singleline(&mut room.title).desired_width(f32::INFINITY))
+                                    .on_hover_text("支持变量: {date}、{date:%m-%d}、{count}（第几场）、{weekday}（周几），保存或套用模板时替换");
+                            });
+                            let title_ctx = self.settings.title_context(domain::now_secs(), room.live_status == 1);
+                            let rendered = domain::render_title_template(&room.title, &title_ctx);
+                            if rendered != room.title {
+                                ui.weak(format!("保存后的标题: {}", rendered));
+                            }
+                            if let Some(draft) = &self.title_draft_prompt {
+                                let mut choice = None;
+                                ui.horizontal_wrapped(|ui| {
+                                    ui.colored_label(egui::Color32::YELLOW, format!("有未保存的标题草稿「{}」，是否恢复？", draft.title));
+                                    if ui.button("恢复").clicked() {
+                                        choice = Some(true);
+                                    }
+                                    if ui.button("丢弃").clicked() {
+                                        choice = Some(false);
+                                    }
+                                });
+                                match choice {
+                                    Some(true) => {
+                                        room.title = draft.title.clone();
+                                        self.title_draft_prompt = None;
+                                    }
+                                    Some(false) => {
+                                        self.saved_title_draft = None;
+                                        self.title_draft_prompt = None;
+                                        if let Err(e) = BiliClient::clear_title_draft() {
+                                            println!("删除标题草稿失败: {}", e);
+                                        }
+                                    }
+                                    None => {}
+                                }
+                            }
+
+                            if let Some(detail) = &mut self.room_detail {
+                                ui.collapsing("直播间简介", |ui| {
+                                    let Some(desc) = &mut self.description_edit else {
+                                        if detail.description.is_empty() {
+                                            ui.weak("（暂无简介）");
+                                        } else {
+                                            ui.label(&detail.description);
+                                        }
+                                        if ui.add_enabled(action_ready, egui::Button::new("编辑简介")).clicked() {
+                                            self.description_edit = Some(detail.description.clone());
+                                        }
+                                        return;
+                                    };
+                                    ui.add(egui::TextEdit::multiline(desc).desired_rows(4).desired_width(f32::INFINITY));
+                                    let check = domain::check_room_description(desc);
+                                    let counter = format!("{}/{}", desc.chars().count(), domain::ROOM_DESCRIPTION_MAX);
+                                    let mut done = false;
+                                    ui.horizontal(|ui| {
+                                        match &check {
+                                            Ok(()) => ui.weak(counter),
+                                            Err(reason) => ui.colored_label(egui::Color32::RED, reason),
+                                        };
+                                        let can_save = action_ready && check.is_ok() && *desc != detail.description;
+                                        if ui.add_enabled(can_save, egui::Button::new("保存简介")).clicked() {
+                                            let res = self.rt.block_on(self.client.update_room_description(room.room_id, desc));
+                                            self.last_action = Some(Instant::now());
+                                            match res {
+                                                Ok(()) => {
+                                                    detail.description = desc.clone();
+                                                    done = true;
+                                                    self.save_result.success("简介已提交，审核通过后对观众可见");
+                                                }
+                                                Err(e) => self.save_result.error(format!("更新简介失败: {}", e)),
+                                            }
+                                        }
+                                        if ui.button("取消").clicked() {
+                                            done = true;
+                                        }
+                                    });
+                                    if done {
+                                        self.description_edit = None;
+                                    }
+                                });
+                            }
+
+                            ui.label(format!("直播间号: {}", room.room_id));
+                            if let Some(short_id) = self.room_detail.as_ref().map(|d| d.short_id).filter(|id| *id != 0) {
+                                ui.label(format!("直播间短号: {}", short_id));
+                            }
+                            let room_url = format!("https://live.bilibili.com/{}", room.room_id);
+                            ui.horizontal(|ui| {
+                                ui.hyperlink_to(&room_url, &room_url);
+                                if ui.button("在浏览器打开").clicked() {
+                                    ctx.open_url(egui::OpenUrl::new_tab(&room_url));
+                                }
+                            });
+                            ui.label(format!("直播状态: {}", if room.live_status == 1 { "直播中" } else { "未开播" }));
+                            if let Some(status) = self.interact {
+                                ui.label(format!("互动状态: {}", status.label()));
+                            }
+                            
+                            // 落在放置区内的文件由 update_cover_drop 处理，这里只在拖动经过窗口时高亮放置区
+                            let hovering = ctx.input(|i| !i.raw.hovered_files.is_empty());
+                            let stroke = if hovering {
+                                egui::Stroke::new(2.0, egui::Color32::from_rgb(0, 174, 236))
+                            } else {
+                                ui.visuals().widgets.noninteractive.bg_stroke
+                            };
+                            let drop_zone = egui::Frame::group(ui.style()).stroke(stroke).show(ui, |ui| {
+                                if let Some(cv) = self.images.get(&room.cover, &self.rt, &self.client, ctx) {
+                                    let cover_width = ui.available_width().min(COVER_MAX_WIDTH);
+                                    let cover_height = cover_width * 9.0 / 16.0; // 16:9 比例
+                                    ui.image((cv.id(), egui::vec2(cover_width, cover_height)));
+                                }
+                                if self.cover_upload.is_some() {
+                                    ui.horizontal(|ui| {
+                                        ui.spinner();
+                                        ui.label("正在上传封面…");
+                                    });
+                                } else if hovering {
+                                    ui.colored_label(egui::Color32::from_rgb(0, 174, 236), "在此松手即裁成 16:9 并上传为封面");
+                                } else {
+                                    ui.weak("拖入 JPG / PNG 图片可更换封面");
+                                }
+                            });
+                            self.cover_drop_rect = Some(drop_zone.response.rect);
+                            ui.collapsing("封面裁剪 (16:9)", |ui| {
+                                ui.label("上传前先裁成 16:9，拖动选框调整范围，导出的图片与预览一致。");
+                                ui.horizontal_wrapped(|ui| {
+                                    ui.add(egui::TextEdit::singleline(&mut self.cover_editor.path).hint_text("图片路径").desired_width(ui.available_width().min(300.0)));
+                                    if ui.button("加载").clicked() {
+                                        if let Err(e) = self.cover_editor.load(ctx) {
+                                            self.notify.error(format!("加载图片失败: {}", e));
+                                        }
+                                    }
+                                    if ui.add_enabled(self.cover_editor.is_loaded(), egui::Button::new("导出")).clicked() {
+                                        match self.cover_editor.export() {
+                                            Ok(path) => self.notify.success(format!("已导出: {}", path.display())),
+                                            Err(e) => self.notify.error(format!("导出失败: {}", e)),
+                                        }
+                                    }
+                                    let can_upload = self.cover_editor.is_loaded() && action_ready && self.cover_upload.is_none();
+                                    if ui.add_enabled(can_upload, egui::Button::new("上传为封面")).clicked() {
+                                        match spawn_cover_upload(&self.rt, ctx, &self.client, &self.cover_editor, room.room_id) {
+                                            Ok(task) => self.cover_upload = Some(task),
+                                            Err(e) => self.notify.error(format!("上传封面失败: {}", e)),
+                                        }
+                                    }
+                                });
+                                self.cover_editor.preview(ui);
+                            });
+                            ui.collapsing("待机封面与开播预告", |ui| {
+                                ui.weak("B 站只有一个封面，没有单独的未开播封面或文案：两套封面保存在本地，切换时重新上传（需重新审核）；预告写入公告，或作为简介的第一行。");
+                                let saved_slots = *self.cover_slots_saved.get_or_insert_with(|| CoverSlot::ALL.map(BiliClient::has_cover_slot));
+                                for slot in CoverSlot::ALL {
+                                    ui.horizontal_wrapped(|ui| {
+                                        let saved = saved_slots[slot as usize];
+                                        ui.label(format!("{}: {}", slot.label(), if saved { "已保存" } else { "未设置" }));
+                                        if ui.add_enabled(self.cover_editor.is_loaded(), egui::Button::new("保存裁剪结果")).clicked() {
+                                            match self.cover_editor.encode_jpeg().and_then(|image| Ok(BiliClient::save_cover_slot(slot, &image)?)) {
+                                                Ok(()) => {
+                                                    self.cover_slots_saved = None;
+                                                    self.notify.success(format!("已保存{}", slot.label()));
+                                                }
+                                                Err(e) => self.notify.error(format!("保存{}失败: {}", slot.label(), e)),
+                                            }
+                                        }
+                                        if ui.add_enabled(saved && action_ready && self.cover_upload.is_none(), egui::Button::new("立即换上")).clicked() {
+                                            let client = self.client.clone();
+                                            let room_id = room.room_id;
+                                            self.cover_upload = Some(BgTask::spawn(&self.rt, ctx, move |token| async move {
+                                                api_client::cancellable(&token, client.apply_cover_slot(room_id, slot)).await
+                                            }));
+                                        }
+                                    });
+                                }
+                                ui.horizontal_wrapped(|ui| {
+                                    ui.label("预告:");
+                                    ui.add(egui::TextEdit::singleline(&mut self.settings.standby.notice).hint_text("下次开播：周五 20:00").desired_width(ui.available_width().min(240.0)));
+                                    let mut target = self.settings.standby.target;
+                                    egui::ComboBox::from_id_source("standby_notice_target")
+                                        .selected_text(target.label())
+                                        .show_ui(ui, |ui| {
+                                            for t in NoticeTarget::ALL {
+                                                ui.selectable_value(&mut target, t, t.label());
+                                            }
+                                        });
+                                    let changed = target != self.settings.standby.target;
+                                    self.settings.standby.target = target;
+                                    let notice = self.settings.standby.notice.trim().to_string();
+                                    let write = ui.add_enabled(action_ready && !notice.is_empty(), egui::Button::new(format!("写入{}", target.label()))).clicked();
+                                    if write {
+                                        let res = self.rt.block_on(self.client.write_standby_notice(room.room_id, target, &notice));
+                                        self.last_action = Some(Instant::now());
+                                        match res {
+                                            Ok(()) => {
+                                                if let Some(detail) = self.room_detail.as_mut().filter(|_| target == NoticeTarget::Description) {
+                                                    detail.description = domain::with_standby_notice(&detail.description, &notice);
+                                                }
+                                                self.save_result.success(format!("预告已写入{}", target.label()));
+                                            }
+                                            Err(e) => self.save_result.error(format!("写入预告失败: {}", e)),
+                                        }
+                                    }
+                                    if changed || write {
+                                        if let Err(e) = BiliClient::save_settings(&self.settings) {
+                                            self.notify.error(format!("保存配置失败: {}", e));
+                                        }
+                                    }
+                                });
+                                if ui.checkbox(&mut self.settings.standby.auto_switch, "关播后自动换上待机封面并写入预告，开播后换回开播封面").changed() {
+                                    if let Err(e) = BiliClient::save_settings(&self.settings) {
+                                        self.notify.error(format!("保存配置失败: {}", e));
+                                    }
+                                }
+                                if self.standby_task.is_some() {
+                                    ui.horizontal(|ui| {
+                                        ui.spinner();
+                                        ui.label("正在切换封面与预告…");
+                                    });
+                                }
+                            });
+                            
+                            ui.add_space(10.0);
+                            let area_fetch_failed = self.area_list_fetch_error.is_some();
+                            let area_blocked = self.selected_area_id
+                                .and_then(|id| self.area_check.get(&id))
+                                .map(|reason| reason.is_some())
+                                .unwrap_or(false);
+                            let starting = self.start_task.is_some();
+                            ui.add_enabled_ui(action_ready && !starting && !area_fetch_failed && (room.live_status == 1 || !area_blocked), |ui| {
+                                let mut confirmed = false;
+                                if self.confirm_toggle_live {
+                                    ui.colored_label(egui::Color32::YELLOW, if room.live_status == 1 { "确定要关播吗？" } else { "确定要开播吗？" });
+                                    ui.horizontal(|ui| {
+                                        if ui.button("确认").clicked() {
+                                            self.confirm_toggle_live = false;
+                                            confirmed = true;
+                                        }
+                                        if ui.button("取消").clicked() {
+                                            self.confirm_toggle_live = false;
+                                        }
+                                    });
+                                }
+                                if ui.add_sized([200.0, 30.0], egui::Button::new(
+                                    if room.live_status == 1 { "停止直播" } else { "开始直播" }
+                                )).on_hover_text("Ctrl+L").clicked() || confirmed {
+                                    if room.live_status == 1 {
+                                        // stop live
+                                        match self.rt.block_on(self.client.stop_live(room.room_id)) {
+                                            Ok(stopped) => {
+                                                room.live_status = 0;
+                                                self.stream = None;
+                                                self.stream_lines = StreamLines::default();
+                                                self.live_wait_task = None;
+                                                self.live_started_at = None;
+                                                self.consecutive_failures = 0;
+                                                if stopped {
+                                                    self.live_result.success("已关播");
+                                                    spawn_event(&self.rt, &self.client, LiveEvent::new("live_stop", room.room_id, ""));
+                                                    self.standby_task = spawn_standby_switch(&self.rt, ctx, &self.client, &self.settings.standby, room.room_id, true);
+                                                } else {
+                                                    self.live_result.success("直播间已不在直播");
+                                                }
+                                            }
+                                            Err(e) => {
+                                                self.live_result.error(format!("关播失败: {}", e));
+                                                self.consecutive_failures += 1;
+                                                spawn_event(&self.rt, &self.client, LiveEvent::new("error", room.room_id, format!("关播失败: {}", e)));
+                                            }
+                                        }
+                                        self.last_action = Some(Instant::now());
+                                    } else {
+                                        if let Some(area_id) = self.selected_area_id {
+                                            self.start_task = Some(PendingStart::spawn(
+                                                &self.rt, ctx, &self.client, room.room_id, area_id, "开播成功".to_string(), format!("分区 {}", area_id),
+                                            ));
+                                        } else {
+                                            self.live_result.warning("请先选择分区");
+                                        }
+                                    }
+                                }
+                            });
+                            if let Some(pending) = &self.start_task {
+                                match &pending.qr {
+                                    Some(qr) => {
+                                        ui.label("开播需要人脸认证，请使用 B 站 App 扫码，认证通过后将自动开播");
+                                        let side = Self::qr_side(qr, ctx, ui.available_width());
+                                        ui.image((qr.id(), egui::vec2(side, side)));
+                                    }
+                                    None => {
+                                        ui.horizontal(|ui| {
+                                            ui.spinner();
+                                            ui.label("正在开播…");
+                                        });
+                                    }
+                                }
+                                if ui.button("取消开播").clicked() {
+                                    self.start_task = None;
+                                    self.live_result.warning("已取消开播");
+                                }
+                            }
+                            if self.live_wait_task.is_some() {
+                                ui.horizontal(|ui| {
+                                    ui.spinner();
+                                    ui.label("等待推流连接…");
+                                });
+                            }
+                            self.live_result.show(ui);
+                            if self.settings.developer_mode && !self.privacy_mode {
+                                exchange_ui(ui, "live_exchange", &["Room/startLive", "Room/stopLive"]);
+                            }
+                            if area_fetch_failed {
+                                ui.colored_label(egui::Color32::RED, self.area_list_fetch_error.as_deref().unwrap_or(""));
+                            }
+                        });
+                        
+                        ui.add_space(10.0);
+                        
+                        if let Some(line) = self.stream.as_ref().filter(|_| room.live_status == 1) {
+                            ui.group(|ui| {
+                                ui.heading("推流信息");
+                                ui.add_space(5.0);
+
+                                ui.horizontal(|ui| {
+                                    ui.label("协议:");
+                                    for p in StreamProtocol::ALL {
+                                        let shown = self.stream_lines.get(p);
+                                        let resp = ui
+                                            .add_enabled(shown.is_some(), egui::RadioButton::new(shown == Some(line), p.label()))
+                                            .on_disabled_hover_text("本场未返回该协议的推流地址");
+                                        if resp.clicked() && shown != Some(line) {
+                                            switch_protocol = Some(p);
+                                        }
+                                    }
+                                });
+
+                                ui.horizontal(|ui| {
+                                    ui.radio_value(&mut self.copy_merged, false, "拆分（OBS 等）");
+                                    ui.radio_value(&mut self.copy_merged, true, "合并为完整地址");
+                                });
+                                let rows: Vec<(&str, String)> = if self.copy_merged {
+                                    vec![("推流地址:", line.full_url())]
+                                } else {
+                                    vec![("服务器:", line.server().to_string()), ("串流密钥:", line.stream_key().to_string())]
+                                };
+                                for (label, value) in rows {
+                                    let shown = if self.privacy_mode { redact(&value) } else { value.clone() };
+                                    ui.label(label);
+                                    ui.horizontal(|ui| {
+                                        let field = ui.add(egui::TextEdit::singleline(&mut shown.as_str()).desired_width(f32::INFINITY));
+                                        // 聚焦时 Ctrl+C 复制完整内容，不必先全选；隐私模式下不接管
+                                        let copy_shortcut = field.has_focus() && !self.privacy_mode && shortcuts::copy_requested(ctx);
+                                        if ui.add_enabled(!self.privacy_mode, egui::Button::new("复制")).clicked() || copy_shortcut {
+                                            ctx.output_mut(|o| o.copied_text = value.clone());
+                                        }
+                                    });
+                                }
+
+                                ui.collapsing("ffmpeg 推流", |ui| {
+                                    let config = &mut self.settings.ffmpeg;
+                                    ui.horizontal_wrapped(|ui| {
+                                        ui.label("输入源:");
+                                        ui.add(egui::TextEdit::singleline(&mut config.input).hint_text("视频文件或设备").desired_width(ui.available_width().min(320.0)));
+                                    });
+                                    ui.horizontal_wrapped(|ui| {
+                                        ui.label("输入参数:");
+                                        ui.add(egui::TextEdit::singleline(&mut config.input_args).desired_width(ui.available_width().min(320.0)));
+                                    });
+                                    ui.label("编码参数:");
+                                    ui.add(egui::TextEdit::multiline(&mut config.encode_args).desired_rows(2).desired_width(ui.available_width().min(400.0)));
+                                    let shell = ShellKind::current();
+                                    let command = config.command(line.server(), line.stream_key(), "", shell);
+                                    let input = config.input.clone();
+                                    ui.horizontal(|ui| {
+                                        let mut action = None;
+                                        if ui.add_enabled(!self.privacy_mode, egui::Button::new("复制 ffmpeg 命令")).clicked() {
+                                            ctx.output_mut(|o| o.copied_text = command.clone());
+                                            action = Some(Ok("已复制 ffmpeg 命令".to_string()));
+                                        }
+                                        if ui.button(format!("导出 .{} 脚本", shell.extension())).clicked() {
+                                            action = Some(
+                                                export_push_script(&shell.script(&command), shell, &input)
+                                                    .map(|path| format!("推流脚本已导出到 {}（含推流密钥，请勿外传）", path.display())),
+                                            );
+                                        }
+                                        match action {
+                                            Some(Ok(msg)) => {
+                                                if let Err(e) = BiliClient::save_settings(&self.settings) {
+                                                    println!("保存配置失败: {}", e);
+                                                }
+                                                self.notify.success(msg);
+                                            }
+                                            Some(Err(e)) => self.notify.error(format!("导出脚本失败: {}", e)),
+                                            None => {}
+                                        }
+                                    });
+                                });
+                            });
+                            ui.add_space(10.0);
+                        }
+
+                        ui.group(|ui| {
+                            ui.heading("发送弹幕");
+                            ui.add_space(5.0);
+                            if let Some((room_id, res)) = self.emoticon_task.as_ref().and_then(|(id, t)| Some((*id, t.poll()?))) {
+                                self.emoticon_task = None;
+                                match res {
+                                    Ok(list) => self.emoticons = Some((room_id, list)),
+                                    Err(e) => self.notify.error(format!("获取表情失败: {}", e)),
+                                }
+                            }
+                            if let Some((room_id, res)) = self.danmaku_config_task.as_ref().and_then(|(id, t)| Some((*id, t.poll()?))) {
+                                self.danmaku_config_task = None;
+                                let config = res.unwrap_or_else(|e| {
+                                    self.notify.warning(format!("获取弹幕设置失败，只能发送白色弹幕: {}", e));
+                                    DanmakuConfig::default()
+                                });
+                                if config.check("", self.danmaku_color).is_err() {
+                                    self.danmaku_color = domain::DEFAULT_DANMAKU_COLOR;
+                                }
+                                self.danmaku_config = Some((room_id, config));
+                            }
+                            let config_loaded = self.danmaku_config.as_ref().filter(|(id, _)| *id == room.room_id).map(|(_, c)| c.clone());
+                            if config_loaded.is_none() && self.offline.is_none() && self.danmaku_config_task.as_ref().map(|(id, _)| *id != room.room_id).unwrap_or(true) {
+                                let client = self.client.clone();
+                                let room_id = room.room_id;
+                                self.danmaku_config_task = Some((room_id, BgTask::spawn(&self.rt, ctx, move |token| async move {
+                                    api_client::cancellable(&token, client.get_danmaku_config(room_id)).await
+                                })));
+                            }
+                            let config = config_loaded.unwrap_or_default();
+                            let mut emoticon = None;
+                            ui.horizontal_wrapped(|ui| {
+                                let resp = ui.add(
+                                    egui::TextEdit::singleline(&mut self.danmaku_input)
+                                        .hint_text("弹幕内容")
+                                        .char_limit(config.max_length)
+                                        .desired_width(240.0),
+                                );
+                                let enter = resp.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                                ui.weak(format!("{}/{}", self.danmaku_input.trim().chars().count(), config.max_length));
+                                if config.usable_colors().count() > 1 {
+                                    let color32 = |c: u32| egui::Color32::from_rgb((c >> 16) as u8, (c >> 8) as u8, c as u8);
+                                    let current = config.colors.iter().find(|c| c.color == self.danmaku_color).map(|c| c.name.clone()).unwrap_or_else(|| "白色".into());
+                                    egui::ComboBox::from_id_source("danmaku_color")
+                                        .selected_text(egui::RichText::new(current).color(color32(self.danmaku_color)))
+                                        .show_ui(ui, |ui| {
+                                            for c in &config.colors {
+                                                ui.add_enabled_ui(c.usable, |ui| {
+                                                    ui.selectable_value(&mut self.danmaku_color, c.color, egui::RichText::new(&c.name).color(color32(c.color)))
+                                                        .on_disabled_hover_text("需要更高的粉丝勋章等级或舰长身份");
+                                                });
+                                            }
+                                        });
+                                }
+                                let checked = config.check(self.danmaku_input.trim(), self.danmaku_color);
+                                if let Err(reason) = &checked {
+                                    ui.colored_label(egui::Color32::RED, reason);
+                                }
+                                let can_send = action_ready && !self.danmaku_input.trim().is_empty() && checked.is_ok();
+                                if ui.add_enabled(can_send, egui::Button::new("发送")).clicked() || (enter && can_send) {
+                                    let msg = self.danmaku_input.trim().to_string();
+                                    self.last_action = Some(Instant::now());
+                                    match self.rt.block_on(self.client.send_colored_danmaku(room.room_id, &msg, self.danmaku_color)) {
+                                        Ok(()) => self.danmaku_input.clear(),
+                                        Err(e) => self.notify.error(format!("发送弹幕失败: {}", e)),
+                                    }
+                                }
+                                ui.add_enabled_ui(action_ready, |ui| {
+                                    ui.menu_button("表情", |ui| {
+                                        ui.set_max_width(320.0);
+                                        let loaded = self.emoticons.as_ref().filter(|(id, _)| *id == room.room_id);
+                                        let Some((_, list)) = loaded else {
+                                            if self.emoticon_task.as_ref().map(|(id, _)| *id != room.room_id).unwrap_or(true) {
+                                                let client = self.client.clone();
+                                                let room_id = room.room_id;
+                                                self.emoticon_task = Some((room_id, BgTask::spawn(&self.rt, ctx, move |token| async move {
+                                                    api_client::cancellable(&token, client.get_emoticons(room_id)).await
+                                                })));
+                                            }
+                                            ui.spinner();
+                                            return;
+                                        };
+                                        if list.is_empty() {
+                                            ui.label("该直播间没有可用表情");
+                                            return;
+                                        }
+                                        egui::ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+                                            let mut packages: Vec<&str> = Vec::new();
+                                            for e in list {
+                                                if !packages.contains(&e.package.as_str()) {
+                                                    packages.push(&e.package);
+                                                }
+                                            }
+                                            for package in packages {
+                                                ui.label(package);
+                                                ui.horizontal_wrapped(|ui| {
+                                                    for e in list.iter().filter(|e| e.package == package) {
+                                                        let button = match self.images.get(&e.url, &self.rt, &self.client, ctx) {
+                                                            Some(tex) => egui::Button::image(egui::Image::new(&tex).fit_to_exact_size(egui::vec2(28.0, 28.0))),
+                                                            None => egui::Button::new(&e.name),
+                                                        };
+                                                        let resp = ui
+                                                            .add_enabled(e.usable, button)
+                                                            .on_hover_text(&e.name)
+                                                            .on_disabled_hover_text(format!("{}（当前账号不可用）", e.name));
+                                                        if resp.clicked() {
+                                                            emoticon = Some(e.unique.clone());
+                                                            ui.close_menu();
+                                                        }
+                                                    }
+                                                });
+                                            }
+                                        });
+                                    });
+                                });
+                            });
+                            if let Some(unique) = emoticon {
+                                self.last_action = Some(Instant::now());
+                                if let Err(e) = self.rt.block_on(self.client.send_emoticon(room.room_id, &unique)) {
+                                    self.notify.error(format!("发送表情失败: {}", e));
+                                }
+                            }
+                        });
+                        ui.add_space(10.0);
+
+                        ui.group(|ui| {
+                            ui.heading("房管操作");
+                            ui.add_space(5.0);
+                            ui.horizontal_wrapped(|ui| {
+                                ui.label("UID");
+                                let uid = id_input(ui, &mut self.mod_uid_input, IdKind::Uid, 120.0).map(|uid| uid as u64);
+                                let mut action = None;
+                                if ui.add_enabled(action_ready && uid.is_some(), egui::Button::new("禁言（本场）")).clicked() {
+                                    action = uid.map(|uid| ModAction::Silence { room_id: room.room_id, uid });
+                                }
+                                if ui.add_enabled(action_ready && uid.is_some(), egui::Button::new("任命房管")).clicked() {
+                                    action = uid.map(|uid| ModAction::AppointAdmin { room_id: room.room_id, uid });
+                                }
+                                let Some(action) = action else { return };
+                                let res = self.rt.block_on(async {
+                                    match action {
+                                        ModAction::Silence { room_id, uid } => self.client.add_silent_user(room_id, uid, 0).await,
+                                        ModAction::AppointAdmin { uid, .. } => self.client.appoint_room_admin(uid).await,
+                                    }
+                                });
+                                self.last_action = Some(Instant::now());
+                                match res {
+                                    Ok(()) => {
+                                        self.notify.success(format!("已{}", action.label()));
+                                        self.undo_stack.push(action, domain::now_secs());
+                                        self.mod_uid_input.clear();
+                                    }
+                                    Err(e) => self.notify.error(format!("{}失败: {}", action.label(), e)),
+                                }
+                            });
+                            let now = domain::now_secs();
+                            if let Some((action, remaining)) = self.undo_stack.latest(now) {
+                                let label = action.label();
+                                ui.horizontal(|ui| {
+                                    ui.label(format!("刚刚{}", label));
+                                    if ui.add_enabled(action_ready, egui::Button::new(format!("撤销（{} 秒）", remaining))).clicked() {
+                                        if let Some(action) = self.undo_stack.pop(now) {
+                                            self.last_action = Some(Instant::now());
+                                            match self.rt.block_on(self.client.undo_mod_action(&action)) {
+                                                Ok(()) => self.notify.success(format!("已撤销{}", label)),
+                                                Err(e) => self.notify.error(format!("撤销{}失败: {}", label, e)),
+                                            }
+                                        }
+                                    }
+                                });
+                                ctx.request_repaint_after(Duration::from_secs(1));
+                            }
+                        });
+                        ui.add_space(10.0);
 
-        for y in 0..module_count {
-            for x in 0..module_count {
-                if code[(x, y)] == Color::Dark {
-                    let start_x = (x + margin_modules) * scale;
-                    let start_y = (y + margin_modules) * scale;
-                    for dy in 0..scale {
-                        for dx in 0..scale {
-                            let idx = ((start_y + dy) * img_side + (start_x + dx)) * 4;
-                            pixels[idx..idx + 4].copy_from_slice(&[0, 0, 0, 255]);
+                        ui.group(|ui| {
+                            ui.heading("开播模板");
+                            ui.add_space(5.0);
+
+                            ui.horizontal_wrapped(|ui| {
+                                egui::ComboBox::from_id_source("template_combo")
+                                    .width(200.0)
+                                    .selected_text(self.selected_template.as_deref().unwrap_or("选择模板"))
+                                    .show_ui(ui, |ui| {
+                                        for t in &self.settings.templates {
+                                            ui.selectable_value(&mut self.selected_template, Some(t.name.clone()), &t.name);
+                                        }
+                                    });
+                                // 自动开播等直播间与分区列表都就绪后，走与"套用并开播"相同的流程
+                                let autostart = !self.area_list.is_empty() && self.autostart_template.is_some();
+                                if autostart {
+                                    let name = self.autostart_template.take().unwrap_or_default();
+                                    if room.live_status == 1 {
+                                        println!("直播间已在直播，跳过自动开播");
+                                    } else if self.settings.template(&name).is_none() {
+                                        self.live_result.error(format!("自动开播失败: 模板「{}」不存在", name));
+                                    } else {
+                                        self.selected_template = Some(name);
+                                    }
+                                }
+                                let tpl = self.selected_template.as_deref().and_then(|n| self.settings.template(n)).cloned();
+                                let title_ctx = self.settings.title_context(domain::now_secs(), room.live_status == 1);
+                                let tpl_title = tpl.as_ref().map(|t| domain::render_title_template(&t.title, &title_ctx));
+                                ui.add_enabled_ui(tpl.is_some(), |ui| {
+                                    let apply = ui.add_enabled(action_ready, egui::Button::new("套用")).clicked();
+                                    let apply_and_start = ui
+                                        .add_enabled(action_ready && room.live_status != 1 && self.start_task.is_none(), egui::Button::new("套用并开播"))
+                                        .clicked()
+                                        || (autostart && room.live_status != 1 && tpl.is_some());
+                                    if let Some(title) = &tpl_title {
+                                        ui.weak(format!("标题: {}", title));
+                                    }
+                                    if ui.button("删除").clicked() {
+                                        if let Some(name) = self.selected_template.take() {
+                                            self.settings.remove_template(&name);
+                                            if let Err(e) = BiliClient::save_settings(&self.settings) {
+                                                self.notify.error(format!("保存模板失败: {}", e));
+                                            }
+                                        }
+                                    }
+                                    let Some(tpl) = tpl.filter(|_| apply || apply_and_start) else { return };
+                                    if let Err(e) = domain::validate_ids(Some(room.room_id), Some(tpl.area_id)) {
+                                        self.live_result.error(format!("模板「{}」配置有误: {}", tpl.name, e));
+                                        return;
+                                    }
+                                    if !self.area_sel.select_area(&self.area_list, tpl.area_id) {
+                                        self.live_result.error(format!("模板「{}」的分区 {} 已失效，请重新选择分区后覆盖保存", tpl.name, tpl.area_id));
+                                        return;
+                                    }
+                                    let title = tpl_title.clone().unwrap_or_default();
+                                    room.title = title.clone();
+                                    self.selected_area_id = Some(tpl.area_id);
+                                    let res = self.rt.block_on(async {
+                                        self.client.update_room_info(room.room_id, Some(&title), Some(tpl.area_id), None).await?;
+                                        if let Some(news) = &tpl.room_news {
+                                            self.client.update_room_news(room.room_id, news).await?;
+                                        }
+                                        anyhow::Ok(())
+                                    });
+                                    self.last_action = Some(Instant::now());
+                                    if res.is_ok() {
+                                        self.room_sync = RoomSync::spawn(&self.rt, ctx, &self.client, room.room_id, title.clone(), Some(tpl.area_id));
+                                        self.server_title = Some(title);
+                                    }
+                                    match res {
+                                        Ok(()) if apply_and_start => {
+                                            self.start_task = Some(PendingStart::spawn(
+                                                &self.rt, ctx, &self.client, room.room_id, tpl.area_id,
+                                                format!("已套用模板「{}」并开播", tpl.name), format!("模板 {}", tpl.name),
+                                            ));
+                                        }
+                                        Ok(()) => {
+                                            self.live_result.success(format!("已套用模板「{}」", tpl.name));
+                                            record_area_use(&mut self.settings, tpl.area_id);
+                                        }
+                                        Err(e) => {
+                                            self.live_result.error(format!("套用模板失败: {}", e));
+                                            if apply_and_start {
+                                                spawn_event(&self.rt, &self.client, LiveEvent::new("error", room.room_id, format!("套用模板开播失败: {}", e)));
+                                            }
+                                        }
+                                    }
+                                });
+                            });
+
+                            ui.horizontal_wrapped(|ui| {
+                                ui.add(egui::TextEdit::singleline(&mut self.new_template_name).hint_text("模板名").desired_width(150.0));
+                                let can_save = !self.new_template_name.trim().is_empty() && self.selected_area_id.is_some();
+                                if ui.add_enabled(can_save, egui::Button::new("将当前标题和分区保存为模板")).clicked() {
+                                    let name = self.new_template_name.trim().to_string();
+                                    let room_news = self.settings.template(&name).and_then(|t| t.room_news.clone());
+                                    self.settings.upsert_template(LiveTemplate {
+                                        name: name.clone(),
+                                        title: room.title.clone(),
+                                        area_id: self.selected_area_id.unwrap_or_default(),
+                                        room_news,
+                                    });
+                                    match BiliClient::save_settings(&self.settings) {
+                                        Ok(()) => {
+                                            self.selected_template = Some(name);
+                                            self.new_template_name.clear();
+                                            self.notify.success("模板已保存");
+                                        }
+                                        Err(e) => self.notify.error(format!("保存模板失败: {}", e)),
+                                    }
+                                }
+                            });
+                        });
+                        ui.add_space(10.0);
+
+                        if !self.area_list.is_empty() {
+                            ui.group(|ui| {
+                                ui.heading("分区设置");
+                                ui.add_space(5.0);
+
+                                let common: Vec<(i64, String)> = self
+                                    .settings
+                                    .top_used_areas(COMMON_AREA_COUNT)
+                                    .into_iter()
+                                    .filter_map(|id| {
+                                        let (pi, ci) = domain::find_area(&self.area_list, id)?;
+                                        Some((id, self.area_list[pi].children[ci].name.clone()))
+                                    })
+                                    .collect();
+                                if !common.is_empty() {
+                                    ui.horizontal_wrapped(|ui| {
+                                        ui.label("常用:");
+                                        for (id, name) in common {
+                                            if ui.selectable_label(self.selected_area_id == Some(id), name).clicked() {
+                                                self.area_sel.select_area(&self.area_list, id);
+                                            }
+                                        }
+                                    });
+                                }
+                                
+                                ui.horizontal_wrapped(|ui| {
+                                    ui.label("按 ID:");
+                                    let id = id_input(ui, &mut self.area_id_input, IdKind::Area, 80.0);
+                                    let found = id.filter(|id| domain::find_area(&self.area_list, *id).is_some());
+                                    if ui.add_enabled(found.is_some(), egui::Button::new("选择")).clicked() {
+                                        if let Some(id) = found {
+                                            self.area_sel.select_area(&self.area_list, id);
+                                            self.area_id_input.clear();
+                                        }
+                                    }
+                                    if let Some(id) = id.filter(|_| found.is_none()) {
+                                        ui.colored_label(egui::Color32::RED, domain::leaf_area_error(&self.area_list, id).unwrap_or_default());
+                                    }
+                                });
+
+                                ui.horizontal_wrapped(|ui| {
+                                    // 下标只经由 AreaSelection 修改，切换父分区时子分区随之重置
+                                    self.area_sel.clamp(&self.area_list);
+                                    let mut parent = self.area_sel.parent();
+                                    egui::ComboBox::from_label("父分区")
+                                        .width(200.0)
+                                        .selected_text(self.area_list[parent].name.as_str())
+                                        .show_ui(ui, |ui| {
+                                            for (idx, p) in self.area_list.iter().enumerate() {
+                                                ui.horizontal(|ui| {
+                                                    match self.images.get(&p.pic, &self.rt, &self.client, ctx) {
+                                                        Some(icon) => {
+                                                            ui.add(egui::Image::new(&icon).fit_to_exact_size(egui::vec2(16.0, 16.0)));
+                                                        }
+                                                        None => ui.add_space(16.0),
+                                                    }
+                                                    ui.selectable_value(&mut parent, idx, p.name.as_str());
+                                                });
+                                            }
+                                        });
+                                    self.area_sel.set_parent(&self.area_list, parent);
+
+                                    ui.add_space(20.0);
+
+                                    let children = &self.area_list[self.area_sel.parent()].children;
+                                    if !children.is_empty() {
+                                        let mut child = self.area_sel.child();
+                                        egui::ComboBox::from_label("子分区")
+                                            .width(200.0)
+                                            .selected_text(children[child].name.as_str())
+                                            .show_ui(ui, |ui| {
+                                                for (idx, c) in children.iter().enumerate() {
+                                                    ui.selectable_value(&mut child, idx, c.name.as_str());
+                                                }
+                                            });
+                                        self.area_sel.set_child(&self.area_list, child);
+                                    }
+                                    self.selected_area_id = self.area_sel.area_id(&self.area_list);
+                                });
+                                let reason = self.selected_area_id.and_then(|id| self.area_check.get(&id).cloned().flatten());
+                                if let Some(reason) = reason {
+                                    ui.colored_label(egui::Color32::RED, reason);
+                                }
+                            });
+                            ui.add_space(10.0);
+                        }
+                        
+                        let area_fetch_failed = self.area_list_fetch_error.is_some();
+                        ui.add_enabled_ui(action_ready && !area_fetch_failed, |ui|{
+                            let save_shortcut = self.shortcuts.save && ui.is_enabled();
+                            if ui.add_sized([200.0, 30.0], egui::Button::new("保存设置")).on_hover_text("Ctrl+Enter").clicked() || save_shortcut {
+                                let area_id_opt = self.selected_area_id;
+                                let title_ctx = self.settings.title_context(domain::now_secs(), room.live_status == 1);
+                                let title_clone = domain::render_title_template(&room.title, &title_ctx);
+                                let res = self.rt.block_on(self.client.update_room_info(room.room_id, Some(&title_clone), area_id_opt, None));
+                                self.last_action = Some(Instant::now());
+                                if let (Ok(_), Some(area_id)) = (&res, area_id_opt) {
+                                    record_area_use(&mut self.settings, area_id);
+                                }
+                                if res.is_ok() {
+                                    room.title = title_clone.clone();
+                                    self.room_sync = RoomSync::spawn(&self.rt, ctx, &self.client, room.room_id, title_clone.clone(), area_id_opt);
+                                    self.server_title = Some(title_clone);
+                                }
+                                match res {
+                                    Ok(Some(audit)) => {
+                                        if audit.audit_title_status != 0 {
+                                            self.save_result.warning(format!("标题审核状态: {} - {}", audit.audit_title_status, audit.audit_title_reason));
+                                        } else {
+                                            self.save_result.success("更新成功");
+                                        }
+                                    }
+                                    Ok(None) => self.save_result.success("更新成功"),
+                                    Err(e) => self.save_result.error(format!("更新失败: {}", e)),
+                                }
+                            }
+                        });
+                        self.save_result.show(ui);
+                        if self.settings.developer_mode && !self.privacy_mode {
+                            exchange_ui(ui, "save_exchange", &["Room/update", "UpdatePreLiveInfo", "upload/web/image"]);
+                        }
+                        if area_fetch_failed {
+                            ui.colored_label(egui::Color32::RED, self.area_list_fetch_error.as_deref().unwrap_or(""));
                         }
                     }
+                    if let Some(protocol) = switch_protocol {
+                        self.select_stream_protocol(protocol);
+                    }
                 }
             }
-        }
+            LoginState::NeedQrCode => {
+                // 后台按配置的间隔轮询扫码结果，离开登录界面时任务随 qr_poll 一起取消
+                if let (Some(qr), None) = (&self.qr_info, &self.qr_poll) {
+                    let client = self.client.clone();
+                    let qr = qr.clone();
+                    let interval = PollingConfig::interval(self.settings.polling.qr_poll_secs);
+                    self.qr_poll = Some(BgTask::spawn(&self.rt, ctx, move |token| async move {
+                        client.wait_qr_login(&qr, interval, &token).await
+                    }));
+                }
+                if let Some(res) = self.qr_poll.as_ref().and_then(|t| t.poll()) {
+                    self.qr_poll = None;
+                    match res {
+                        Ok(LoginState::LoggedIn) => {
+                            self.login_state = LoginState::LoggedIn;
+                            self.qr_texture = None;
+                            self.qr_info = None;
+                            ctx.request_repaint();
+                            println!("登录成功，状态已更新为LoggedIn");
+                        }
+                        Ok(LoginState::NeedQrCode) => {}
+                        Err(e) if e.downcast_ref::<BiliError>().is_some_and(BiliError::is_cancelled) => {}
+                        Err(e) => println!("扫码轮询失败: {}", e),
+                    }
+                }
 
-        let img = egui::ColorImage::from_rgba_unmultiplied([img_side, img_side], &pixels);
-        ctx.load_texture("qr", img, Default::default())
-    }
+                ui.vertical_centered(|ui| {
+                    ui.heading("请扫码登录");
+                    ui.add_space(20.0);
+                    
+                    let retry_due = self.qr_retry_at.map(|at| Instant::now() >= at).unwrap_or(true);
+                    if self.qr_texture.is_none() && self.qr_fetch.is_none() && retry_due {
+                        // 首次进入或到了重试时间，后台获取二维码；地址不属于官方域名时 fetch_qr_code 直接报错，不会渲染
+                        let client = self.client.clone();
+                        self.qr_fetch = Some(BgTask::spawn(&self.rt, ctx, move |token| async move {
+                            api_client::cancellable(&token, client.fetch_qr_code()).await
+                        }));
+                    }
+                    if let Some(res) = self.qr_fetch.as_ref().and_then(|t| t.poll()) {
+                        self.qr_fetch = None;
+                        match res {
+                            Ok(qr) => {
+                                self.qr_texture = Some(Self::load_qr_texture(&qr.url, ctx));
+                                self.qr_info = Some(qr);
+                                self.qr_error = None;
+                                self.qr_retry_at = None;
+                            }
+                            Err(e) => {
+                                println!("获取登录二维码失败: {:#}", e);
+                                self.qr_error = Some(qr_error_message(&e));
+                                self.qr_retry_at = Some(Instant::now() + PollingConfig::interval(self.settings.polling.qr_retry_secs));
+                            }
+                        }
+                    }
+                    if self.qr_fetch.is_some() && self.qr_texture.is_none() {
+                        ui.spinner();
+                    }
+                    if let Some(err) = &self.qr_error {
+                        ui.colored_label(egui::Color32::RED, err);
+                        let remaining = self.qr_retry_at.map(|at| at.saturating_duration_since(Instant::now())).unwrap_or_default();
+                        ui.label(format!("{} 秒后自动重试", remaining.as_secs() + 1));
+                        if ui.button("立即重试").clicked() {
+                            self.qr_retry_at = None;
+                        }
+                        ctx.request_repaint_after(Duration::from_millis(500));
+                    }
+                    
+                    if let Some(tex) = &self.qr_texture {
+                        ui.add_space(10.0);
+                        // 窄窗口下按可用宽度等比缩小
+                        let side = Self::qr_side(tex, ctx, ui.available_width());
+                        ui.image((tex.id(), egui::vec2(side, side)));
+                        ui.add_space(20.0);
+                    }
+                    
+                    if ui.add_sized([200.0, 30.0], egui::Button::new("手动检查扫码状态")).clicked() {
+                        if let Some(qr) = &self.qr_info {
+                            match self.rt.block_on(self.client.poll_qr_login(qr)) {
+                                Ok(LoginState::LoggedIn) => {
+                                    self.login_state = LoginState::LoggedIn;
+                                    self.qr_texture = None;
+                                    self.qr_info = None;
+                                    ctx.request_repaint();
+                                    self.qr_poll = None;
+                                    println!("手动检查：登录成功，状态已更新为LoggedIn");
+                                }
+                                Ok(LoginState::NeedQrCode) => {
+                                    self.notify.warning("尚未扫码或已过期，请稍后重试/刷新。");
+                                }
+                                Err(e) => {
+                                    self.notify.error(format!("登录失败: {}", e));
+                                }
+                            }
+                        }
+                    }
 
-    fn bytes_to_texture(bytes: &[u8], ctx: &egui::Context) -> Option<egui::TextureHandle> {
-        if let Ok(img) = ImageReader::new(std::io::Cursor::new(bytes)).with_guessed_format().unwrap().decode() {
-            let size = [img.width() as usize, img.height() as usize];
-            let rgba = img.into_rgba8();
-            let pixels = rgba.into_raw();
-            let img = egui::ColorImage::from_rgba_unmultiplied(size, &pixels);
-            Some(ctx.load_texture("net_img", img, Default::default()))
-        } else { None }
+                    ui.add_space(20.0);
+                    ui.collapsing("从其它工具导入 cookie", |ui| {
+                        ui.label("支持 bilibili-api 等工具的 JSON、浏览器导出的 cookies.txt 或 Cookie 字符串，可直接把文件拖进窗口");
+                        if let Some(path) = ctx.input(|i| i.raw.dropped_files.iter().find_map(|f| f.path.clone())) {
+                            self.cookie_file_input = path.display().to_string();
+                        }
+                        ui.horizontal(|ui| {
+                            ui.add(egui::TextEdit::singleline(&mut self.cookie_file_input).hint_text("cookie 文件路径").desired_width(260.0));
+                            let path = self.cookie_file_input.trim().to_string();
+                            if ui.add_enabled(!path.is_empty(), egui::Button::new("导入")).clicked() {
+                                self.import_cookie_file(&path);
+                            }
+                        });
+                    });
+                });
+            }
+        }
     }
 
-    fn fetch_texture(rt: &Runtime, client: &reqwest::Client, url: &str, ctx: &egui::Context) -> Option<egui::TextureHandle> {
-        let fut = async {
-            let resp = client.get(url).send().await.ok()?;
-            let bytes = resp.bytes().await.ok()?;
-            Some(bytes.to_vec())
-        };
-        if let Some(bytes_vec) = rt.block_on(fut) {
-            let bytes = &bytes_vec;
-            Self::bytes_to_texture(bytes, ctx)
-        } else { None }
+    /// 导入其它工具的 cookie 文件，登录态有效才保存并直接进入主界面，失效时原有登录信息不变
+    fn import_cookie_file(&mut self, path: &str) {
+        match self.rt.block_on(self.client.import_from_file(std::path::Path::new(path))) {
+            Ok(format) => {
+                self.login_state = LoginState::LoggedIn;
+                self.qr_texture = None;
+                self.qr_info = None;
+                self.qr_poll = None;
+                self.cookie_file_input.clear();
+                self.notify.success(format!("已按 {} 格式导入 cookie", format));
+            }
+            Err(e) => self.notify.error(format!("导入 cookie 失败: {}", e)),
+        }
     }
+
 }
 
 impl Default for BiliApp {
     fn default() -> Self {
         let client = BiliClient::new();
+        let settings = BiliClient::load_settings();
+        api_client::set_capture_exchanges(settings.developer_mode);
         let rt = Runtime::new().expect("failed to create tokio runtime");
         
-        let initial_state = rt.block_on(client.check_login_state()).unwrap_or(LoginState::NeedQrCode);
-        
-        Self {
+        // 断网时有缓存数据就先以只读模式进入主界面，而不是卡在扫码
+        let (initial_state, start_offline) = match rt.block_on(client.check_login_state()) {
+            Ok(state) => (state, false),
+            Err(e) if api_client::error_kind(&e) == ErrorKind::Network && BiliClient::load_offline_snapshot().is_some_and(|s| s.user.is_some()) => {
+                println!("网络不可用，使用离线数据: {}", e);
+                (LoginState::LoggedIn, true)
+            }
+            Err(_) => (LoginState::NeedQrCode, false),
+        };
+
+        let mut app = Self {
             client,
             rt,
             login_state: initial_state,
             user_info: None,
             room_info: None,
+            room_detail: None,
             qr_texture: None,
             qr_info: None,
-            avatar_texture: None,
-            cover_texture: None,
+            qr_error: None,
+            qr_retry_at: None,
+            qr_fetch: None,
+            images: ImageCache::default(),
+            cover_editor: CoverEditor::default(),
+            cover_upload: None,
+            cover_drop_rect: None,
+            standby_task: None,
+            cover_slots_saved: None,
             area_list: Vec::new(),
-            selected_parent: 0,
-            selected_child: 0,
+            area_sel: AreaSelection::default(),
             selected_area_id: None,
-            push_addr: String::new(),
-            push_key: String::new(),
-            last_qr_poll: None,
+            stream: None,
+            stream_lines: StreamLines::default(),
+            copy_merged: false,
+            qr_poll: None,
             last_user_info_fetch: None,
+            login_task: None,
+            last_login_check: Some(Instant::now()),
             area_list_fetch_error: None,
-            version: env!("CARGO_PKG_VERSION").to_string(),
+            version: domain::version_line(env!("CARGO_PKG_VERSION")),
+            tab: Tab::Live,
+            confirm_reset: false,
+            shortcuts: shortcuts::Pressed::default(),
+            confirm_toggle_live: false,
+            reset_keep_config: true,
+            show_cookie_values: false,
+            privacy_mode: false,
+            webhook_input: settings.webhook_url.clone().unwrap_or_default(),
+            proxy_input: settings.proxy.clone().unwrap_or_default(),
+            cookie_file_input: String::new(),
+            proxy_rules_input: domain::format_proxy_rules(&settings.proxy_rules),
+            obs_input: settings.obs_text.clone(),
+            obs_task: None,
+            status_input: settings.status_output.clone(),
+            status_task: None,
+            auto_reply_input: settings.auto_reply.clone(),
+            auto_reply_task: None,
+            start_task: None,
+            live_wait_task: None,
+            room_sync: None,
+            offline: None,
+            offline_probe: None,
+            last_offline_probe: None,
+            mod_uid_input: String::new(),
+            danmaku_input: String::new(),
+            emoticons: None,
+            emoticon_task: None,
+            danmaku_config: None,
+            danmaku_config_task: None,
+            danmaku_color: domain::DEFAULT_DANMAKU_COLOR,
+            area_id_input: String::new(),
+            applied_zoom: 1.0,
+            relogin_draft: None,
+            income_task: None,
+            members: None,
+            members_task: None,
+            members_error: None,
+            members_cache_loaded: false,
+            last_members_refresh: None,
+            income: None,
+            income_error: None,
+            show_income: false,
+            activities: None,
+            activities_task: None,
+            server_title: None,
+            saved_title_draft: None,
+            last_title_draft_save: None,
+            title_draft_prompt: None,
+            description_edit: None,
+            undo_stack: UndoStack::new(UNDO_WINDOW_SECS),
+            auto_reply_retry_at: None,
+            stats_task: None,
+            stats_retry_at: None,
+            stats_ring: Arc::new(Mutex::new(StatsRing::new(STATS_RING_CAP))),
+            stats_session: 0,
+            interaction_task: None,
+            interaction_retry_at: None,
+            interaction: Arc::default(),
+            interaction_session: None,
+            settings,
+            selected_template: None,
+            new_template_name: String::new(),
+            area_check: HashMap::new(),
+            area_check_task: None,
+            notify: Notifications::default(),
+            live_result: StatusLine::default(),
+            save_result: StatusLine::default(),
+            last_action: None,
+            consecutive_failures: 0,
+            diag_task: None,
+            network_diag: None,
+            live_started_at: None,
+            duration_reminded: false,
+            interact: None,
+            interact_task: None,
+            last_interact_fetch: None,
+            autostart_enabled: autostart::is_autostart_enabled(),
+            start_minimized: false,
+            autostart_template: None,
+        };
+        if start_offline {
+            app.enter_offline();
         }
+        app
     }
 }
 
 impl eframe::App for BiliApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, UI_STATE_KEY, &self.ui_state());
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut Frame) {
+        self.handle_shortcuts(ctx);
+        // Ctrl +/- 快捷键改变的缩放也写回配置
+        if ctx.zoom_factor() != self.applied_zoom {
+            self.settings.ui_scale = ctx.zoom_factor().clamp(*UI_SCALE_RANGE.start(), *UI_SCALE_RANGE.end());
+            self.apply_zoom(ctx);
+        }
+        // 离开扫码界面时取消挂起的轮询，避免结果回来后污染新状态
+        if self.tab != Tab::Live || !matches!(self.login_state, LoginState::NeedQrCode) {
+            self.qr_poll = None;
+        }
+        if std::mem::take(&mut self.start_minimized) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(true));
+        }
+
+        egui::TopBottomPanel::top("top_bar").show(ctx, |ui| self.top_bar(ui, ctx));
+        self.notify.show(ctx);
+
         egui::CentralPanel::default()
             .frame(egui::Frame::default().inner_margin(egui::Margin::ZERO))
             .show(ctx, |ui| {
@@ -126,299 +3100,192 @@ impl eframe::App for BiliApp {
                 frame.show(ui, |ui|{
                     ui.heading("B站直播工具");
                     ui.add_space(10.0);
-                    
-                    ui.label(format!("当前登录状态: {:?}", self.login_state));
-                    ui.add_space(5.0);
-                    
-                    match self.login_state {
-                        LoginState::LoggedIn => {
-                            if self.user_info.is_none() {
-                                let should_fetch = self.last_user_info_fetch.map_or(true, |t| t.elapsed() >= Duration::from_secs(5));
-
-                                if should_fetch {
-                                    self.last_user_info_fetch = Some(Instant::now());
-                                    ui.label("正在获取用户信息...");
-                                    ctx.request_repaint();
-                                    
-                                    match self.rt.block_on(self.client.get_self_info()) {
-                                        Ok(info) => {
-                                            println!("获取到用户详细信息: {:?}", info);
-                                            self.avatar_texture = Self::fetch_texture(&self.rt, self.client.client(), &info.face, ctx);
-                                            if info.live_room.room_status == 1 {
-                                                self.cover_texture = Self::fetch_texture(&self.rt, self.client.client(), &info.live_room.cover, ctx);
-                                            }
-                                            self.room_info = Some(info.live_room.clone());
-                                            self.user_info = Some(info);
-                                            if let Ok(list) = self.rt.block_on(self.client.get_area_list()) {
-                                                println!("获取到分区列表，数量: {}", list.len());
-                                                self.area_list = list;
-                                                self.area_list_fetch_error = None;
-                                            } else {
-                                                let err_msg = "获取分区列表失败，请稍后重试".to_string();
-                                                println!("{}", err_msg);
-                                                self.area_list_fetch_error = Some(err_msg);
-                                            }
-                                            // 强制重绘
-                                            ctx.request_repaint();
-                                        },
-                                        Err(e) => {
-                                            println!("获取用户信息失败: {}", e);
-                                            // 不要立即重置登录状态，让它在5秒后重试
-                                        }
-                                    }
-                                } else {
-                                    ui.label("获取用户信息失败，正在重试...");
-                                }
-                            }
 
-                            if let Some(user) = &self.user_info {
-                                ui.horizontal(|ui| {
-                                    if let Some(av) = &self.avatar_texture {
-                                        let avatar_size = 80.0;
-                                        ui.image((av.id(), egui::vec2(avatar_size, avatar_size)));
-                                        ui.add_space(10.0);
-                                    }
-                                    ui.vertical(|ui| {
-                                        ui.heading(&user.name);
-                                        ui.label(format!("UID: {}", user.mid));
-                                    });
-                                });
-                                ui.add_space(10.0);
-                                
-                                if user.live_room.room_status == 0 {
-                                    let elapsed = self.last_user_info_fetch.map_or(Duration::from_secs(5), |t| t.elapsed());
-
-                                    if elapsed >= Duration::from_secs(5) {
-                                        self.user_info = None;
-                                        self.room_info = None;
-                                        self.last_user_info_fetch = None;
-                                        ctx.request_repaint();
-                                    } else {
-                                        let remaining = Duration::from_secs(5) - elapsed;
-                                        ui.colored_label(egui::Color32::YELLOW, format!("未能获取直播间信息，{:.0}秒后自动重试...", remaining.as_secs_f32().ceil()));
-                                        ctx.request_repaint_after(Duration::from_secs(1));
-                                    }
-                                    return;
-                                }
-                                
-                                if let Some(room) = &mut self.room_info {
-                                    ui.group(|ui| {
-                                        ui.heading("直播间信息");
-                                        ui.add_space(5.0);
-                                        
-                                        ui.horizontal(|ui| {
-                                            ui.label("标题: ");
-                                            ui.add(egui::TextEdit::singleline(&mut room.title).desired_width(f32::INFINITY));
-                                        });
-                                        
-                                        ui.label(format!("直播间号: {}", room.room_id));
-                                        ui.label(format!("直播状态: {}", if room.live_status == 1 { "直播中" } else { "未开播" }));
-                                        
-                                        if let Some(cv) = &self.cover_texture {
-                                            let cover_height = 180.0;
-                                            let cover_width = cover_height * 16.0 / 9.0; // 16:9 比例
-                                            ui.image((cv.id(), egui::vec2(cover_width, cover_height)));
-                                        }
-                                        
-                                        ui.add_space(10.0);
-                                        let area_fetch_failed = self.area_list_fetch_error.is_some();
-                                        ui.add_enabled_ui(!area_fetch_failed, |ui| {
-                                            if ui.add_sized([200.0, 30.0], egui::Button::new(
-                                                if room.live_status == 1 { "停止直播" } else { "开始直播" }
-                                            )).clicked() {
-                                                if room.live_status == 1 {
-                                                    // stop live
-                                                    match self.rt.block_on(self.client.stop_live(room.room_id)) {
-                                                        Ok(()) => {
-                                                            room.live_status = 0;
-                                                            self.push_addr.clear();
-                                                            self.push_key.clear();
-                                                        }
-                                                        Err(e) => {
-                                                            ui.colored_label(egui::Color32::RED, format!("关播失败: {}", e));
-                                                        }
-                                                    }
-                                                } else {
-                                                    if let Some(area_id) = self.selected_area_id {
-                                                        match self.rt.block_on(self.client.start_live(room.room_id, area_id)) {
-                                                            Ok((addr, key)) => {
-                                                                room.live_status = 1;
-                                                                self.push_addr = addr;
-                                                                self.push_key = key;
-                                                            }
-                                                            Err(e) => {
-                                                                ui.colored_label(egui::Color32::RED, format!("开播失败: {}", e));
-                                                            }
-                                                        }
-                                                    } else {
-                                                        ui.colored_label(egui::Color32::YELLOW, "请先选择分区");
-                                                    }
-                                                }
-                                            }
-                                        });
-                                        if area_fetch_failed {
-                                            ui.colored_label(egui::Color32::RED, self.area_list_fetch_error.as_deref().unwrap_or(""));
-                                        }
-                                    });
-                                    
-                                    ui.add_space(10.0);
-                                    
-                                    if room.live_status == 1 && !self.push_addr.is_empty() {
-                                        ui.group(|ui| {
-                                            ui.heading("推流信息");
-                                            ui.add_space(5.0);
-                                            
-                                            ui.label("推流地址:");
-                                            ui.horizontal(|ui| {
-                                                ui.add(egui::TextEdit::singleline(&mut self.push_addr).desired_width(f32::INFINITY));
-                                                if ui.button("复制").clicked() {
-                                                    ctx.output_mut(|o| o.copied_text = self.push_addr.clone());
-                                                }
-                                            });
-                                            
-                                            ui.label("推流密钥:");
-                                            ui.horizontal(|ui| {
-                                                ui.add(egui::TextEdit::singleline(&mut self.push_key).desired_width(f32::INFINITY));
-                                                if ui.button("复制").clicked() {
-                                                    ctx.output_mut(|o| o.copied_text = self.push_key.clone());
-                                                }
-                                            });
-                                        });
-                                        ui.add_space(10.0);
-                                    }
-                                    
-                                    if !self.area_list.is_empty() {
-                                        ui.group(|ui| {
-                                            ui.heading("分区设置");
-                                            ui.add_space(5.0);
-                                            
-                                            ui.horizontal(|ui| {
-                                                // parent combo
-                                                let parent_names: Vec<_> = self.area_list.iter().map(|p| p.name.as_str()).collect();
-                                                egui::ComboBox::from_label("父分区")
-                                                    .width(200.0)
-                                                    .selected_text(parent_names[self.selected_parent])
-                                                    .show_ui(ui, |ui| {
-                                                        for (idx, p) in parent_names.iter().enumerate() {
-                                                            ui.selectable_value(&mut self.selected_parent, idx, *p);
-                                                        }
-                                                    });
-                                                    
-                                                ui.add_space(20.0);
-                                                
-                                                // ensure selected_child within bounds
-                                                if self.selected_parent >= self.area_list.len() { self.selected_parent = 0; }
-                                                let child_list = &self.area_list[self.selected_parent].children;
-                                                if child_list.is_empty() { return; }
-                                                if self.selected_child >= child_list.len() { self.selected_child = 0; }
-                                                let child_names: Vec<_> = child_list.iter().map(|c| c.name.as_str()).collect();
-                                                egui::ComboBox::from_label("子分区")
-                                                    .width(200.0)
-                                                    .selected_text(child_names[self.selected_child])
-                                                    .show_ui(ui, |ui| {
-                                                        for (idx, c) in child_names.iter().enumerate() {
-                                                            ui.selectable_value(&mut self.selected_child, idx, *c);
-                                                        }
-                                                    });
-                                                self.selected_area_id = Some(child_list[self.selected_child].id);
-                                            });
-                                        });
-                                        ui.add_space(10.0);
-                                    }
-                                    
-                                    let area_fetch_failed = self.area_list_fetch_error.is_some();
-                                    ui.add_enabled_ui(!area_fetch_failed, |ui|{
-                                        if ui.add_sized([200.0, 30.0], egui::Button::new("保存设置")).clicked() {
-                                            let area_id_opt = self.selected_area_id;
-                                            let title_clone = room.title.clone();
-                                            let res = self.rt.block_on(self.client.update_room_info(room.room_id, Some(&title_clone), area_id_opt));
-                                            match res {
-                                                Ok(Some(audit)) => {
-                                                    if audit.audit_title_status != 0 {
-                                                        ui.colored_label(egui::Color32::YELLOW, format!("标题审核状态: {} - {}", audit.audit_title_status, audit.audit_title_reason));
-                                                    } else {
-                                                        ui.colored_label(egui::Color32::GREEN, "更新成功");
-                                                    }
-                                                }
-                                                Ok(None) => { ui.colored_label(egui::Color32::GREEN, "更新成功"); }
-                                                Err(e) => { ui.colored_label(egui::Color32::RED, format!("更新失败: {}", e)); }
-                                            }
-                                        }
-                                    });
-                                    if area_fetch_failed {
-                                        ui.colored_label(egui::Color32::RED, self.area_list_fetch_error.as_deref().unwrap_or(""));
-                                    }
-                                }
-                            }
-                        }
-                        LoginState::NeedQrCode => {
-                            // 自动轮询扫码结果：每 2 秒检查一次
-                            if let Some(qr) = &self.qr_info {
-                                let should_poll = self.last_qr_poll.map_or(true, |t| t.elapsed() >= Duration::from_secs(2));
-                                if should_poll {
-                                    self.last_qr_poll = Some(Instant::now());
-                                    if let Ok(LoginState::LoggedIn) = self.rt.block_on(self.client.poll_qr_login(qr)) {
-                                        self.login_state = LoginState::LoggedIn;
-                                        self.qr_texture = None;
-                                        self.qr_info = None;
-                                        ctx.request_repaint();
-                                        println!("登录成功，状态已更新为LoggedIn");
-                                    }
-                                }
-                            }
+                    ui.horizontal(|ui| {
+                        ui.selectable_value(&mut self.tab, Tab::Live, "直播");
+                        ui.selectable_value(&mut self.tab, Tab::Data, "数据");
+                        ui.selectable_value(&mut self.tab, Tab::Members, "成员");
+                        ui.selectable_value(&mut self.tab, Tab::Settings, "设置");
+                        ui.selectable_value(&mut self.tab, Tab::About, "关于");
+                    });
+                    ui.separator();
+                    ui.add_space(5.0);
 
-                            ui.vertical_centered(|ui| {
-                                ui.heading("请扫码登录");
-                                ui.add_space(20.0);
-                                
-                                if self.qr_texture.is_none() {
-                                    // 首次进入，获取二维码
-                                    if let Ok(qr) = self.rt.block_on(self.client.fetch_qr_code()) {
-                                        self.qr_texture = Some(Self::load_qr_texture(&qr.url, ctx));
-                                        self.qr_info = Some(qr);
-                                    }
-                                }
-                                
-                                if let Some(tex) = &self.qr_texture {
-                                    ui.add_space(10.0);
-                                    ui.image((tex.id(), tex.size_vec2()));
-                                    ui.add_space(20.0);
-                                }
-                                
-                                if ui.add_sized([200.0, 30.0], egui::Button::new("手动检查扫码状态")).clicked() {
-                                    if let Some(qr) = &self.qr_info {
-                                        match self.rt.block_on(self.client.poll_qr_login(qr)) {
-                                            Ok(LoginState::LoggedIn) => {
-                                                self.login_state = LoginState::LoggedIn;
-                                                self.qr_texture = None;
-                                                self.qr_info = None;
-                                                ctx.request_repaint();
-                                                println!("手动检查：登录成功，状态已更新为LoggedIn");
-                                            }
-                                            Ok(LoginState::NeedQrCode) => {
-                                                ui.colored_label(egui::Color32::YELLOW, "尚未扫码或已过期，请稍后重试/刷新。");
-                                            }
-                                            Err(e) => {
-                                                ui.colored_label(egui::Color32::RED, format!("登录失败: {}", e));
-                                            }
-                                        }
-                                    }
-                                }
-                            });
-                        }
+                    match self.tab {
+                        Tab::Live => self.live_page(ui, ctx),
+                        Tab::Data => self.data_page(ui),
+                        Tab::Members => self.members_page(ui),
+                        Tab::Settings => self.settings_page(ui, ctx),
+                        Tab::About => about::about_page(ui),
                     }
 
                     ui.add_space(10.0);
                     ui.separator();
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                        ui.label(format!("v{}", self.version));
+                        ui.label(&self.version);
                         ui.add_space(10.0);
                         ui.hyperlink_to("源代码", "https://github.com/BlueIceChannel/Bili-Live-Tool");
                     });
                 });
             });
         });
+
+        self.update_area_check(ctx);
+        self.update_login_state(ctx);
+        self.update_obs_text(ctx);
+        self.update_status_output(ctx);
+        self.update_start_live(ctx);
+        self.update_live_wait();
+        self.update_room_sync();
+        self.update_cover_drop(ctx);
+        self.update_cover_upload();
+        self.update_standby_switch();
+        self.update_offline(ctx);
+        self.update_title_draft(ctx);
+        self.update_auto_reply(ctx);
+        self.update_stats_collector(ctx);
+        self.update_interaction_stats(ctx);
+        self.update_interact_status(ctx);
+    }
+}
+
+/// DragValue 拖动或输入期间只改内存中的值，松手或结束输入时才算提交，避免每帧写配置
+fn drag_committed(resp: &egui::Response) -> bool {
+    resp.drag_stopped() || resp.lost_focus()
+}
+
+/// 宽度足够时横排，否则竖排
+fn responsive_row<R>(ui: &mut egui::Ui, add_contents: impl FnOnce(&mut egui::Ui) -> R) -> R {
+    if ui.available_width() >= NARROW_WIDTH {
+        ui.horizontal(add_contents).inner
+    } else {
+        ui.vertical(add_contents).inner
+    }
+}
+
+/// 只接受合法 id 的输入框：内容非法时红框并在悬停时给出原因，返回解析出的 id。空输入不标红
+fn id_input(ui: &mut egui::Ui, text: &mut String, kind: IdKind, width: f32) -> Option<i64> {
+    let parsed = domain::parse_id(kind, text);
+    let invalid = parsed.as_ref().err().filter(|_| !text.trim().is_empty());
+    ui.scope(|ui| {
+        if invalid.is_some() {
+            let stroke = egui::Stroke::new(1.5, egui::Color32::RED);
+            let visuals = ui.visuals_mut();
+            visuals.widgets.inactive.bg_stroke = stroke;
+            visuals.widgets.hovered.bg_stroke = stroke;
+            visuals.selection.stroke = stroke;
+        }
+        let resp = ui.add(egui::TextEdit::singleline(text).hint_text(kind.label()).desired_width(width));
+        if let Some(e) = invalid {
+            resp.on_hover_text(e);
+        }
+    });
+    parsed.ok()
+}
+
+/// 累计一次分区使用并保存配置
+fn record_area_use(settings: &mut Settings, area_id: i64) {
+    settings.record_area_use(area_id, domain::now_secs());
+    if let Err(e) = BiliClient::save_settings(settings) {
+        println!("保存分区使用统计失败: {}", e);
+    }
+}
+
+/// 开发者模式下在操作下方展示相关接口最近一次的请求；`fragments` 为接口路径片段，取其中最近的一次
+fn exchange_ui(ui: &mut egui::Ui, id: &str, fragments: &[&str]) {
+    let Some(exchange) = fragments.iter().filter_map(|f| api_client::last_exchange(f)).max_by_key(|e| e.time) else { return };
+    egui::CollapsingHeader::new("原始请求").id_source(id).show(ui, |ui| exchange_detail(ui, &exchange));
+}
+
+/// 一次请求的地址、参数与原始响应，可复制
+fn exchange_detail(ui: &mut egui::Ui, exchange: &ApiExchange) {
+    let status = exchange.status.map(|s| format!("HTTP {}", s)).unwrap_or_else(|| "请求失败".to_string());
+    ui.label(format!("{} · {}", exchange.time_label(), status));
+    ui.monospace(format!("{} {}", exchange.method, exchange.url));
+    for (k, v) in &exchange.params {
+        ui.monospace(format!("{}={}", k, v));
+    }
+    egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+        ui.add(egui::TextEdit::multiline(&mut exchange.response.as_str()).code_editor().desired_width(f32::INFINITY));
+    });
+    if ui.button("复制").clicked() {
+        ui.output_mut(|o| o.copied_text = exchange.to_report());
+    }
+}
+
+/// 获取二维码失败的提示，区分网络失败、风控与接口异常
+fn qr_error_message(err: &anyhow::Error) -> String {
+    match api_client::error_kind(err.as_ref()) {
+        ErrorKind::Network => format!("网络连接失败，无法获取二维码，请检查网络或代理设置（{}）", err),
+        ErrorKind::Blocked => format!("获取二维码的请求被风控拦截，请稍后再试（{}）", err),
+        _ => format!("登录接口异常，获取二维码失败: {}", err),
+    }
+}
+
+/// 把封面编辑器当前的 16:9 选区编码后在后台上传为封面；拖入图片与"上传为封面"按钮共用
+fn spawn_cover_upload(rt: &Runtime, ctx: &egui::Context, client: &BiliClient, editor: &CoverEditor, room_id: i64) -> Result<BgTask<String>> {
+    let image = editor.encode_jpeg()?;
+    let client = client.clone();
+    Ok(BgTask::spawn(rt, ctx, move |token| async move { api_client::cancellable(&token, client.upload_cover(room_id, &image)).await }))
+}
+
+/// 开播 / 关播后在后台切换封面与预告，未开启自动切换时返回 None
+fn spawn_standby_switch(
+    rt: &Runtime,
+    ctx: &egui::Context,
+    client: &BiliClient,
+    config: &StandbyConfig,
+    room_id: i64,
+    standby: bool,
+) -> Option<BgTask<(&'static str, Option<String>)>> {
+    if !config.auto_switch {
+        return None;
+    }
+    let (client, config) = (client.clone(), config.clone());
+    Some(BgTask::spawn(rt, ctx, move |token| async move {
+        if standby {
+            api_client::cancellable(&token, client.switch_to_standby(room_id, &config)).await.map(|cover| ("待机", cover))
+        } else {
+            api_client::cancellable(&token, client.switch_to_live(room_id, &config)).await.map(|cover| ("开播", cover))
+        }
+    }))
+}
+
+/// 在后台推送 webhook 事件，不阻塞界面
+fn spawn_event(rt: &Runtime, client: &BiliClient, event: LiveEvent) {
+    let client = client.clone();
+    rt.spawn(async move { client.emit_event(&event).await });
+}
+
+/// 用系统文件管理器打开目录
+fn open_folder(dir: &std::path::Path) -> std::io::Result<()> {
+    let program = if cfg!(windows) {
+        "explorer"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    std::process::Command::new(program).arg(dir).spawn().map(|_| ())
+}
+
+/// 推流脚本写到输入文件所在目录，输入不是本地文件时写到用户主目录。脚本含推流密钥，Unix 下只给当前用户权限
+fn export_push_script(script: &str, shell: ShellKind, input: &str) -> Result<std::path::PathBuf> {
+    let input = std::path::Path::new(input.trim());
+    let dir = match input.parent().filter(|_| input.is_file()) {
+        Some(dir) => dir.to_path_buf(),
+        None => directories::BaseDirs::new()
+            .map(|b| b.home_dir().to_path_buf())
+            .ok_or_else(|| anyhow::anyhow!("无法定位用户目录"))?,
+    };
+    let path = dir.join(format!("bili_push.{}", shell.extension()));
+    std::fs::write(&path, script)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o700))?;
     }
+    Ok(path)
 }
 
 fn load_icon() -> egui::viewport::IconData {
@@ -439,8 +3306,13 @@ fn load_icon() -> egui::viewport::IconData {
 }
 
 fn main() -> Result<()> {
+    let args = GuiArgs::try_parse().unwrap_or_else(|e| {
+        println!("启动参数无效，按默认方式启动: {}", e);
+        GuiArgs::default()
+    });
     let mut native_options = eframe::NativeOptions::default();
     native_options.viewport.inner_size = Some(egui::vec2(800.0, 600.0));
+    native_options.viewport.min_inner_size = Some(egui::vec2(360.0, 480.0));
     native_options.viewport.icon = Some(Arc::new(load_icon()));
     
     // 使用默认渲染器
@@ -500,7 +3372,11 @@ fn main() -> Result<()> {
             style.visuals = visuals; // Set the customized visuals to the style
             cc.egui_ctx.set_style(style); // Set the full style
             
-            Box::new(BiliApp::default())
+            let ui_state = cc.storage.and_then(|s| eframe::get_value::<UiState>(s, UI_STATE_KEY)).unwrap_or_default();
+            let mut app = BiliApp::default().restore_ui_state(ui_state).with_args(args);
+            app.applied_zoom = app.settings.ui_scale.clamp(*UI_SCALE_RANGE.start(), *UI_SCALE_RANGE.end());
+            cc.egui_ctx.set_zoom_factor(app.applied_zoom);
+            Box::new(app)
             // --- END NEW LOGIC ---
         }),
     );
@@ -0,0 +1,117 @@
+//! 操作反馈：右上角堆叠、几秒后淡出的通知，以及固定在按钮旁、保留到下次操作的结果行。
+
+use eframe::egui;
+use std::time::{Duration, Instant};
+
+const LIFETIME: Duration = Duration::from_secs(4);
+const FADE: Duration = Duration::from_secs(1);
+const STATUS_LIFETIME: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Copy)]
+pub enum Level {
+    Success,
+    Warning,
+    Error,
+}
+
+impl Level {
+    fn color(self) -> egui::Color32 {
+        match self {
+            Level::Success => egui::Color32::GREEN,
+            Level::Warning => egui::Color32::YELLOW,
+            Level::Error => egui::Color32::RED,
+        }
+    }
+}
+
+struct Notice {
+    level: Level,
+    text: String,
+    created: Instant,
+}
+
+#[derive(Default)]
+pub struct Notifications {
+    items: Vec<Notice>,
+}
+
+impl Notifications {
+    pub fn push(&mut self, level: Level, text: impl Into<String>) {
+        self.items.push(Notice { level, text: text.into(), created: Instant::now() });
+    }
+
+    pub fn success(&mut self, text: impl Into<String>) {
+        self.push(Level::Success, text);
+    }
+
+    pub fn warning(&mut self, text: impl Into<String>) {
+        self.push(Level::Warning, text);
+    }
+
+    pub fn error(&mut self, text: impl Into<String>) {
+        self.push(Level::Error, text);
+    }
+
+    /// 在窗口右上角渲染未过期的通知，过期的随之移除
+    pub fn show(&mut self, ctx: &egui::Context) {
+        self.items.retain(|n| n.created.elapsed() < LIFETIME);
+        if self.items.is_empty() {
+            return;
+        }
+        egui::Area::new(egui::Id::new("notifications"))
+            .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-12.0, 48.0))
+            .order(egui::Order::Foreground)
+            .interactable(false)
+            .show(ctx, |ui| {
+                for n in &self.items {
+                    let remaining = LIFETIME.saturating_sub(n.created.elapsed());
+                    let alpha = (remaining.as_secs_f32() / FADE.as_secs_f32()).min(1.0);
+                    ui.set_opacity(alpha);
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.colored_label(n.level.color(), &n.text);
+                    });
+                    ui.add_space(4.0);
+                }
+            });
+        ctx.request_repaint_after(Duration::from_millis(50));
+    }
+}
+
+/// 关键操作（开播、保存设置等）的结果，显示在按钮旁，直到下次操作或超时
+#[derive(Default)]
+pub struct StatusLine {
+    current: Option<Notice>,
+}
+
+impl StatusLine {
+    pub fn set(&mut self, level: Level, text: impl Into<String>) {
+        self.current = Some(Notice { level, text: text.into(), created: Instant::now() });
+    }
+
+    pub fn success(&mut self, text: impl Into<String>) {
+        self.set(Level::Success, text);
+    }
+
+    pub fn warning(&mut self, text: impl Into<String>) {
+        self.set(Level::Warning, text);
+    }
+
+    pub fn error(&mut self, text: impl Into<String>) {
+        self.set(Level::Error, text);
+    }
+
+    pub fn clear(&mut self) {
+        self.current = None;
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        let Some(n) = &self.current else { return };
+        let elapsed = n.created.elapsed();
+        if elapsed >= STATUS_LIFETIME {
+            self.current = None;
+            return;
+        }
+        ui.colored_label(n.level.color(), &n.text);
+        ui.ctx().request_repaint_after(STATUS_LIFETIME - elapsed);
+    }
+}
@@ -0,0 +1,42 @@
+//! 全局快捷键。文本框聚焦时 F5 与 Ctrl+L 不触发，避免打字时误操作；
+//! Ctrl+C 只在推流密钥输入框聚焦时接管，其余地方保持系统默认的复制行为。
+
+use eframe::egui::{self, Key, KeyboardShortcut, Modifiers};
+
+/// 保存直播间设置
+const SAVE: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::Enter);
+/// 重新获取用户、直播间与分区信息（扫码页为刷新二维码）
+const REFRESH: KeyboardShortcut = KeyboardShortcut::new(Modifiers::NONE, Key::F5);
+/// 开播 / 关播，需要再确认一次
+const TOGGLE_LIVE: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::L);
+
+/// 关于页展示的快捷键列表
+pub const LIST: [(&str, &str); 4] = [
+    ("Ctrl+Enter", "保存直播间设置"),
+    ("F5", "刷新用户、直播间与分区信息 / 刷新登录二维码"),
+    ("Ctrl+L", "开播 / 关播（需确认）"),
+    ("Ctrl+C", "焦点在推流地址或密钥上时复制完整内容"),
+];
+
+/// 本帧按下的快捷键
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Pressed {
+    pub save: bool,
+    pub refresh: bool,
+    pub toggle_live: bool,
+}
+
+/// 读取并消费本帧的快捷键
+pub fn read(ctx: &egui::Context) -> Pressed {
+    let typing = ctx.wants_keyboard_input();
+    ctx.input_mut(|i| Pressed {
+        save: i.consume_shortcut(&SAVE),
+        refresh: !typing && i.consume_shortcut(&REFRESH),
+        toggle_live: !typing && i.consume_shortcut(&TOGGLE_LIVE),
+    })
+}
+
+/// 本帧是否触发了复制（Ctrl+C 会被转换成复制事件，不再是按键事件）
+pub fn copy_requested(ctx: &egui::Context) -> bool {
+    ctx.input(|i| i.events.iter().any(|e| matches!(e, egui::Event::Copy)))
+}